@@ -4,7 +4,7 @@
 -- Create the trica_packages table
 CREATE TABLE IF NOT EXISTS trica_packages (
     id SERIAL PRIMARY KEY,
-    name VARCHAR(255) UNIQUE NOT NULL,
+    name VARCHAR(255) NOT NULL,
     version VARCHAR(50) NOT NULL,
     description TEXT NOT NULL,
     author VARCHAR(255) NOT NULL,
@@ -19,7 +19,9 @@ CREATE TABLE IF NOT EXISTS trica_packages (
     repository_url VARCHAR(500),
     homepage_url VARCHAR(500),
     is_featured BOOLEAN DEFAULT FALSE,
-    is_deprecated BOOLEAN DEFAULT FALSE
+    is_deprecated BOOLEAN DEFAULT FALSE,
+    -- Every published version is its own row
+    UNIQUE (name, version)
 );
 
 -- Create indexes for better performance
@@ -5,6 +5,18 @@ pub struct Program {
     pub includes: Vec<Include>,
     pub imports: Vec<Import>,
     pub statements: Vec<Statement>,  // DIRECT STATEMENTS - NO MAIN BLOCK!
+    pub legacy_main: Option<LegacyMain>,
+}
+
+/// Where a legacy `Main { }` block sat in the source. Its statements were
+/// flattened into `Program::statements`, starting at `first_statement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyMain {
+    pub first_statement: usize,
+    pub line: usize,
+    pub column: usize,
+    /// Line of the closing `}`
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,6 +40,7 @@ pub struct MainBlock {
     pub statements: Vec<Statement>,
     pub line: usize,
     pub column: usize,
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,6 +61,50 @@ pub enum Statement {
         line: usize,
         column: usize,
     },
+    /// `if c { } else if d { } else { }`; one branch per condition
+    If {
+        branches: Vec<(Expression, Block)>,
+        else_block: Option<Block>,
+        line: usize,
+        column: usize,
+    },
+    While {
+        condition: Expression,
+        body: Block,
+        line: usize,
+        column: usize,
+    },
+    /// `for variable in iterable { }`; the variable belongs to the body's
+    /// scope unless it names a visible variable. A list gives its elements,
+    /// a map its keys, or with `for key, value in map` both.
+    For {
+        variable: String,
+        value: Option<String>,
+        iterable: Expression,
+        body: Block,
+        line: usize,
+        column: usize,
+    },
+    /// `object[index] = value`
+    IndexAssignment {
+        object: Expression,
+        index: Expression,
+        value: Expression,
+        line: usize,
+        column: usize,
+    },
+}
+
+/// Statements between `{` and `}`. Variables first assigned inside a block
+/// are local to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    /// Position of the opening `{`
+    pub line: usize,
+    pub column: usize,
+    /// Line of the closing `}`
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -57,11 +114,21 @@ pub enum Expression {
         line: usize,
         column: usize,
     },
-    NumberLiteral {
+    IntLiteral {
+        value: i64,
+        line: usize,
+        column: usize,
+    },
+    FloatLiteral {
         value: f64,
         line: usize,
         column: usize,
     },
+    BooleanLiteral {
+        value: bool,
+        line: usize,
+        column: usize,
+    },
     Identifier {
         name: String,
         line: usize,
@@ -86,6 +153,65 @@ pub enum Expression {
         line: usize,
         column: usize,
     },
+    /// `"Hello ${name}"`: the parts are concatenated as `Print` shows them
+    Interpolation {
+        parts: Vec<InterpolationPart>,
+        line: usize,
+        column: usize,
+    },
+    ListLiteral {
+        elements: Vec<Expression>,
+        line: usize,
+        column: usize,
+        /// Line of the `]`
+        end_line: usize,
+    },
+    /// `{ key: value, ... }`
+    MapLiteral {
+        entries: Vec<(Expression, Expression)>,
+        line: usize,
+        column: usize,
+        /// Line of the `}`
+        end_line: usize,
+    },
+    /// `object[index]`, a list element or a map value
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+        line: usize,
+        column: usize,
+    },
+    /// `object[start:end]`; a missing bound is the start or end of the list
+    Slice {
+        object: Box<Expression>,
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+        line: usize,
+        column: usize,
+    },
+    /// `receiver.method(args)` on a value, e.g. `xs.push(4)`. Calls on
+    /// imported modules (`In.input(...)`) are `FunctionCall`s.
+    MethodCall {
+        receiver: Box<Expression>,
+        method: String,
+        args: Vec<Expression>,
+        line: usize,
+        column: usize,
+    },
+    /// Positioned at the operator
+    UnaryOp {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+        line: usize,
+        column: usize,
+    },
+}
+
+/// A piece of an `Interpolation`: literal text, or an embedded `${expression}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Text(String),
+    Expression(Expression),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,6 +220,9 @@ pub enum BinaryOperator {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    /// `**`, right-associative
+    Power,
     Equal,
     NotEqual,
     Less,
@@ -102,35 +231,116 @@ pub enum BinaryOperator {
     GreaterEqual,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnaryOperator {
+    /// `not x` or `!x`
+    Not,
+    /// `-x`
+    Negate,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     String,
-    Number,
+    Int,
+    Float,
     Boolean,
+    /// Elements all of one type; `List<Unknown>` for `[]`
+    List(Box<Type>),
+    /// Keys and values each of one type; keys are Strings, Ints or Booleans
+    Map(Box<Type>, Box<Type>),
     Void,
     Unknown,
 }
 
+impl Type {
+    /// Whether a value of type `other` can be used where `self` is
+    /// expected. `Unknown` fits anywhere, including inside a `List`.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::List(a), Type::List(b)) => a.accepts(b),
+            (Type::Map(k1, v1), Type::Map(k2, v2)) => k1.accepts(k2) && v1.accepts(v2),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::List(element) => write!(f, "List<{}>", element),
+            Type::Map(key, value) => write!(f, "Map<{}, {}>", key, value),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl Program {
+    /// Top-level statements, and the statements of the legacy `Main { }`
+    /// block if there is one. `Main` is a block: its variables are local.
+    pub fn split_main(&self) -> (&[Statement], Option<&[Statement]>) {
+        match &self.legacy_main {
+            Some(main) => {
+                let (top, main) = self.statements.split_at(main.first_statement);
+                (top, Some(main))
+            }
+            None => (&self.statements, None),
+        }
+    }
+}
+
+impl Statement {
+    /// Blocks nested directly in this statement, in source order
+    pub fn blocks(&self) -> Vec<&Block> {
+        match self {
+            Statement::If { branches, else_block, .. } => {
+                branches.iter().map(|(_, block)| block).chain(else_block).collect()
+            }
+            Statement::While { body, .. } | Statement::For { body, .. } => vec![body],
+            _ => Vec::new(),
+        }
+    }
+}
+
 impl Expression {
     pub fn line(&self) -> usize {
         match self {
             Expression::StringLiteral { line, .. } => *line,
-            Expression::NumberLiteral { line, .. } => *line,
+            Expression::IntLiteral { line, .. } => *line,
+            Expression::FloatLiteral { line, .. } => *line,
+            Expression::BooleanLiteral { line, .. } => *line,
             Expression::Identifier { line, .. } => *line,
             Expression::PropertyAccess { line, .. } => *line,
             Expression::FunctionCall { line, .. } => *line,
             Expression::BinaryOp { line, .. } => *line,
+            Expression::UnaryOp { line, .. } => *line,
+            Expression::Interpolation { line, .. } => *line,
+            Expression::ListLiteral { line, .. } => *line,
+            Expression::MapLiteral { line, .. } => *line,
+            Expression::Index { line, .. } => *line,
+            Expression::Slice { line, .. } => *line,
+            Expression::MethodCall { line, .. } => *line,
         }
     }
     
     pub fn column(&self) -> usize {
         match self {
             Expression::StringLiteral { column, .. } => *column,
-            Expression::NumberLiteral { column, .. } => *column,
+            Expression::IntLiteral { column, .. } => *column,
+            Expression::FloatLiteral { column, .. } => *column,
+            Expression::BooleanLiteral { column, .. } => *column,
             Expression::Identifier { column, .. } => *column,
             Expression::PropertyAccess { column, .. } => *column,
             Expression::FunctionCall { column, .. } => *column,
             Expression::BinaryOp { column, .. } => *column,
+            Expression::UnaryOp { column, .. } => *column,
+            Expression::Interpolation { column, .. } => *column,
+            Expression::ListLiteral { column, .. } => *column,
+            Expression::MapLiteral { column, .. } => *column,
+            Expression::Index { column, .. } => *column,
+            Expression::Slice { column, .. } => *column,
+            Expression::MethodCall { column, .. } => *column,
         }
     }
 }
\ No newline at end of file
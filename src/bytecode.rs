@@ -1,361 +1,439 @@
 // 🔥 TRICA BYTECODE VM - LEGENDARY EXECUTION ENGINE 🔥
 // This VM executes Trica bytecode at impossible speeds
 
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::rc::Rc;
+use indexmap::IndexMap;
+use string_interner::{DefaultStringInterner, DefaultSymbol};
 use crate::ast::{*, BinaryOperator};
+use crate::capabilities::Capabilities;
 use crate::error::TricarError;
+use crate::random::Rng;
+use crate::clock::{Clock, SystemClock};
+use crate::natives::{self, Native};
+use crate::resolver::{resolve, LoopSlots, Resolution, Slot};
 
 /// Trica Bytecode Instructions - Each one bends reality
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     // Basic operations
-    LoadString(String),           // Load string literal onto stack
-    LoadNumber(f64),             // Load number onto stack
+    LoadConst(u32),              // Load constant pool entry onto stack
     Print,                       // Print top of stack (destroys minds)
     Pop,                         // Remove top of stack
     
     // String operations
     Concat,                      // Concatenate two strings (quantum entanglement)
+    ConcatMany(u32),             // Pop that many values, push their text joined (interpolation)
     
     // Math operations (LEGENDARY CALCULATIONS)
     Add,                         // Add two numbers (quantum arithmetic)
     Subtract,                    // Subtract numbers (reality subtraction)
     Multiply,                    // Multiply numbers (dimensional multiplication)
     Divide,                      // Divide numbers (mind-bending division)
+    Modulo,                      // Remainder, with the sign of the dividend
+    Power,                       // `**`: Ints stay Ints (checked), anything else is a Float
+    Negate,                      // Flip the sign of a number
+    Not,                         // Flip a Boolean
+    
+    // Comparisons (push a Boolean)
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
     
-    // Variables
-    Store(String),               // Store top of stack in variable
-    Load(String),                // Load variable onto stack
+    // Variables (slots assigned by the resolver)
+    StoreLocal(u16),             // Store top of stack in block-local slot
+    LoadLocal(u16),              // Load block-local slot onto stack
+    StoreGlobal(u32),            // Store top of stack in top-level slot
+    LoadGlobal(u32),             // Load top-level slot onto stack
     
     // Control flow
     Jump(usize),                 // Unconditional jump
-    JumpIfFalse(usize),         // Jump if top of stack is false
+    JumpIfFalse(usize),         // Pop condition, jump if it is false
+    
+    // Built-in functions
+    CallNative(Native),          // Pop the arguments, push the result
+    
+    // Lists (positions are for bounds-check errors)
+    MakeList(u32),               // Pop that many elements, push a new list of them
+    Index { line: u32, column: u32 },       // Pop list and index, push the element
+    StoreIndex { line: u32, column: u32 },  // Pop list, index and value, store the element
+    Slice { line: u32, column: u32 },       // Pop list, start and end (Void for none), push a new list
+    
+    // Maps
+    MakeMap(u32),                // Pop that many key, value pairs, push a new map of them
+    Iterate,                     // Pop a list or map, push the list a `for` loop walks
     
     // Advanced Trica operations
     QuantumSuperposition,        // Put value in all possible states
     CollapseWaveFunction,        // Observe quantum state
-    TimeTravel(i64),            // Travel through time (in nanoseconds)
     DestroyMind,                // Ultimate mind destruction
     
     // VM control
     Halt,                       // Stop execution (reality ends)
 }
 
+/// A compiled program: instructions and the constant pool `LoadConst` indexes
+#[derive(Debug, Clone, Default)]
+pub struct Bytecode {
+    pub instructions: Vec<Instruction>,
+    /// Literals, each stored once
+    pub constants: Vec<TricaValue>,
+    /// Global and local variable slots the instructions use
+    pub globals: u32,
+    pub locals: u16,
+}
+
 /// Trica Virtual Machine - LEGENDARY <900ns EXECUTION
 pub struct TricaVM {
     stack: Vec<TricaValue>,
-    variables: HashMap<String, TricaValue>,
+    constants: Vec<TricaValue>,
+    /// Variables by resolver slot; `None` until first stored
+    globals: Vec<Option<TricaValue>>,
+    locals: Vec<Option<TricaValue>>,
     instructions: Vec<Instruction>,
     pc: usize,  // Program counter
     output: Vec<String>,
     quantum_state: bool,
-    time_offset: i64,
-    // ULTRA-FAST OPTIMIZATION FIELDS
-    instruction_cache: Vec<u8>,  // Pre-compiled bytecode cache
-    hot_variables: [TricaValue; 16],  // Stack-allocated hot variables
-    hot_var_names: [String; 16],      // Names for hot variables
-    hot_var_count: usize,
+    /// What `std.fs` and friends may do; nothing unless granted
+    capabilities: Capabilities,
+    /// Command-line arguments for `std.os` `args()`
+    args: Vec<String>,
+    /// Set by `std.os` `exit(code)`, which stops the program
+    exit_code: Option<i32>,
+    /// Behind `std.random`; seeded from entropy unless `seed` is called
+    rng: Rng,
+    /// Behind `std.time`; the system clock unless replaced
+    clock: Box<dyn Clock>,
 }
 
 /// Trica Values - Can exist in multiple states simultaneously
 #[derive(Debug, Clone)]
 pub enum TricaValue {
-    String(String),
-    Number(f64),
+    String(Rc<str>),  // Shared, so loading a constant is a pointer copy
+    Int(i64),         // Overflow is an error, never a wrap
+    Float(f64),
     Boolean(bool),
     Quantum(Vec<TricaValue>),  // Superposition of values
+    List(Rc<RefCell<Vec<TricaValue>>>),  // Shared and mutable: copies alias one list
+    Map(Rc<RefCell<TricaMap>>),          // Shared like lists, in insertion order
     Void,
 }
 
+/// Map entries in insertion order, hashed with ahash
+pub type TricaMap = IndexMap<MapKey, TricaValue, ahash::RandomState>;
+
+/// The values that can be map keys
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    String(Rc<str>),
+    Int(i64),
+    Boolean(bool),
+}
+
+impl MapKey {
+    pub fn new(value: &TricaValue) -> Result<Self, TricarError> {
+        match value {
+            TricaValue::String(s) => Ok(MapKey::String(s.clone())),
+            TricaValue::Int(i) => Ok(MapKey::Int(*i)),
+            TricaValue::Boolean(b) => Ok(MapKey::Boolean(*b)),
+            other => Err(TricarError::UnsupportedOperation(format!("{:?} as a map key", other))),
+        }
+    }
+    
+    pub fn value(&self) -> TricaValue {
+        match self {
+            MapKey::String(s) => TricaValue::String(s.clone()),
+            MapKey::Int(i) => TricaValue::Int(*i),
+            MapKey::Boolean(b) => TricaValue::Boolean(*b),
+        }
+    }
+}
+
+impl TricaValue {
+    pub fn new_list(elements: Vec<TricaValue>) -> Self {
+        TricaValue::List(Rc::new(RefCell::new(elements)))
+    }
+}
+
+impl Default for TricaVM {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TricaVM {
     pub fn new() -> Self {
         Self {
             stack: Vec::with_capacity(256),  // Pre-allocate for speed
-            variables: HashMap::with_capacity(64),
+            constants: Vec::new(),
+            globals: Vec::with_capacity(16),
+            locals: Vec::with_capacity(16),
             instructions: Vec::new(),
             pc: 0,
             output: Vec::with_capacity(32),
             quantum_state: false,
-            time_offset: 0,
-            // ULTRA-FAST OPTIMIZATION INITIALIZATION
-            instruction_cache: Vec::with_capacity(1024),
-            hot_variables: [
-                TricaValue::Void, TricaValue::Void, TricaValue::Void, TricaValue::Void,
-                TricaValue::Void, TricaValue::Void, TricaValue::Void, TricaValue::Void,
-                TricaValue::Void, TricaValue::Void, TricaValue::Void, TricaValue::Void,
-                TricaValue::Void, TricaValue::Void, TricaValue::Void, TricaValue::Void,
-            ],
-            hot_var_names: [
-                String::new(), String::new(), String::new(), String::new(),
-                String::new(), String::new(), String::new(), String::new(),
-                String::new(), String::new(), String::new(), String::new(),
-                String::new(), String::new(), String::new(), String::new(),
-            ],
-            hot_var_count: 0,
+            capabilities: Capabilities::none(),
+            args: Vec::new(),
+            exit_code: None,
+            rng: Rng::from_entropy(),
+            clock: Box::new(SystemClock::new()),
         }
     }
     
-    /// Load bytecode into the VM
-    pub fn load_bytecode(&mut self, instructions: Vec<Instruction>) {
-        self.instructions = instructions;
+    /// VM whose scripts may do what `capabilities` allows
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        Self { capabilities, ..Self::new() }
+    }
+    
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+    
+    /// Arguments the script sees through `std.os` `args()`
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+    
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+    
+    /// Stop the program after the current instruction, with exit status `code`
+    pub fn exit(&mut self, code: i32) {
+        self.exit_code = Some(code);
+    }
+    
+    /// The status passed to `std.os` `exit`, if the program called it
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+    
+    /// Make `std.random` produce the same numbers on every run
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+    
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    
+    /// Run `std.time` on `clock`, e.g. a `VirtualClock` in tests
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+    
+    pub fn clock(&mut self) -> &mut dyn Clock {
+        self.clock.as_mut()
+    }
+    
+    /// Load bytecode into the VM. Compiler output can be loaded as is;
+    /// anything else should go through `verifier::verify` first.
+    pub fn load_bytecode(&mut self, bytecode: Bytecode) {
+        self.instructions = bytecode.instructions;
+        self.constants = bytecode.constants;
+        self.globals = vec![None; bytecode.globals as usize];
+        self.locals = vec![None; bytecode.locals as usize];
         self.pc = 0;
+        self.exit_code = None;
     }
     
-    /// Execute bytecode at LEGENDARY <900ns speed
+    /// Execute bytecode at LEGENDARY <900ns speed. Optimization happens at
+    /// compile time, see `optimizer::optimize`.
     #[inline(always)]
     pub fn execute(&mut self) -> Result<(), TricarError> {
-        // ULTRA-FAST EXECUTION - NO OUTPUT FOR <900ns SPEED
-        
-        // Pre-compile to optimized bytecode for maximum speed
-        self.optimize_instructions();
-        
         // SILENT MODE - No debug output for maximum speed
-        return self.execute_silent();
-        
-        while self.pc < self.instructions.len() {
-            // UNSAFE: Maximum performance, bounds checking removed
-            let instruction = unsafe { self.instructions.get_unchecked(self.pc).clone() };
-            
+        self.execute_silent()
+    }
+    
+    /// LEGENDARY <900ns EXECUTION - MAXIMUM OPTIMIZATION!
+    #[inline(always)]
+    fn execute_silent(&mut self) -> Result<(), TricarError> {
+        // ULTRA-FAST LOOP - INSTRUCTIONS ARE COPY, NO CLONING
+        while let Some(&instruction) = self.instructions.get(self.pc) {
+            // BLAZING FAST MATCH - NO ALLOCATION
             match instruction {
-                Instruction::LoadString(s) => {
-                    self.stack.push(TricaValue::String(s));
-                }
-                
-                Instruction::LoadNumber(n) => {
-                    self.stack.push(TricaValue::Number(n));
+                Instruction::LoadConst(index) => {
+                    // ULTRA-FAST CONSTANT PUSH - STRINGS ARE A POINTER COPY
+                    let value = self.constants.get(index as usize)
+                        .ok_or_else(|| TricarError::InvalidBytecode(format!("constant {} is out of range", index)))?;
+                    self.stack.push(value.clone());
                 }
                 
                 Instruction::Print => {
-                    if let Some(value) = self.stack.pop() {
-                        let output = self.format_value_fast(&value);
-                        // NO PRINTLN IN HOT PATH - STORE FOR LATER
-                        self.output.push(output);
-                    }
+                    // LEGENDARY FAST PRINT - NO FORMAT OVERHEAD
+                    let value = self.pop()?;
+                    let output = self.format_value_fast(&value);
+                    self.output.push(output);
                 }
                 
                 Instruction::Pop => {
-                    self.stack.pop();
+                    // ULTRA-FAST POP
+                    self.pop()?;
                 }
                 
-                Instruction::Concat => {
-                    if let (Some(b), Some(a)) = (self.stack.pop(), self.stack.pop()) {
-                        let result = format!("{}{}", 
-                            self.format_value(&a), 
-                            self.format_value(&b)
-                        );
-                        self.stack.push(TricaValue::String(result));
-                    }
+                // LEGENDARY FAST MATH, CONCATENATION AND COMPARISONS
+                Instruction::Concat | Instruction::Add | Instruction::Subtract |
+                Instruction::Multiply | Instruction::Divide | Instruction::Modulo | Instruction::Power |
+                Instruction::Equal | Instruction::NotEqual | Instruction::Less |
+                Instruction::Greater | Instruction::LessEqual | Instruction::GreaterEqual => {
+                    let (a, b) = self.pop_pair()?;
+                    self.stack.push(binary_operation(&instruction, a, b)?);
                 }
                 
-                Instruction::Add => {
-                    if let (Some(b), Some(a)) = (self.stack.pop(), self.stack.pop()) {
-                        match (a, b) {
-                            (TricaValue::Number(x), TricaValue::Number(y)) => {
-                                println!("🧮 QUANTUM ARITHMETIC: {} + {} = {}", x, y, x + y);
-                                self.stack.push(TricaValue::Number(x + y));
-                            }
-                            (a, b) => {
-                                // Fallback to string concatenation
-                                let result = format!("{}{}", 
-                                    self.format_value(&a), 
-                                    self.format_value(&b)
-                                );
-                                self.stack.push(TricaValue::String(result));
-                            }
-                        }
-                    }
+                Instruction::ConcatMany(count) => {
+                    let parts = self.pop_many(count as usize)?;
+                    self.stack.push(concat_values(&parts));
                 }
                 
-                Instruction::Subtract => {
-                    if let (Some(b), Some(a)) = (self.stack.pop(), self.stack.pop()) {
-                        if let (TricaValue::Number(x), TricaValue::Number(y)) = (a, b) {
-                            println!("➖ REALITY SUBTRACTION: {} - {} = {}", x, y, x - y);
-                            self.stack.push(TricaValue::Number(x - y));
-                        }
-                    }
+                Instruction::Negate | Instruction::Not => {
+                    let value = self.pop()?;
+                    self.stack.push(unary_operation(&instruction, value)?);
                 }
                 
-                Instruction::Multiply => {
-                    if let (Some(b), Some(a)) = (self.stack.pop(), self.stack.pop()) {
-                        if let (TricaValue::Number(x), TricaValue::Number(y)) = (a, b) {
-                            println!("✖️ DIMENSIONAL MULTIPLICATION: {} × {} = {}", x, y, x * y);
-                            self.stack.push(TricaValue::Number(x * y));
-                        }
+                Instruction::CallNative(native) => {
+                    let args = self.pop_many(native.arity())?;
+                    let result = native.call(args, self)?;
+                    self.stack.push(result);
+                    // `exit` unwinds like a Halt; the output so far stays
+                    if self.exit_code.is_some() {
+                        break;
                     }
                 }
                 
-                Instruction::Divide => {
-                    if let (Some(b), Some(a)) = (self.stack.pop(), self.stack.pop()) {
-                        if let (TricaValue::Number(x), TricaValue::Number(y)) = (a, b) {
-                            if y != 0.0 {
-                                println!("➗ MIND-BENDING DIVISION: {} ÷ {} = {}", x, y, x / y);
-                                self.stack.push(TricaValue::Number(x / y));
-                            } else {
-                                println!("💥 DIVISION BY ZERO - REALITY COLLAPSE!");
-                                self.stack.push(TricaValue::Number(f64::INFINITY));
-                            }
-                        }
-                    }
+                Instruction::MakeList(count) => {
+                    let elements = self.pop_many(count as usize)?;
+                    self.stack.push(TricaValue::new_list(elements));
                 }
                 
-                Instruction::Store(name) => {
-                    if let Some(value) = self.stack.pop() {
-                        self.store_variable_fast(&name, value);
+                Instruction::MakeMap(count) => {
+                    let entries = self.pop_many(count as usize * 2)?;
+                    let mut map = TricaMap::default();
+                    for pair in entries.chunks_exact(2) {
+                        map.insert(MapKey::new(&pair[0])?, pair[1].clone());
                     }
+                    self.stack.push(TricaValue::Map(Rc::new(RefCell::new(map))));
                 }
                 
-                Instruction::Load(name) => {
-                    if let Some(value) = self.load_variable_fast(&name) {
-                        self.stack.push(value);
-                    } else {
-                        return Err(TricarError::VMUndefinedVariable(name));
-                    }
+                Instruction::Index { line, column } => {
+                    let (container, index) = self.pop_pair()?;
+                    let element = match &container {
+                        TricaValue::Map(map) => map.borrow().get(&MapKey::new(&index)?).cloned()
+                            .ok_or_else(|| key_not_found(&index, line, column))?,
+                        _ => {
+                            let list = as_list(&container)?;
+                            let list = list.borrow();
+                            list[list_index(list.len(), &index, line, column)?].clone()
+                        }
+                    };
+                    self.stack.push(element);
                 }
                 
-                Instruction::QuantumSuperposition => {
-                    if let Some(value) = self.stack.pop() {
-                        // Put value in quantum superposition
-                        let quantum_value = TricaValue::Quantum(vec![
-                            value.clone(),
-                            TricaValue::Void,
-                            value,
-                        ]);
-                        self.stack.push(quantum_value);
-                        self.quantum_state = true;
-                        println!("⚛️  QUANTUM SUPERPOSITION ACTIVATED");
+                Instruction::StoreIndex { line, column } => {
+                    let value = self.pop()?;
+                    let (container, index) = self.pop_pair()?;
+                    if let TricaValue::Map(map) = &container {
+                        map.borrow_mut().insert(MapKey::new(&index)?, value);
+                    } else {
+                        let list = as_list(&container)?;
+                        let mut list = list.borrow_mut();
+                        let index = list_index(list.len(), &index, line, column)?;
+                        list[index] = value;
                     }
                 }
                 
-                Instruction::CollapseWaveFunction => {
-                    if let Some(TricaValue::Quantum(states)) = self.stack.pop() {
-                        // Collapse to first non-void state
-                        let collapsed = states.into_iter()
-                            .find(|v| !matches!(v, TricaValue::Void))
-                            .unwrap_or(TricaValue::Void);
-                        self.stack.push(collapsed);
-                        self.quantum_state = false;
-                        println!("🌊 WAVE FUNCTION COLLAPSED");
-                    }
+                Instruction::Iterate => {
+                    // The list, or a map's keys, is copied, so the loop body can
+                    // change what it iterates over
+                    let iterable = self.pop()?;
+                    self.stack.push(match &iterable {
+                        TricaValue::Map(map) => TricaValue::new_list(map.borrow().keys().map(MapKey::value).collect()),
+                        TricaValue::List(list) => TricaValue::new_list(list.borrow().clone()),
+                        other => return Err(TricarError::UnsupportedOperation(format!("iterating over {:?}", other))),
+                    });
                 }
                 
-                Instruction::TimeTravel(offset) => {
-                    self.time_offset += offset;
-                    println!("⏰ TIME TRAVEL: {}ns offset", offset);
+                Instruction::Slice { line, column } => {
+                    let end = self.pop()?;
+                    let (list, start) = self.pop_pair()?;
+                    let list = as_list(&list)?;
+                    let list = list.borrow();
+                    let bound = |value: &TricaValue, default: usize| match value {
+                        TricaValue::Void => Ok(default),
+                        // The end of the list is a valid bound
+                        value => list_index(list.len() + 1, value, line, column)
+                            .map_err(|_| out_of_bounds(value, list.len(), line, column)),
+                    };
+                    let start = bound(&start, 0)?;
+                    let end = bound(&end, list.len())?;
+                    let elements = list.get(start..end).unwrap_or_default().to_vec();
+                    self.stack.push(TricaValue::new_list(elements));
                 }
                 
-                Instruction::DestroyMind => {
-                    println!("🧠💥 MIND DESTRUCTION COMPLETE 💥🧠");
-                    println!("🌀 REALITY HAS BEEN FUNDAMENTALLY ALTERED 🌀");
+                // ULTRA-FAST VARIABLES - DIRECT SLOT INDEX, NO NAME LOOKUP
+                Instruction::StoreLocal(slot) => {
+                    let value = self.pop()?;
+                    store_slot(&mut self.locals, slot as usize, value);
                 }
                 
-                Instruction::Jump(addr) => {
-                    self.pc = addr;
-                    continue;
+                Instruction::LoadLocal(slot) => {
+                    let value = load_slot(&self.locals, slot as usize, "local")?;
+                    self.stack.push(value);
                 }
                 
-                Instruction::JumpIfFalse(addr) => {
-                    if let Some(TricaValue::Boolean(false)) = self.stack.last() {
-                        self.pc = addr;
-                        continue;
-                    }
+                Instruction::StoreGlobal(slot) => {
+                    let value = self.pop()?;
+                    store_slot(&mut self.globals, slot as usize, value);
                 }
                 
-                Instruction::Halt => {
-                    println!("🔥 TRICA VM HALTED - REALITY RESTORED 🔥");
-                    break;
-                }
-            }
-            
-            self.pc += 1;
-        }
-        
-        Ok(())
-    }
-    
-    /// LEGENDARY <900ns EXECUTION - MAXIMUM OPTIMIZATION!
-    #[inline(always)]
-    fn execute_silent(&mut self) -> Result<(), TricarError> {
-        // ULTRA-FAST LOOP - NO BOUNDS CHECKING, NO CLONING
-        let instructions_ptr = self.instructions.as_ptr();
-        let instructions_len = self.instructions.len();
-        
-        while self.pc < instructions_len {
-            let instruction = unsafe { &*instructions_ptr.add(self.pc) };
-            
-            // BLAZING FAST MATCH - NO ALLOCATION
-            match instruction {
-                Instruction::LoadString(s) => {
-                    // FAST STRING PUSH - PRE-ALLOCATED
-                    unsafe {
-                        self.stack.push(TricaValue::String(s.clone()));
-                    }
+                Instruction::LoadGlobal(slot) => {
+                    let value = load_slot(&self.globals, slot as usize, "global")?;
+                    self.stack.push(value);
                 }
                 
-                Instruction::LoadNumber(n) => {
-                    // ULTRA-FAST NUMBER PUSH
-                    unsafe {
-                        self.stack.push(TricaValue::Number(*n));
-                    }
+                Instruction::Jump(target) => {
+                    self.pc = target;
+                    continue;
                 }
                 
-                Instruction::Print => {
-                    // LEGENDARY FAST PRINT - NO FORMAT OVERHEAD
-                    if let Some(value) = self.stack.pop() {
-                        match value {
-                            TricaValue::String(s) => self.output.push(s),
-                            TricaValue::Number(n) => {
-                                // ULTRA-FAST NUMBER TO STRING
-                                if n.fract() == 0.0 && n >= 0.0 && n < 1000.0 {
-                                    self.output.push((n as i32).to_string());
-                                } else {
-                                    self.output.push(n.to_string());
-                                }
-                            }
-                            _ => self.output.push("QUANTUM".to_string()),
-                        }
+                Instruction::JumpIfFalse(target) => {
+                    if let TricaValue::Boolean(false) = self.pop()? {
+                        self.pc = target;
+                        continue;
                     }
                 }
                 
-                Instruction::Pop => {
-                    // ULTRA-FAST POP
-                    unsafe { self.stack.pop(); }
-                }
-                
-                Instruction::Add => {
-                    // LEGENDARY FAST ADD - NO ALLOCATION
-                    if self.stack.len() >= 2 {
-                        let b = unsafe { self.stack.pop().unwrap_unchecked() };
-                        let a = unsafe { self.stack.pop().unwrap_unchecked() };
-                        match (a, b) {
-                            (TricaValue::Number(x), TricaValue::Number(y)) => {
-                                self.stack.push(TricaValue::Number(x + y));
-                            }
-                            _ => {
-                                // FAST STRING CONCAT - NO FORMAT MACRO
-                                self.stack.push(TricaValue::String("CONCAT".to_string()));
-                            }
-                        }
-                    }
+                Instruction::QuantumSuperposition => {
+                    // Put value in quantum superposition
+                    let value = self.pop()?;
+                    self.stack.push(TricaValue::Quantum(vec![value.clone(), TricaValue::Void, value]));
+                    self.quantum_state = true;
                 }
                 
-                Instruction::Store(_) => {
-                    // ULTRA-FAST STORE - SKIP VARIABLE STORAGE FOR SPEED
-                    self.stack.pop();
+                Instruction::CollapseWaveFunction => {
+                    // Collapse to first non-void state; anything else is already observed
+                    let collapsed = match self.pop()? {
+                        TricaValue::Quantum(states) => states.into_iter()
+                            .find(|v| !matches!(v, TricaValue::Void))
+                            .unwrap_or(TricaValue::Void),
+                        value => value,
+                    };
+                    self.stack.push(collapsed);
+                    self.quantum_state = false;
                 }
                 
-                Instruction::Load(_) => {
-                    // ULTRA-FAST LOAD - PUSH ZERO FOR SPEED
-                    self.stack.push(TricaValue::Number(0.0));
+                Instruction::DestroyMind => {
+                    // Minds are destroyed silently in fast mode
                 }
                 
                 Instruction::Halt => {
                     // LEGENDARY FAST HALT
                     break;
                 }
-                
-                _ => {
-                    // ULTRA-FAST SKIP - NO PROCESSING
-                }
             }
             
             // BLAZING FAST PC INCREMENT
@@ -365,21 +443,24 @@ impl TricaVM {
         Ok(())
     }
     
-
+    #[inline(always)]
+    fn pop(&mut self) -> Result<TricaValue, TricarError> {
+        self.stack.pop().ok_or(TricarError::StackUnderflow)
+    }
     
-    fn format_value(&self, value: &TricaValue) -> String {
-        match value {
-            TricaValue::String(s) => s.clone(),
-            TricaValue::Number(n) => n.to_string(),
-            TricaValue::Boolean(b) => b.to_string(),
-            TricaValue::Quantum(states) => {
-                format!("⚛️[{}]", states.iter()
-                    .map(|v| self.format_value(v))
-                    .collect::<Vec<_>>()
-                    .join(" | "))
-            }
-            TricaValue::Void => "∅".to_string(),
-        }
+    /// Pop the top `count` values, deepest first
+    #[inline(always)]
+    fn pop_many(&mut self, count: usize) -> Result<Vec<TricaValue>, TricarError> {
+        let first = self.stack.len().checked_sub(count).ok_or(TricarError::StackUnderflow)?;
+        Ok(self.stack.split_off(first))
+    }
+    
+    /// Pop the two operands of a binary instruction, left operand first
+    #[inline(always)]
+    fn pop_pair(&mut self) -> Result<(TricaValue, TricaValue), TricarError> {
+        let b = self.pop()?;
+        let a = self.pop()?;
+        Ok((a, b))
     }
     
     pub fn get_output(&self) -> &Vec<String> {
@@ -390,120 +471,464 @@ impl TricaVM {
     #[inline(always)]
     fn format_value_fast(&self, value: &TricaValue) -> String {
         match value {
-            TricaValue::String(s) => s.clone(),
-            TricaValue::Number(n) => {
-                // FAST number to string conversion
-                if n.fract() == 0.0 && *n >= 0.0 && *n < 1000000.0 {
-                    // Fast path for small integers
-                    (*n as i64).to_string()
-                } else {
-                    n.to_string()
-                }
-            }
+            TricaValue::String(s) => s.to_string(),
+            TricaValue::Int(i) => i.to_string(),
+            TricaValue::Float(f) => format_float(*f),
             TricaValue::Boolean(b) => {
                 // Avoid allocation for booleans
                 if *b { "true".to_string() } else { "false".to_string() }
             }
             TricaValue::Quantum(_) => "⚛️QUANTUM".to_string(),
+            TricaValue::List(_) | TricaValue::Map(_) => format_value(value),
             TricaValue::Void => "∅".to_string(),
         }
     }
+}
+
+/// The list inside a value, for the list instructions
+fn as_list(value: &TricaValue) -> Result<Rc<RefCell<Vec<TricaValue>>>, TricarError> {
+    match value {
+        TricaValue::List(list) => Ok(list.clone()),
+        other => Err(TricarError::UnsupportedOperation(format!("indexing {:?}", other))),
+    }
+}
+
+fn key_not_found(key: &TricaValue, line: u32, column: u32) -> TricarError {
+    TricarError::KeyNotFound {
+        key: format_nested(key, 1),
+        line: line as usize,
+        column: column as usize,
+    }
+}
+
+fn out_of_bounds(index: &TricaValue, length: usize, line: u32, column: u32) -> TricarError {
+    match index {
+        TricaValue::Int(index) => TricarError::IndexOutOfBounds {
+            index: *index,
+            length,
+            line: line as usize,
+            column: column as usize,
+        },
+        other => TricarError::UnsupportedOperation(format!("index {:?}", other)),
+    }
+}
+
+/// Check `index` against a list of `length` elements
+fn list_index(length: usize, index: &TricaValue, line: u32, column: u32) -> Result<usize, TricarError> {
+    match index {
+        TricaValue::Int(i) => usize::try_from(*i).ok()
+            .filter(|&i| i < length)
+            .ok_or_else(|| out_of_bounds(index, length, line, column)),
+        other => Err(out_of_bounds(other, length, line, column)),
+    }
+}
+
+impl Instruction {
+    /// Pops two operands and pushes one result, see `binary_operation`
+    pub fn is_binary(&self) -> bool {
+        matches!(self,
+            Instruction::Concat | Instruction::Add | Instruction::Subtract |
+            Instruction::Multiply | Instruction::Divide | Instruction::Modulo | Instruction::Power |
+            Instruction::Equal | Instruction::NotEqual | Instruction::Less |
+            Instruction::Greater | Instruction::LessEqual | Instruction::GreaterEqual)
+    }
     
-    /// Pre-optimize instructions for maximum speed
-    #[inline(always)]
-    fn optimize_instructions(&mut self) {
-        // Pre-compile common instruction patterns
-        // This runs once and optimizes the entire instruction stream
-        
-        // Example: Convert LoadString + Print sequences to optimized PrintString
-        let mut optimized = Vec::with_capacity(self.instructions.len());
-        let mut i = 0;
-        
-        while i < self.instructions.len() {
-            match (&self.instructions[i], self.instructions.get(i + 1)) {
-                (Instruction::LoadString(s), Some(Instruction::Print)) => {
-                    // Optimize LoadString + Print to direct output
-                    self.output.push(s.clone());
-                    i += 2; // Skip both instructions
-                }
-                _ => {
-                    optimized.push(self.instructions[i].clone());
-                    i += 1;
-                }
-            }
+    /// Pops one operand and pushes one result, see `unary_operation`
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Instruction::Negate | Instruction::Not)
+    }
+    
+    /// How many values the instruction pops, then pushes
+    pub fn stack_effect(&self) -> (usize, usize) {
+        match self {
+            _ if self.is_binary() => (2, 1),
+            _ if self.is_unary() => (1, 1),
+            Instruction::LoadConst(_) | Instruction::LoadLocal(_) | Instruction::LoadGlobal(_) => (0, 1),
+            Instruction::Print | Instruction::Pop | Instruction::StoreLocal(_) |
+            Instruction::StoreGlobal(_) | Instruction::JumpIfFalse(_) => (1, 0),
+            Instruction::QuantumSuperposition | Instruction::CollapseWaveFunction => (1, 1),
+            Instruction::CallNative(native) => (native.arity(), 1),
+            Instruction::MakeList(count) | Instruction::ConcatMany(count) => (*count as usize, 1),
+            Instruction::Index { .. } => (2, 1),
+            Instruction::StoreIndex { .. } => (3, 0),
+            Instruction::Slice { .. } => (3, 1),
+            Instruction::MakeMap(count) => (*count as usize * 2, 1),
+            Instruction::Iterate => (1, 1),
+            _ => (0, 0),
         }
-        
-        self.instructions = optimized;
     }
+}
+
+/// Operands of an arithmetic or comparison instruction. Two Ints stay Ints;
+/// mixing in a Float makes both Floats.
+enum Numbers {
+    Int(i64, i64),
+    Float(f64, f64),
+}
+
+fn numbers(a: &TricaValue, b: &TricaValue) -> Option<Numbers> {
+    match (a, b) {
+        (TricaValue::Int(x), TricaValue::Int(y)) => Some(Numbers::Int(*x, *y)),
+        (TricaValue::Int(x), TricaValue::Float(y)) => Some(Numbers::Float(*x as f64, *y)),
+        (TricaValue::Float(x), TricaValue::Int(y)) => Some(Numbers::Float(*x, *y as f64)),
+        (TricaValue::Float(x), TricaValue::Float(y)) => Some(Numbers::Float(*x, *y)),
+        _ => None,
+    }
+}
+
+/// Result of a binary instruction on its operands. The VM and the
+/// optimizer's constant folding both use this, so they always agree.
+pub fn binary_operation(instruction: &Instruction, a: TricaValue, b: TricaValue) -> Result<TricaValue, TricarError> {
+    let numbers = numbers(&a, &b);
+    let arithmetic = |symbol: &str, int: fn(i64, i64) -> Option<i64>, float: fn(f64, f64) -> f64| match numbers {
+        Some(Numbers::Int(_, 0)) if symbol == "/" || symbol == "%" => {
+            Err(TricarError::ArithmeticError(format!("integer division by zero in {} {} 0", format_value(&a), symbol)))
+        }
+        Some(Numbers::Int(x, y)) => int(x, y).map(TricaValue::Int).ok_or_else(|| {
+            TricarError::ArithmeticError(format!("integer overflow in {} {} {}", x, symbol, y))
+        }),
+        Some(Numbers::Float(x, y)) => Ok(TricaValue::Float(float(x, y))),
+        None => Err(TricarError::UnsupportedOperation(format!("arithmetic on {:?} and {:?}", a, b))),
+    };
+    // Numbers, strings and booleans compare; other values are never equal
+    let ordering = || match (&numbers, &a, &b) {
+        (Some(Numbers::Int(x, y)), _, _) => Some(x.cmp(y)),
+        (Some(Numbers::Float(x, y)), _, _) => x.partial_cmp(y),
+        (None, TricaValue::String(x), TricaValue::String(y)) => Some(x.cmp(y)),
+        (None, TricaValue::Boolean(x), TricaValue::Boolean(y)) => Some(x.cmp(y)),
+        // Lists are equal or not, never ordered
+        (None, TricaValue::List(x), TricaValue::List(y)) => lists_equal(x, y, 0).then_some(Ordering::Equal),
+        (None, TricaValue::Map(x), TricaValue::Map(y)) => maps_equal(x, y, 0).then_some(Ordering::Equal),
+        _ => None,
+    };
+    let concat = || TricaValue::String(format!("{}{}", format_value(&a), format_value(&b)).into());
     
-    /// ULTRA-FAST variable storage using hot cache
-    #[inline(always)]
-    fn store_variable_fast(&mut self, name: &str, value: TricaValue) {
-        // Try to store in hot cache first (stack allocated)
-        for i in 0..self.hot_var_count {
-            if self.hot_var_names[i] == name {
-                self.hot_variables[i] = value;
-                return;
-            }
+    Ok(match instruction {
+        Instruction::Concat => concat(),
+        // Numbers add, anything else concatenates
+        Instruction::Add if numbers.is_some() => arithmetic("+", i64::checked_add, |x, y| x + y)?,
+        Instruction::Add => concat(),
+        Instruction::Subtract => arithmetic("-", i64::checked_sub, |x, y| x - y)?,
+        Instruction::Multiply => arithmetic("*", i64::checked_mul, |x, y| x * y)?,
+        // Ints divide toward zero; Floats follow IEEE 754, so dividing by zero
+        // gives an infinity or NaN
+        Instruction::Divide => arithmetic("/", i64::checked_div, |x, y| x / y)?,
+        Instruction::Modulo => arithmetic("%", i64::checked_rem, |x, y| x % y)?,
+        // An Int to a negative power is a fraction, so only Floats have one
+        Instruction::Power if matches!(numbers, Some(Numbers::Int(_, y)) if y < 0) => {
+            return Err(TricarError::ArithmeticError(format!(
+                "negative Int exponent in {} ** {}; use a Float base", format_value(&a), format_value(&b)
+            )));
         }
-        
-        // Add to hot cache if space available
-        if self.hot_var_count < 16 {
-            self.hot_var_names[self.hot_var_count] = name.to_string();
-            self.hot_variables[self.hot_var_count] = value;
-            self.hot_var_count += 1;
-        } else {
-            // Fallback to HashMap
-            self.variables.insert(name.to_string(), value);
+        Instruction::Power => arithmetic("**", |x, y| x.checked_pow(u32::try_from(y).ok()?), f64::powf)?,
+        Instruction::Equal => TricaValue::Boolean(ordering() == Some(Ordering::Equal)),
+        Instruction::NotEqual => TricaValue::Boolean(ordering() != Some(Ordering::Equal)),
+        Instruction::Less => TricaValue::Boolean(ordering() == Some(Ordering::Less)),
+        Instruction::Greater => TricaValue::Boolean(ordering() == Some(Ordering::Greater)),
+        Instruction::LessEqual => TricaValue::Boolean(matches!(ordering(), Some(Ordering::Less | Ordering::Equal))),
+        Instruction::GreaterEqual => TricaValue::Boolean(matches!(ordering(), Some(Ordering::Greater | Ordering::Equal))),
+        other => return Err(TricarError::UnsupportedOperation(format!("{:?} is not a binary operation", other))),
+    })
+}
+
+/// Lists and maps nested deeper than this compare unequal, and print as
+/// `[...]` or `{...}`; a list can contain itself
+const MAX_LIST_DEPTH: usize = 64;
+
+/// Same length, and every pair of elements `==`
+fn lists_equal(a: &RefCell<Vec<TricaValue>>, b: &RefCell<Vec<TricaValue>>, depth: usize) -> bool {
+    if std::ptr::eq(a, b) {
+        return true;
+    }
+    let (a, b) = (a.borrow(), b.borrow());
+    depth < MAX_LIST_DEPTH && a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| values_equal(x, y, depth))
+}
+
+/// Same keys, each with `==` values; the order of the entries doesn't matter
+fn maps_equal(a: &RefCell<TricaMap>, b: &RefCell<TricaMap>, depth: usize) -> bool {
+    if std::ptr::eq(a, b) {
+        return true;
+    }
+    let (a, b) = (a.borrow(), b.borrow());
+    depth < MAX_LIST_DEPTH && a.len() == b.len()
+        && a.iter().all(|(key, x)| b.get(key).is_some_and(|y| values_equal(x, y, depth)))
+}
+
+/// `==` on two elements of lists or maps `depth` deep
+fn values_equal(x: &TricaValue, y: &TricaValue, depth: usize) -> bool {
+    match (x, y) {
+        (TricaValue::List(x), TricaValue::List(y)) => lists_equal(x, y, depth + 1),
+        (TricaValue::Map(x), TricaValue::Map(y)) => maps_equal(x, y, depth + 1),
+        (x, y) => matches!(binary_operation(&Instruction::Equal, x.clone(), y.clone()), Ok(TricaValue::Boolean(true))),
+    }
+}
+
+/// Result of `ConcatMany` on its operands, deepest first: the text `Print`
+/// would show for each, joined. Shared with the optimizer like `binary_operation`.
+pub fn concat_values(parts: &[TricaValue]) -> TricaValue {
+    let mut text = String::new();
+    for part in parts {
+        match part {
+            TricaValue::String(s) => text.push_str(s),
+            other => text.push_str(&format_value(other)),
         }
     }
-    
-    /// ULTRA-FAST variable loading using hot cache
-    #[inline(always)]
-    fn load_variable_fast(&self, name: &str) -> Option<TricaValue> {
-        // Check hot cache first (stack allocated)
-        for i in 0..self.hot_var_count {
-            if self.hot_var_names[i] == name {
-                return Some(self.hot_variables[i].clone());
-            }
+    TricaValue::String(text.into())
+}
+
+/// Result of a unary instruction on its operand; shared like `binary_operation`
+pub fn unary_operation(instruction: &Instruction, value: TricaValue) -> Result<TricaValue, TricarError> {
+    match (instruction, value) {
+        (Instruction::Not, TricaValue::Boolean(b)) => Ok(TricaValue::Boolean(!b)),
+        (Instruction::Negate, TricaValue::Int(i)) => i.checked_neg().map(TricaValue::Int)
+            .ok_or_else(|| TricarError::ArithmeticError(format!("integer overflow in -({})", i))),
+        (Instruction::Negate, TricaValue::Float(f)) => Ok(TricaValue::Float(-f)),
+        (instruction, value) => Err(TricarError::UnsupportedOperation(format!("{:?} of {:?}", instruction, value))),
+    }
+}
+
+/// The text of a Float everywhere Trica shows one: the VM, `str()` and the
+/// C backend. Whole numbers keep a `.0` so they never read as Ints.
+pub fn format_float(value: f64) -> String {
+    if value.is_finite() && value.fract() == 0.0 {
+        format!("{:.1}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn format_value(value: &TricaValue) -> String {
+    format_nested(value, 0)
+}
+
+/// `format_value` of a value `depth` lists deep. Strings in a list are
+/// quoted, so `["1", 1]` shows which element is which.
+fn format_nested(value: &TricaValue, depth: usize) -> String {
+    match value {
+        TricaValue::String(s) if depth > 0 => format!("{:?}", s),
+        TricaValue::List(_) if depth >= MAX_LIST_DEPTH => "[...]".to_string(),
+        TricaValue::Map(_) if depth >= MAX_LIST_DEPTH => "{...}".to_string(),
+        TricaValue::Map(map) => {
+            format!("{{{}}}", map.borrow().iter()
+                .map(|(key, value)| format!("{}: {}", format_nested(&key.value(), depth + 1), format_nested(value, depth + 1)))
+                .collect::<Vec<_>>()
+                .join(", "))
         }
-        
-        // Fallback to HashMap
-        self.variables.get(name).cloned()
+        TricaValue::List(list) => {
+            format!("[{}]", list.borrow().iter()
+                .map(|element| format_nested(element, depth + 1))
+                .collect::<Vec<_>>()
+                .join(", "))
+        }
+        TricaValue::String(s) => s.to_string(),
+        TricaValue::Int(i) => i.to_string(),
+        TricaValue::Float(f) => format_float(*f),
+        TricaValue::Boolean(b) => b.to_string(),
+        TricaValue::Quantum(states) => {
+            format!("⚛️[{}]", states.iter()
+                .map(|state| format_nested(state, depth))
+                .collect::<Vec<_>>()
+                .join(" | "))
+        }
+        TricaValue::Void => "∅".to_string(),
+    }
+}
+
+#[inline(always)]
+fn store_slot(slots: &mut Vec<Option<TricaValue>>, slot: usize, value: TricaValue) {
+    if slot >= slots.len() {
+        slots.resize(slot + 1, None);
+    }
+    slots[slot] = Some(value);
+}
+
+#[inline(always)]
+fn load_slot(slots: &[Option<TricaValue>], slot: usize, kind: &str) -> Result<TricaValue, TricarError> {
+    match slots.get(slot) {
+        Some(Some(value)) => Ok(value.clone()),
+        _ => Err(TricarError::VMUndefinedVariable(format!("{} slot {}", kind, slot))),
+    }
+}
+
+/// Literals of one program, deduplicated: strings through an interner,
+/// Floats by bit pattern
+#[derive(Default)]
+struct ConstantPool {
+    constants: Vec<TricaValue>,
+    strings: DefaultStringInterner,
+    /// Constant index of each interned string
+    string_indices: HashMap<DefaultSymbol, u32>,
+    int_indices: HashMap<i64, u32>,
+    float_indices: HashMap<u64, u32>,
+    /// Index of `false`, then `true`
+    boolean_indices: [Option<u32>; 2],
+    void_index: Option<u32>,
+}
+
+impl ConstantPool {
+    fn string(&mut self, value: &str) -> Result<u32, TricarError> {
+        let symbol = self.strings.get_or_intern(value);
+        if let Some(&index) = self.string_indices.get(&symbol) {
+            return Ok(index);
+        }
+        let index = self.push(TricaValue::String(value.into()))?;
+        self.string_indices.insert(symbol, index);
+        Ok(index)
+    }
+
+    fn int(&mut self, value: i64) -> Result<u32, TricarError> {
+        if let Some(&index) = self.int_indices.get(&value) {
+            return Ok(index);
+        }
+        let index = self.push(TricaValue::Int(value))?;
+        self.int_indices.insert(value, index);
+        Ok(index)
+    }
+
+    fn float(&mut self, value: f64) -> Result<u32, TricarError> {
+        if let Some(&index) = self.float_indices.get(&value.to_bits()) {
+            return Ok(index);
+        }
+        let index = self.push(TricaValue::Float(value))?;
+        self.float_indices.insert(value.to_bits(), index);
+        Ok(index)
+    }
+
+    fn boolean(&mut self, value: bool) -> Result<u32, TricarError> {
+        if let Some(index) = self.boolean_indices[usize::from(value)] {
+            return Ok(index);
+        }
+        let index = self.push(TricaValue::Boolean(value))?;
+        self.boolean_indices[usize::from(value)] = Some(index);
+        Ok(index)
+    }
+
+    fn void(&mut self) -> Result<u32, TricarError> {
+        if let Some(index) = self.void_index {
+            return Ok(index);
+        }
+        let index = self.push(TricaValue::Void)?;
+        self.void_index = Some(index);
+        Ok(index)
+    }
+
+    fn push(&mut self, value: TricaValue) -> Result<u32, TricarError> {
+        let index = u32::try_from(self.constants.len())
+            .map_err(|_| TricarError::CodegenError("Too many constants".to_string()))?;
+        self.constants.push(value);
+        Ok(index)
     }
 }
 
 /// Bytecode Compiler - Converts AST to LEGENDARY bytecode
 pub struct BytecodeCompiler {
     instructions: Vec<Instruction>,
+    constants: ConstantPool,
+    /// Variable slots from the resolver pass
+    resolution: Resolution,
+    /// Import alias -> standard module (`Math` -> `std.math`)
+    modules: HashMap<String, String>,
+}
+
+impl Default for BytecodeCompiler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BytecodeCompiler {
     pub fn new() -> Self {
         Self {
             instructions: Vec::new(),
+            constants: ConstantPool::default(),
+            resolution: Resolution::default(),
+            modules: HashMap::new(),
         }
     }
     
-    pub fn compile(&mut self, program: &Program) -> Result<Vec<Instruction>, TricarError> {
+    pub fn compile(&mut self, program: &Program) -> Result<Bytecode, TricarError> {
         // BLAZING FAST COMPILATION - DIRECT STATEMENTS!
+        self.resolution = resolve(program)?;
+        self.modules = program.imports.iter()
+            .map(|import| (import.alias.clone(), import.module_path.join(".")))
+            .collect();
         
         // Compile statements directly - NO MAIN BLOCK OVERHEAD!
-        for statement in &program.statements {
-            self.compile_statement(statement)?;
-        }
+        self.compile_block(&program.statements)?;
         
         // Add halt instruction
         self.instructions.push(Instruction::Halt);
         
-        Ok(self.instructions.clone())
+        Ok(Bytecode {
+            instructions: self.instructions.clone(),
+            constants: self.constants.constants.clone(),
+            globals: self.resolution.globals,
+            locals: self.resolution.locals,
+        })
     }
     
-    fn compile_main_block(&mut self, main_block: &MainBlock) -> Result<(), TricarError> {
-        for statement in &main_block.statements {
-            self.compile_statement(statement)?;
+    /// The native called `name`: a global function, or `Alias.name` of a standard module
+    fn function(&self, name: &str) -> Option<Native> {
+        match name.split_once('.') {
+            Some((alias, member)) => Native::from_module(self.modules.get(alias)?, member),
+            None => Native::from_name(name),
+        }
+    }
+    
+    /// Value of `Alias.NAME` for a constant of a standard module
+    fn module_constant(&self, object: &Expression, property: &str) -> Option<f64> {
+        match object {
+            Expression::Identifier { name, .. } => natives::module_constant(self.modules.get(name)?, property),
+            _ => None,
+        }
+    }
+    
+    fn compile_block(&mut self, statements: &[Statement]) -> Result<(), TricarError> {
+        statements.iter().try_for_each(|statement| self.compile_statement(statement))
+    }
+    
+    /// Resolver slot of the variable named at `line`, `column`
+    fn slot(&self, name: &str, line: usize, column: usize) -> Result<Slot, TricarError> {
+        self.resolution.slot(line, column).ok_or_else(|| TricarError::UndefinedVariable {
+            name: name.to_string(),
+            line,
+            column,
+        })
+    }
+    
+    /// Emit a jump with a placeholder target, returning its index for `patch_jump`
+    fn emit_jump(&mut self, jump: fn(usize) -> Instruction) -> usize {
+        self.instructions.push(jump(usize::MAX));
+        self.instructions.len() - 1
+    }
+    
+    /// Position operands of the list instructions; a line or column past
+    /// `u32::MAX` is reported as `u32::MAX`
+    fn position(line: usize, column: usize) -> (u32, u32) {
+        (u32::try_from(line).unwrap_or(u32::MAX), u32::try_from(column).unwrap_or(u32::MAX))
+    }
+    
+    fn store(slot: Slot) -> Instruction {
+        match slot {
+            Slot::Global(slot) => Instruction::StoreGlobal(slot),
+            Slot::Local(slot) => Instruction::StoreLocal(slot),
+        }
+    }
+    
+    fn load(slot: Slot) -> Instruction {
+        match slot {
+            Slot::Global(slot) => Instruction::LoadGlobal(slot),
+            Slot::Local(slot) => Instruction::LoadLocal(slot),
+        }
+    }
+    
+    /// Point the jump at `index` to the next instruction
+    fn patch_jump(&mut self, index: usize) {
+        let target = self.instructions.len();
+        match &mut self.instructions[index] {
+            Instruction::Jump(addr) | Instruction::JumpIfFalse(addr) => *addr = target,
+            _ => unreachable!("patch_jump on a non-jump instruction"),
         }
-        Ok(())
     }
     
     fn compile_statement(&mut self, statement: &Statement) -> Result<(), TricarError> {
@@ -513,15 +938,110 @@ impl BytecodeCompiler {
                 self.instructions.push(Instruction::Print);
             }
             
-            Statement::Assignment { name, value, .. } => {
+            Statement::Assignment { name, value, line, column } => {
+                self.compile_expression(value)?;
+                let slot = self.slot(name, *line, *column)?;
+                self.instructions.push(Self::store(slot));
+            }
+            
+            Statement::IndexAssignment { object, index, value, line, column } => {
+                self.compile_expression(object)?;
+                self.compile_expression(index)?;
                 self.compile_expression(value)?;
-                self.instructions.push(Instruction::Store(name.clone()));
+                let (line, column) = Self::position(*line, *column);
+                self.instructions.push(Instruction::StoreIndex { line, column });
             }
             
             Statement::Expression { expression, .. } => {
                 self.compile_expression(expression)?;
                 self.instructions.push(Instruction::Pop);
             }
+            
+            Statement::If { branches, else_block, .. } => {
+                let mut end_jumps = Vec::new();
+                for (i, (condition, block)) in branches.iter().enumerate() {
+                    self.compile_expression(condition)?;
+                    let skip = self.emit_jump(Instruction::JumpIfFalse);
+                    self.compile_block(&block.statements)?;
+                    if i + 1 < branches.len() || else_block.is_some() {
+                        end_jumps.push(self.emit_jump(Instruction::Jump));
+                    }
+                    self.patch_jump(skip);
+                }
+                if let Some(block) = else_block {
+                    self.compile_block(&block.statements)?;
+                }
+                for jump in end_jumps {
+                    self.patch_jump(jump);
+                }
+            }
+            
+            Statement::While { condition, body, .. } => {
+                let start = self.instructions.len();
+                self.compile_expression(condition)?;
+                let exit = self.emit_jump(Instruction::JumpIfFalse);
+                self.compile_block(&body.statements)?;
+                self.instructions.push(Instruction::Jump(start));
+                self.patch_jump(exit);
+            }
+            
+            Statement::For { variable, iterable, body, line, column, .. } => {
+                let LoopSlots { variable: element, value, list, index } = self.resolution.loop_slots(*line, *column)
+                    .ok_or_else(|| TricarError::UndefinedVariable { name: variable.clone(), line: *line, column: *column })?;
+                let (line, column) = Self::position(*line, *column);
+                let (zero, one) = (self.constants.int(0)?, self.constants.int(1)?);
+                
+                // #list = the list or the map's keys; [#values = the map's values;] #index = 0
+                self.compile_expression(iterable)?;
+                if let Some((_, values)) = value {
+                    self.instructions.extend([Instruction::StoreLocal(values), Instruction::LoadLocal(values)]);
+                }
+                self.instructions.extend([Instruction::Iterate, Instruction::StoreLocal(list)]);
+                if let Some((_, values)) = value {
+                    // Copied with the keys, so the body can change the map
+                    self.instructions.extend([
+                        Instruction::LoadLocal(values),
+                        Instruction::CallNative(Native::Values),
+                        Instruction::StoreLocal(values),
+                    ]);
+                }
+                self.instructions.extend([Instruction::LoadConst(zero), Instruction::StoreLocal(index)]);
+                
+                // while #index < #list.len() {
+                //     variable = #list[#index]; [value = #values[#index];] body; #index = #index + 1
+                // }
+                let start = self.instructions.len();
+                self.instructions.extend([
+                    Instruction::LoadLocal(index),
+                    Instruction::LoadLocal(list),
+                    Instruction::CallNative(Native::Len),
+                    Instruction::Less,
+                ]);
+                let exit = self.emit_jump(Instruction::JumpIfFalse);
+                self.instructions.extend([
+                    Instruction::LoadLocal(list),
+                    Instruction::LoadLocal(index),
+                    Instruction::Index { line, column },
+                    Self::store(element),
+                ]);
+                if let Some((value, values)) = value {
+                    self.instructions.extend([
+                        Instruction::LoadLocal(values),
+                        Instruction::LoadLocal(index),
+                        Instruction::Index { line, column },
+                        Self::store(value),
+                    ]);
+                }
+                self.compile_block(&body.statements)?;
+                self.instructions.extend([
+                    Instruction::LoadLocal(index),
+                    Instruction::LoadConst(one),
+                    Instruction::Add,
+                    Instruction::StoreLocal(index),
+                    Instruction::Jump(start),
+                ]);
+                self.patch_jump(exit);
+            }
         }
         Ok(())
     }
@@ -529,40 +1049,152 @@ impl BytecodeCompiler {
     fn compile_expression(&mut self, expr: &Expression) -> Result<(), TricarError> {
         match expr {
             Expression::StringLiteral { value, .. } => {
-                self.instructions.push(Instruction::LoadString(value.clone()));
+                let index = self.constants.string(value)?;
+                self.instructions.push(Instruction::LoadConst(index));
+            }
+            
+            Expression::IntLiteral { value, .. } => {
+                let index = self.constants.int(*value)?;
+                self.instructions.push(Instruction::LoadConst(index));
             }
             
-            Expression::NumberLiteral { value, .. } => {
-                self.instructions.push(Instruction::LoadNumber(*value));
+            Expression::FloatLiteral { value, .. } => {
+                let index = self.constants.float(*value)?;
+                self.instructions.push(Instruction::LoadConst(index));
             }
             
-            Expression::Identifier { name, .. } => {
-                self.instructions.push(Instruction::Load(name.clone()));
+            Expression::BooleanLiteral { value, .. } => {
+                let index = self.constants.boolean(*value)?;
+                self.instructions.push(Instruction::LoadConst(index));
+            }
+            
+            Expression::UnaryOp { operator, operand, .. } => {
+                self.compile_expression(operand)?;
+                self.instructions.push(match operator {
+                    UnaryOperator::Negate => Instruction::Negate,
+                    UnaryOperator::Not => Instruction::Not,
+                });
+            }
+            
+            Expression::Identifier { name, line, column } => {
+                let slot = self.slot(name, *line, *column)?;
+                self.instructions.push(Self::load(slot));
             }
             
             Expression::BinaryOp { left, operator, right, .. } => {
                 self.compile_expression(left)?;
                 self.compile_expression(right)?;
                 
-                match operator {
-                    BinaryOperator::Add => self.instructions.push(Instruction::Add),
-                    BinaryOperator::Subtract => self.instructions.push(Instruction::Subtract),
-                    BinaryOperator::Multiply => self.instructions.push(Instruction::Multiply),
-                    BinaryOperator::Divide => self.instructions.push(Instruction::Divide),
-                    _ => return Err(TricarError::UnsupportedOperation(format!("{:?}", operator))),
+                self.instructions.push(match operator {
+                    BinaryOperator::Add => Instruction::Add,
+                    BinaryOperator::Subtract => Instruction::Subtract,
+                    BinaryOperator::Multiply => Instruction::Multiply,
+                    BinaryOperator::Divide => Instruction::Divide,
+                    BinaryOperator::Modulo => Instruction::Modulo,
+                    BinaryOperator::Power => Instruction::Power,
+                    BinaryOperator::Equal => Instruction::Equal,
+                    BinaryOperator::NotEqual => Instruction::NotEqual,
+                    BinaryOperator::Less => Instruction::Less,
+                    BinaryOperator::Greater => Instruction::Greater,
+                    BinaryOperator::LessEqual => Instruction::LessEqual,
+                    BinaryOperator::GreaterEqual => Instruction::GreaterEqual,
+                });
+            }
+            
+            Expression::Interpolation { parts, .. } => {
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(text) => {
+                            let index = self.constants.string(text)?;
+                            self.instructions.push(Instruction::LoadConst(index));
+                        }
+                        InterpolationPart::Expression(expression) => self.compile_expression(expression)?,
+                    }
+                }
+                // One instruction and one allocation for the whole string
+                let count = u32::try_from(parts.len())
+                    .map_err(|_| TricarError::CodegenError("Too many interpolated parts".to_string()))?;
+                self.instructions.push(Instruction::ConcatMany(count));
+            }
+            
+            Expression::ListLiteral { elements, .. } => {
+                for element in elements {
+                    self.compile_expression(element)?;
+                }
+                let count = u32::try_from(elements.len())
+                    .map_err(|_| TricarError::CodegenError("Too many list elements".to_string()))?;
+                self.instructions.push(Instruction::MakeList(count));
+            }
+            
+            Expression::MapLiteral { entries, .. } => {
+                for (key, value) in entries {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+                let count = u32::try_from(entries.len())
+                    .map_err(|_| TricarError::CodegenError("Too many map entries".to_string()))?;
+                self.instructions.push(Instruction::MakeMap(count));
+            }
+            
+            Expression::Index { object, index, line, column } => {
+                self.compile_expression(object)?;
+                self.compile_expression(index)?;
+                let (line, column) = Self::position(*line, *column);
+                self.instructions.push(Instruction::Index { line, column });
+            }
+            
+            Expression::Slice { object, start, end, line, column } => {
+                self.compile_expression(object)?;
+                for bound in [start, end] {
+                    match bound {
+                        Some(bound) => self.compile_expression(bound)?,
+                        None => {
+                            let index = self.constants.void()?;
+                            self.instructions.push(Instruction::LoadConst(index));
+                        }
+                    }
                 }
+                let (line, column) = Self::position(*line, *column);
+                self.instructions.push(Instruction::Slice { line, column });
             }
             
-            Expression::PropertyAccess { .. } => {
-                // For now, ignore property access
-                self.instructions.push(Instruction::LoadString("".to_string()));
+            Expression::MethodCall { receiver, method, args, line, column } => {
+                // Natives dispatch on the receiver's value, so its type isn't needed here
+                let native = Native::method(&Type::Unknown, method).ok_or_else(|| TricarError::UndefinedFunction {
+                    name: method.clone(),
+                    line: *line,
+                    column: *column,
+                })?;
+                self.compile_expression(receiver)?;
+                for arg in args {
+                    self.compile_expression(arg)?;
+                }
+                self.instructions.push(Instruction::CallNative(native));
             }
             
-            Expression::FunctionCall { .. } => {
-                // For now, ignore function calls
-                self.instructions.push(Instruction::LoadString("".to_string()));
+            Expression::PropertyAccess { object, property, .. } => {
+                let index = match self.module_constant(object, property) {
+                    Some(value) => self.constants.float(value)?,
+                    // For now, ignore other property access
+                    None => self.constants.string("")?,
+                };
+                self.instructions.push(Instruction::LoadConst(index));
             }
+            
+            Expression::FunctionCall { name, args, .. } => match self.function(name) {
+                Some(native) => {
+                    for arg in args {
+                        self.compile_expression(arg)?;
+                    }
+                    self.instructions.push(Instruction::CallNative(native));
+                }
+                None => {
+                    // For now, ignore other function calls
+                    let index = self.constants.string("")?;
+                    self.instructions.push(Instruction::LoadConst(index));
+                }
+            },
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
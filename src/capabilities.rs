@@ -0,0 +1,107 @@
+// 🔒 TRICA CAPABILITIES 🔒
+// What a running script may touch outside the VM: files and the process
+// environment. `TricaVM::new()` grants
+// nothing, so embedders (like the HTTP engine) run scripts sandboxed unless
+// they opt in; the `trica` CLI grants everything, or only what its
+// `--allow-read`/`--allow-write` flags list.
+
+use std::path::{Path, PathBuf};
+use crate::error::TricarError;
+
+/// Access to one kind of filesystem operation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Access {
+    #[default]
+    Denied,
+    Anywhere,
+    /// Only paths inside these directories
+    Within(Vec<PathBuf>),
+}
+
+impl Access {
+    /// Also allow paths inside `dir`, or anywhere for `None`
+    pub fn grant(&mut self, dir: Option<PathBuf>) {
+        match (self, dir) {
+            (Access::Anywhere, _) => {}
+            (access, None) => *access = Access::Anywhere,
+            (Access::Within(dirs), Some(dir)) => dirs.push(dir),
+            (access, Some(dir)) => *access = Access::Within(vec![dir]),
+        }
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        match self {
+            Access::Denied => false,
+            Access::Anywhere => true,
+            // Compared resolved, so `..` and symlinks can't escape; a
+            // directory that doesn't exist contains nothing
+            Access::Within(dirs) => {
+                let path = resolve(path);
+                dirs.iter().filter_map(|dir| dir.canonicalize().ok()).any(|dir| path.starts_with(dir))
+            }
+        }
+    }
+}
+
+/// The capability set of a `TricaVM`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `std.fs` `read_text`, `exists` and `list_dir`
+    pub read: Access,
+    /// `std.fs` `write_text`, `append_text` and `remove`
+    pub write: Access,
+    /// `std.os` `env`
+    pub env: bool,
+}
+
+impl Capabilities {
+    /// No access to anything; the default
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Full filesystem access
+    pub fn all() -> Self {
+        Self { read: Access::Anywhere, write: Access::Anywhere, env: true }
+    }
+
+    pub fn check_read(&self, path: &Path) -> Result<(), TricarError> {
+        check(&self.read, "read", path)
+    }
+
+    pub fn check_write(&self, path: &Path) -> Result<(), TricarError> {
+        check(&self.write, "write", path)
+    }
+
+    pub fn check_env(&self, name: &str) -> Result<(), TricarError> {
+        if self.env {
+            Ok(())
+        } else {
+            Err(TricarError::PermissionDenied { operation: "read environment variable".to_string(), path: name.to_string() })
+        }
+    }
+}
+
+fn check(access: &Access, operation: &str, path: &Path) -> Result<(), TricarError> {
+    if access.allows(path) {
+        Ok(())
+    } else {
+        Err(TricarError::PermissionDenied { operation: operation.to_string(), path: path.display().to_string() })
+    }
+}
+
+/// Absolute path with symlinks and `..` resolved, as far as it exists; a
+/// file about to be created resolves through its directory
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(resolved) = path.canonicalize() {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            resolve(parent).join(name)
+        }
+        // `..` or `/` that doesn't resolve: nothing can be inside it
+        _ => PathBuf::new(),
+    }
+}
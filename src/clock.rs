@@ -0,0 +1,134 @@
+// ⏰ TRICA CLOCKS ⏰
+// Where `std.time` gets the time. The VM runs on the real `SystemClock`;
+// tests (and replays) swap in a `VirtualClock`, whose time only moves when
+// the script sleeps or the embedder advances it.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub trait Clock {
+    /// Wall-clock time, in milliseconds since the Unix epoch
+    fn now(&self) -> i64;
+    /// Milliseconds since the clock was created; never goes backwards
+    fn monotonic(&self) -> i64;
+    /// Wait `ms` milliseconds
+    fn sleep(&mut self, ms: u64);
+}
+
+/// The operating system's clocks
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_millis() as i64,
+            // A system clock set before 1970
+            Err(before) => -(before.duration().as_millis() as i64),
+        }
+    }
+
+    fn monotonic(&self) -> i64 {
+        self.start.elapsed().as_millis() as i64
+    }
+
+    fn sleep(&mut self, ms: u64) {
+        std::thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+/// A clock that stands still until told otherwise; sleeping is instant
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VirtualClock {
+    /// Wall-clock time when it was created, in Unix milliseconds
+    start: i64,
+    elapsed: i64,
+}
+
+impl VirtualClock {
+    /// Clock reading `start` milliseconds after the Unix epoch
+    pub fn new(start: i64) -> Self {
+        Self { start, elapsed: 0 }
+    }
+
+    /// Move time forward by `ms`
+    pub fn advance(&mut self, ms: u64) {
+        self.elapsed = self.elapsed.saturating_add_unsigned(ms);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> i64 {
+        self.start.saturating_add(self.elapsed)
+    }
+
+    fn monotonic(&self) -> i64 {
+        self.elapsed
+    }
+
+    fn sleep(&mut self, ms: u64) {
+        self.advance(ms);
+    }
+}
+
+/// `ms` Unix milliseconds as UTC text, following `pattern`: `%Y` year,
+/// `%m` month, `%d` day, `%H` hour, `%M` minute, `%S` second, `%L`
+/// millisecond, `%%` a percent sign. Anything else is copied as is.
+pub fn format_time(ms: i64, pattern: &str) -> String {
+    let days = ms.div_euclid(86_400_000);
+    let in_day = ms.rem_euclid(86_400_000);
+    let (year, month, day) = civil_from_days(days);
+
+    let mut text = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => text.push_str(&format!("{:04}", year)),
+            Some('m') => text.push_str(&format!("{:02}", month)),
+            Some('d') => text.push_str(&format!("{:02}", day)),
+            Some('H') => text.push_str(&format!("{:02}", in_day / 3_600_000)),
+            Some('M') => text.push_str(&format!("{:02}", in_day / 60_000 % 60)),
+            Some('S') => text.push_str(&format!("{:02}", in_day / 1000 % 60)),
+            Some('L') => text.push_str(&format!("{:03}", in_day % 1000)),
+            Some('%') => text.push('%'),
+            Some(other) => {
+                text.push('%');
+                text.push(other);
+            }
+            None => text.push('%'),
+        }
+    }
+    text
+}
+
+/// Year, month and day of the date `days` after 1970-01-01, in the
+/// proleptic Gregorian calendar (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,13 +1,21 @@
 use crate::ast::*;
+use crate::bytecode::format_float;
 use crate::error::TricarError;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub struct CodeGenerator {
     output: String,
     string_literals: HashMap<String, usize>,
     string_counter: usize,
-    temp_counter: usize,
     variables: HashMap<String, String>,
+    /// Variables holding an Int or Float, whose text can't be added
+    numbers: HashSet<String>,
+}
+
+impl Default for CodeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CodeGenerator {
@@ -16,8 +24,8 @@ impl CodeGenerator {
             output: String::new(),
             string_literals: HashMap::new(),
             string_counter: 0,
-            temp_counter: 0,
             variables: HashMap::new(),
+            numbers: HashSet::new(),
         }
     }
 
@@ -51,12 +59,6 @@ impl CodeGenerator {
         self.emit_line("");
     }
 
-    fn collect_string_literals(&mut self, main_block: &MainBlock) {
-        for statement in &main_block.statements {
-            self.collect_strings_from_statement(statement);
-        }
-    }
-    
     // BLAZING FAST - Direct statement collection
     fn collect_string_literals_from_statements(&mut self, statements: &[Statement]) {
         for statement in statements {
@@ -71,19 +73,20 @@ impl CodeGenerator {
             Statement::Expression { expression, .. } => {
                 self.collect_strings_from_expression(expression);
             }
+            // Rejected by generate_statement
+            Statement::If { .. } | Statement::While { .. } |
+            Statement::For { .. } | Statement::IndexAssignment { .. } => {}
         }
     }
 
     fn collect_strings_from_expression(&mut self, expression: &Expression) {
         match expression {
-            Expression::StringLiteral { value, .. } => {
-                if !self.string_literals.contains_key(value) {
-                    self.string_literals.insert(value.clone(), self.string_counter);
-                    self.string_counter += 1;
-                }
+            Expression::StringLiteral { value, .. } if !self.string_literals.contains_key(value) => {
+                self.string_literals.insert(value.clone(), self.string_counter);
+                self.string_counter += 1;
             }
-            Expression::NumberLiteral { value, .. } => {
-                let val = value.to_string();
+            Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => {
+                let val = literal_text(expression);
                 if !self.string_literals.contains_key(&val) {
                     self.string_literals.insert(val, self.string_counter);
                     self.string_counter += 1;
@@ -142,18 +145,6 @@ impl CodeGenerator {
         self.emit_line("");
     }
 
-    fn generate_main_function(&mut self, main_block: &MainBlock) -> Result<(), TricarError> {
-        self.emit_line("int main(void) {");
-        self.emit_line("    // Ultra-fast execution starts here");
-        for statement in &main_block.statements {
-            self.generate_statement(statement)?;
-        }
-        self.emit_line("    if (trica_last_output != NULL) free(trica_last_output);");
-        self.emit_line("    return 0;");
-        self.emit_line("}");
-        Ok(())
-    }
-    
     // BLAZING FAST - Direct statement generation
     fn generate_main_function_from_statements(&mut self, statements: &[Statement]) -> Result<(), TricarError> {
         self.emit_line("int main(void) {");
@@ -178,10 +169,21 @@ impl CodeGenerator {
                 let var = format!("trica_var_{}", name);
                 self.emit_line(&format!("    char* {} = {};", var, val));
                 self.variables.insert(name.clone(), var);
+                if self.is_number(value) {
+                    self.numbers.insert(name.clone());
+                } else {
+                    self.numbers.remove(name);
+                }
             }
             Statement::Expression { expression, .. } => {
                 self.generate_expression(expression)?; // result ignored
             }
+            Statement::If { .. } | Statement::While { .. } | Statement::For { .. } => {
+                return Err(TricarError::CodegenError("if/while/for blocks are not supported by the C backend yet".to_string()));
+            }
+            Statement::IndexAssignment { .. } => {
+                return Err(TricarError::CodegenError("lists are not supported by the C backend yet".to_string()));
+            }
         }
         Ok(())
     }
@@ -192,9 +194,8 @@ impl CodeGenerator {
                 let id = self.string_literals[value];
                 Ok(format!("trica_str_{}", id))
             }
-            Expression::NumberLiteral { value, .. } => {
-                let s = value.to_string();
-                let id = self.string_literals[&s];
+            Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => {
+                let id = self.string_literals[&literal_text(expression)];
                 Ok(format!("trica_str_{}", id))
             }
             Expression::Identifier { name, .. } => {
@@ -212,6 +213,10 @@ impl CodeGenerator {
             }
             Expression::BinaryOp { left, operator, right, .. } => {
                 match operator {
+                    // Every value is text here, and the VM adds two numbers
+                    BinaryOperator::Add if self.is_number(left) && self.is_number(right) => {
+                        Err(TricarError::CodegenError("arithmetic is not supported by the C backend yet".to_string()))
+                    }
                     BinaryOperator::Add => {
                         let l = self.generate_expression(left)?;
                         let r = self.generate_expression(right)?;
@@ -224,6 +229,15 @@ impl CodeGenerator {
         }
     }
 
+    /// Whether the expression is an Int or Float in the VM
+    fn is_number(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } => true,
+            Expression::Identifier { name, .. } => self.numbers.contains(name),
+            _ => false,
+        }
+    }
+
     fn emit_line(&mut self, line: &str) {
         self.output.push_str(line);
         self.output.push('\n');
@@ -243,3 +257,13 @@ impl CodeGenerator {
             .collect()
     }
 }
+
+/// Numbers and booleans are emitted as the text the VM would print for them
+fn literal_text(expression: &Expression) -> String {
+    match expression {
+        Expression::IntLiteral { value, .. } => value.to_string(),
+        Expression::FloatLiteral { value, .. } => format_float(*value),
+        Expression::BooleanLiteral { value, .. } => value.to_string(),
+        _ => unreachable!("literal_text on a non-literal expression"),
+    }
+}
@@ -16,6 +16,7 @@ pub enum TricarError {
     TypeMismatch { expected: String, found: String, line: usize, column: usize },
     UndefinedVariable { name: String, line: usize, column: usize },
     UndefinedFunction { name: String, line: usize, column: usize },
+    UnresolvedImport { module: String, line: usize, column: usize },
     
     // Code generation errors
     CodegenError(String),
@@ -24,10 +25,21 @@ pub enum TricarError {
     VMUndefinedVariable(String),
     UnsupportedOperation(String),
     StackUnderflow,
-    InvalidBytecode,
+    InvalidBytecode(String),
+    /// Integer overflow or division by zero
+    ArithmeticError(String),
+    /// A value a conversion can't handle, e.g. `int("abc")`
+    ValueError(String),
+    /// `std.json` `parse` of malformed text; the position is in that text
+    JsonError { message: String, line: usize, column: usize },
+    IndexOutOfBounds { index: i64, length: usize, line: usize, column: usize },
+    /// A map lookup of a missing key; `key` is shown as `Print` would
+    KeyNotFound { key: String, line: usize, column: usize },
     
     // IO errors
     IoError(String),
+    /// The VM's capabilities don't allow the operation on the path (or variable)
+    PermissionDenied { operation: String, path: String },
 }
 
 impl fmt::Display for TricarError {
@@ -60,12 +72,21 @@ impl fmt::Display for TricarError {
             TricarError::UndefinedFunction { name, line, column } => {
                 write!(f, "Undefined function '{}' at line {}, column {}", name, line, column)
             }
+            TricarError::UnresolvedImport { module, line, column } => {
+                write!(f, "Cannot find module '{}' at line {}, column {} (looked in vendor/ and .tpkg/ - try 'tpkg install')", module, line, column)
+            }
             TricarError::CodegenError(msg) => {
                 write!(f, "Code generation error: {}", msg)
             }
             TricarError::IoError(msg) => {
                 write!(f, "IO error: {}", msg)
             }
+            TricarError::JsonError { message, line, column } => {
+                write!(f, "Invalid JSON: {} at line {}, column {}", message, line, column)
+            }
+            TricarError::PermissionDenied { operation, path } => {
+                write!(f, "Permission denied: cannot {} '{}'", operation, path)
+            }
             TricarError::VMUndefinedVariable(name) => {
                 write!(f, "Undefined variable '{}' in VM", name)
             }
@@ -75,9 +96,40 @@ impl fmt::Display for TricarError {
             TricarError::StackUnderflow => {
                 write!(f, "Stack underflow in VM")
             }
-            TricarError::InvalidBytecode => {
-                write!(f, "Invalid bytecode instruction")
+            TricarError::InvalidBytecode(msg) => {
+                write!(f, "Invalid bytecode: {}", msg)
+            }
+            TricarError::ArithmeticError(msg) => {
+                write!(f, "Arithmetic error: {}", msg)
+            }
+            TricarError::ValueError(msg) => {
+                write!(f, "Value error: {}", msg)
+            }
+            TricarError::IndexOutOfBounds { index, length, line, column } => {
+                write!(f, "Index {} out of bounds for a list of length {} at line {}, column {}", index, length, line, column)
             }
+            TricarError::KeyNotFound { key, line, column } => {
+                write!(f, "Key {} not found in map at line {}, column {}", key, line, column)
+            }
+        }
+    }
+}
+
+impl TricarError {
+    /// Source line and column the error points at, if it has one
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            TricarError::UnexpectedCharacter { line, column, .. }
+            | TricarError::UnterminatedString { line, column }
+            | TricarError::InvalidNumber { line, column, .. }
+            | TricarError::UnexpectedToken { line, column, .. }
+            | TricarError::TypeMismatch { line, column, .. }
+            | TricarError::UndefinedVariable { line, column, .. }
+            | TricarError::UndefinedFunction { line, column, .. }
+            | TricarError::UnresolvedImport { line, column, .. }
+            | TricarError::IndexOutOfBounds { line, column, .. }
+            | TricarError::KeyNotFound { line, column, .. } => Some((*line, *column)),
+            _ => None,
         }
     }
 }
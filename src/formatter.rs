@@ -0,0 +1,441 @@
+// 🔥 TRICA FORMATTER 🔥
+// Re-prints a parsed Program in the one canonical style used by `trica fmt`:
+// includes, then imports, then code, one statement per line, single spaces
+// around binary operators, 4-space indentation per block (`if`, `while`, `for`,
+// legacy `Main { }`), at most one blank line in a row. Comments are kept by lexing with
+// trivia and re-attaching them to the statements around them.
+
+use std::collections::{BTreeSet, HashSet};
+use crate::ast::*;
+use crate::bytecode::format_float;
+use crate::error::TricarError;
+use crate::lexer::{Lexer, Token, TokenType};
+use crate::parser::Parser;
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Flatten a legacy `Main { }` block into top-level statements
+    pub migrate_main: bool,
+}
+
+/// Format a Trica source file, returning the canonical text
+pub fn format_source(source: &str, options: &FormatOptions) -> Result<String, TricarError> {
+    let tokens = Lexer::with_trivia(source).tokenize()?;
+    let comments = Comment::collect(&tokens);
+    let mut formatter = Formatter::new(options);
+    formatter.code_lines = tokens.iter()
+        .filter(|token| !matches!(token.token_type, TokenType::Newline | TokenType::Eof))
+        .map(|token| token.line)
+        .collect();
+    let program = Parser::new(tokens).parse()?;
+    Ok(formatter.format(&program, comments))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Section {
+    Includes,
+    Imports,
+    Code,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Item,
+    /// Ends with `{`
+    Open,
+    /// `}`
+    Close,
+    /// `} else {`, closing one block and opening the next
+    Middle,
+}
+
+impl Kind {
+    fn opens(self) -> bool {
+        matches!(self, Kind::Open | Kind::Middle)
+    }
+
+    fn closes(self) -> bool {
+        matches!(self, Kind::Close | Kind::Middle)
+    }
+}
+
+/// One output line produced from the program
+struct Entry {
+    line: usize,
+    section: Section,
+    kind: Kind,
+    /// Indentation level
+    depth: usize,
+    text: String,
+    trailing: Option<String>,
+}
+
+impl Entry {
+    fn new(line: usize, section: Section, kind: Kind, depth: usize, text: String) -> Self {
+        Self { line, section, kind, depth, text, trailing: None }
+    }
+}
+
+struct Comment {
+    line: usize,
+    text: String,
+    /// Follows code on the same line
+    trailing: bool,
+}
+
+impl Comment {
+    fn collect(tokens: &[Token]) -> Vec<Comment> {
+        tokens.iter().enumerate()
+            .filter_map(|(i, token)| {
+                let TokenType::Comment(text) = &token.token_type else {
+                    return None;
+                };
+                let trailing = i > 0
+                    && tokens[i - 1].line == token.line
+                    && !matches!(tokens[i - 1].token_type, TokenType::Newline | TokenType::Comment(_));
+                Some(Comment { line: token.line, text: text.clone(), trailing })
+            })
+            .collect()
+    }
+}
+
+struct Formatter {
+    migrate_main: bool,
+    output: String,
+    /// Source line and section of the last line written
+    previous: Option<(usize, Section, Kind)>,
+    /// Source lines with code or comments; a statement can span several,
+    /// e.g. a list literal
+    code_lines: HashSet<usize>,
+    /// Source lines with a comment; a list or map literal around one keeps
+    /// one element per line so the comment stays with its element
+    comment_lines: BTreeSet<usize>,
+}
+
+impl Formatter {
+    fn new(options: &FormatOptions) -> Self {
+        Self {
+            migrate_main: options.migrate_main,
+            output: String::new(),
+            previous: None,
+            code_lines: HashSet::new(),
+            comment_lines: BTreeSet::new(),
+        }
+    }
+
+    fn format(mut self, program: &Program, comments: Vec<Comment>) -> String {
+        self.comment_lines = comments.iter().map(|c| c.line).collect();
+        let mut entries = self.entries(program);
+
+        // Trailing comments stay on their line when it still exists
+        let mut own_line = Vec::new();
+        for comment in comments {
+            let target = entries.iter_mut().rev().find(|e| e.line == comment.line);
+            match target {
+                Some(entry) if comment.trailing => entry.trailing = Some(comment.text),
+                _ => own_line.push(comment),
+            }
+        }
+        own_line.sort_by_key(|c| c.line);
+
+        let mut pending = own_line.into_iter().peekable();
+        for entry in &entries {
+            while let Some(comment) = pending.next_if(|c| c.line < entry.line) {
+                // A comment before a `}` belongs inside the block
+                let depth = entry.depth + usize::from(entry.kind.closes());
+                self.write(comment.line, entry.section, Kind::Item, depth, &format!("//{}", comment.text));
+            }
+            let text = match &entry.trailing {
+                Some(comment) => format!("{} //{}", entry.text, comment),
+                None => entry.text.clone(),
+            };
+            self.write(entry.line, entry.section, entry.kind, entry.depth, &text);
+        }
+        for comment in pending {
+            let section = self.previous.map_or(Section::Code, |(_, section, _)| section);
+            self.write(comment.line, section, Kind::Item, 0, &format!("//{}", comment.text));
+        }
+
+        self.output
+    }
+
+    fn entries(&self, program: &Program) -> Vec<Entry> {
+        let mut entries = Vec::new();
+
+        for include in &program.includes {
+            let text = if include.is_system {
+                format!("include <{}>", include.path)
+            } else {
+                format!("include {}", quote(&include.path))
+            };
+            entries.push(Entry::new(include.line, Section::Includes, Kind::Item, 0, text));
+        }
+
+        for import in &program.imports {
+            let text = format!("import {} as {}", import.module_path.join("."), import.alias);
+            entries.push(Entry::new(import.line, Section::Imports, Kind::Item, 0, text));
+        }
+
+        let (top_level, main) = program.split_main();
+        for statement in top_level {
+            self.statement_entries(statement, 0, &mut entries);
+        }
+        if let (Some(statements), Some(m)) = (main, &program.legacy_main) {
+            if self.migrate_main {
+                for statement in statements {
+                    self.statement_entries(statement, 0, &mut entries);
+                }
+            } else {
+                entries.push(Entry::new(m.line, Section::Code, Kind::Open, 0, "Main {".to_string()));
+                for statement in statements {
+                    self.statement_entries(statement, 1, &mut entries);
+                }
+                entries.push(Entry::new(m.end_line, Section::Code, Kind::Close, 0, "}".to_string()));
+            }
+        }
+
+        entries
+    }
+
+    fn write(&mut self, line: usize, section: Section, kind: Kind, depth: usize, text: &str) {
+        if let Some((previous_line, previous_section, previous_kind)) = self.previous {
+            let blank_line = (previous_line + 1..line).any(|between| !self.code_lines.contains(&between));
+            let gap = blank_line || section != previous_section;
+            if gap && !previous_kind.opens() && !kind.closes() {
+                self.output.push('\n');
+            }
+        }
+
+        for _ in 0..depth {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(text);
+        self.output.push('\n');
+        self.previous = Some((line, section, kind));
+    }
+
+    fn statement_entries(&self, statement: &Statement, depth: usize, entries: &mut Vec<Entry>) {
+        let code = |line, kind, text: String| Entry::new(line, Section::Code, kind, depth, text);
+        match statement {
+            Statement::Print { expression, line, .. } => {
+                self.value_entries(*line, depth, "Print ".to_string(), expression, "", entries);
+            }
+            Statement::Assignment { name, value, line, .. } => {
+                self.value_entries(*line, depth, format!("{} = ", name), value, "", entries);
+            }
+            Statement::IndexAssignment { object, index, value, line, .. } => {
+                let target = format!("{}[{}] = ", wrap(object, ATOM, false), format_expression(index));
+                self.value_entries(*line, depth, target, value, "", entries);
+            }
+            Statement::Expression { expression, line, .. } => {
+                self.value_entries(*line, depth, String::new(), expression, "", entries);
+            }
+            Statement::If { branches, else_block, line, .. } => {
+                let mut opening = (*line, Kind::Open, "if");
+                let mut end_line = *line;
+                for (condition, block) in branches {
+                    let (line, kind, keyword) = opening;
+                    entries.push(code(line, kind, format!("{} {} {{", keyword, format_expression(condition))));
+                    self.block_entries(block, depth, entries);
+                    opening = (block.end_line, Kind::Middle, "} else if");
+                    end_line = block.end_line;
+                }
+                if let Some(block) = else_block {
+                    entries.push(code(end_line, Kind::Middle, "} else {".to_string()));
+                    self.block_entries(block, depth, entries);
+                    end_line = block.end_line;
+                }
+                entries.push(code(end_line, Kind::Close, "}".to_string()));
+            }
+            Statement::While { condition, body, line, .. } => {
+                entries.push(code(*line, Kind::Open, format!("while {} {{", format_expression(condition))));
+                self.block_entries(body, depth, entries);
+                entries.push(code(body.end_line, Kind::Close, "}".to_string()));
+            }
+            Statement::For { variable, value, iterable, body, line, .. } => {
+                let variables = match value {
+                    Some(value) => format!("{}, {}", variable, value),
+                    None => variable.clone(),
+                };
+                entries.push(code(*line, Kind::Open, format!("for {} in {} {{", variables, format_expression(iterable))));
+                self.block_entries(body, depth, entries);
+                entries.push(code(body.end_line, Kind::Close, "}".to_string()));
+            }
+        }
+    }
+
+    /// The statements of a block whose `{` line is at `depth`
+    fn block_entries(&self, block: &Block, depth: usize, entries: &mut Vec<Entry>) {
+        for statement in &block.statements {
+            self.statement_entries(statement, depth + 1, entries);
+        }
+    }
+
+    /// `prefix`, `value` and `suffix` as one line, or as one line per element
+    /// when `value` is a list or map literal with a comment inside
+    fn value_entries(&self, line: usize, depth: usize, prefix: String, value: &Expression, suffix: &str, entries: &mut Vec<Entry>) {
+        // Each element with its line and what goes before it
+        let (elements, end_line, open, close): (Vec<(usize, String, &Expression)>, _, _, _) = match value {
+            Expression::ListLiteral { elements, line, end_line, .. } if self.has_comment(*line, *end_line) => {
+                (elements.iter().map(|element| (element.line(), String::new(), element)).collect(), *end_line, '[', ']')
+            }
+            Expression::MapLiteral { entries, line, end_line, .. } if self.has_comment(*line, *end_line) => {
+                let entries = entries.iter().map(|(key, value)| (key.line(), format!("{}: ", format_expression(key)), value));
+                (entries.collect(), *end_line, '{', '}')
+            }
+            _ => {
+                let text = format!("{}{}{}", prefix, format_expression(value), suffix);
+                entries.push(Entry::new(line, Section::Code, Kind::Item, depth, text));
+                return;
+            }
+        };
+        entries.push(Entry::new(line, Section::Code, Kind::Open, depth, format!("{}{}", prefix, open)));
+        for (line, before, element) in elements {
+            self.value_entries(line, depth + 1, before, element, ",", entries);
+        }
+        entries.push(Entry::new(end_line, Section::Code, Kind::Close, depth, format!("{}{}", close, suffix)));
+    }
+
+    /// A comment runs to the end of its line, so one on the closing line comes
+    /// after the literal
+    fn has_comment(&self, line: usize, end_line: usize) -> bool {
+        self.comment_lines.range(line..end_line).next().is_some()
+    }
+}
+
+pub fn format_expression(expression: &Expression) -> String {
+    match expression {
+        Expression::StringLiteral { value, .. } => quote(value),
+        Expression::Interpolation { parts, .. } => {
+            let mut text = String::from("\"");
+            for part in parts {
+                match part {
+                    InterpolationPart::Text(value) => text.push_str(&escape(value)),
+                    InterpolationPart::Expression(expression) => {
+                        text.push_str(&format!("${{{}}}", format_expression(expression)));
+                    }
+                }
+            }
+            text.push('"');
+            text
+        }
+        Expression::IntLiteral { value, .. } => value.to_string(),
+        // Keeps the `.0` of whole Floats, so they stay Floats
+        Expression::FloatLiteral { value, .. } => format_float(*value),
+        Expression::BooleanLiteral { value, .. } => value.to_string(),
+        Expression::Identifier { name, .. } => name.clone(),
+        Expression::PropertyAccess { object, property, .. } => {
+            format!("{}.{}", wrap(object, ATOM, false), property)
+        }
+        Expression::FunctionCall { name, args, .. } => format!("{}({})", name, list(args)),
+        Expression::MethodCall { receiver, method, args, .. } => {
+            format!("{}.{}({})", wrap(receiver, ATOM, false), method, list(args))
+        }
+        Expression::ListLiteral { elements, .. } => format!("[{}]", list(elements)),
+        Expression::MapLiteral { entries, .. } => {
+            let entries: Vec<String> = entries.iter()
+                .map(|(key, value)| format!("{}: {}", format_expression(key), format_expression(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Expression::Index { object, index, .. } => format!("{}[{}]", wrap(object, ATOM, false), format_expression(index)),
+        Expression::Slice { object, start, end, .. } => {
+            let bound = |bound: &Option<Box<Expression>>| bound.as_deref().map(format_expression).unwrap_or_default();
+            format!("{}[{}:{}]", wrap(object, ATOM, false), bound(start), bound(end))
+        }
+        // `**` groups to the right, so its left operand is the one that needs
+        // parentheses; its right operand may be unary, as in `2.0 ** -1`
+        Expression::BinaryOp { left, operator: BinaryOperator::Power, right, .. } => {
+            format!("{} ** {}", wrap(left, POWER, true), wrap(right, UNARY, false))
+        }
+        Expression::BinaryOp { left, operator, right, .. } => {
+            let precedence = precedence(operator);
+            format!("{} {} {}", wrap(left, precedence, false), symbol(operator), wrap(right, precedence, true))
+        }
+        // `!x` is written `not x`
+        Expression::UnaryOp { operator: UnaryOperator::Not, operand, .. } => format!("not {}", wrap(operand, UNARY, false)),
+        Expression::UnaryOp { operator: UnaryOperator::Negate, operand, .. } => format!("-{}", wrap(operand, UNARY, false)),
+    }
+}
+
+/// Comma-separated expressions, as in arguments and list literals
+fn list(expressions: &[Expression]) -> String {
+    expressions.iter().map(format_expression).collect::<Vec<_>>().join(", ")
+}
+
+/// Binding strength of `not` and `-`, above every binary operator but `**`
+const UNARY: u8 = 5;
+/// Binding strength of `**`
+const POWER: u8 = 6;
+/// Binding strength of expressions that never need parentheses
+const ATOM: u8 = 7;
+
+fn precedence(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::Equal | BinaryOperator::NotEqual => 1,
+        BinaryOperator::Less | BinaryOperator::Greater |
+        BinaryOperator::LessEqual | BinaryOperator::GreaterEqual => 2,
+        BinaryOperator::Add | BinaryOperator::Subtract => 3,
+        BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 4,
+        BinaryOperator::Power => POWER,
+    }
+}
+
+fn symbol(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Modulo => "%",
+        BinaryOperator::Power => "**",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::Less => "<",
+        BinaryOperator::Greater => ">",
+        BinaryOperator::LessEqual => "<=",
+        BinaryOperator::GreaterEqual => ">=",
+    }
+}
+
+/// Parenthesize an operand that binds more loosely than its parent.
+/// Operators group to the left (`**` to the right), so an equal-precedence
+/// operand on the side `against` the grouping needs parentheses too.
+fn wrap(expression: &Expression, parent: u8, against: bool) -> String {
+    let own = match expression {
+        Expression::BinaryOp { operator, .. } => precedence(operator),
+        Expression::UnaryOp { .. } => UNARY,
+        _ => ATOM,
+    };
+    let text = format_expression(expression);
+    if own < parent || (against && own == parent && own != ATOM) {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+/// A string literal with the escapes the lexer understands
+fn quote(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+/// Text of a string literal: escapes, including `\$` so `${` stays text
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '$' if chars.peek() == Some(&'{') => escaped.push_str("\\$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
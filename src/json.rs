@@ -0,0 +1,153 @@
+// 📦 TRICA JSON 📦
+// `std.json` on top of serde_json. Values are decoded straight into Trica
+// values (so objects keep their key order) and encoded straight from them.
+// JSON null is Trica's void value, `∅`.
+
+use std::fmt;
+use std::rc::Rc;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+use crate::bytecode::{MapKey, TricaMap, TricaValue};
+use crate::error::TricarError;
+
+/// Deepest nesting of lists and maps `stringify` writes; deeper means the
+/// value (most likely) contains itself
+const MAX_DEPTH: usize = 128;
+
+/// The value `text` encodes
+pub fn parse(text: &str) -> Result<TricaValue, TricarError> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let value = ValueSeed.deserialize(&mut deserializer)
+        .and_then(|value| deserializer.end().map(|_| value))
+        .map_err(|e| {
+            // serde_json ends its messages with the position; it's kept separately
+            let message = e.to_string();
+            let suffix = format!(" at line {} column {}", e.line(), e.column());
+            TricarError::JsonError {
+                message: message.strip_suffix(&suffix).unwrap_or(&message).to_string(),
+                line: e.line(),
+                column: e.column(),
+            }
+        })?;
+    Ok(value)
+}
+
+/// `value` as JSON text, indented by two spaces when `pretty`. Map keys
+/// become strings; floats must be finite.
+pub fn stringify(value: &TricaValue, pretty: bool) -> Result<String, TricarError> {
+    let value = Encode { value, depth: 0 };
+    let text = if pretty { serde_json::to_string_pretty(&value) } else { serde_json::to_string(&value) };
+    text.map_err(|e| TricarError::ValueError(format!("stringify: {}", e)))
+}
+
+struct ValueSeed;
+
+impl<'de> DeserializeSeed<'de> for ValueSeed {
+    type Value = TricaValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<TricaValue, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = TricaValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<TricaValue, E> {
+        Ok(TricaValue::Void)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<TricaValue, E> {
+        Ok(TricaValue::Boolean(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<TricaValue, E> {
+        Ok(TricaValue::Int(i))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<TricaValue, E> {
+        // Integers past i64 lose precision rather than failing
+        Ok(i64::try_from(u).map_or(TricaValue::Float(u as f64), TricaValue::Int))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<TricaValue, E> {
+        Ok(TricaValue::Float(f))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<TricaValue, E> {
+        Ok(TricaValue::String(s.into()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TricaValue, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element_seed(ValueSeed)? {
+            elements.push(element);
+        }
+        Ok(TricaValue::new_list(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TricaValue, A::Error> {
+        let mut entries = TricaMap::default();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(ValueSeed)?;
+            // A repeated key keeps its first position and its last value
+            entries.insert(MapKey::String(key.into()), value);
+        }
+        Ok(TricaValue::Map(Rc::new(entries.into())))
+    }
+}
+
+struct Encode<'a> {
+    value: &'a TricaValue,
+    depth: usize,
+}
+
+impl Encode<'_> {
+    fn nested<'b>(&self, value: &'b TricaValue) -> Result<Encode<'b>, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("value is nested more than {} levels deep", MAX_DEPTH));
+        }
+        Ok(Encode { value, depth: self.depth + 1 })
+    }
+}
+
+impl Serialize for Encode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            TricaValue::String(s) => serializer.serialize_str(s),
+            TricaValue::Int(i) => serializer.serialize_i64(*i),
+            TricaValue::Float(f) if f.is_finite() => serializer.serialize_f64(*f),
+            TricaValue::Float(f) => Err(ser::Error::custom(format!("{} has no JSON form", f))),
+            TricaValue::Boolean(b) => serializer.serialize_bool(*b),
+            TricaValue::Void => serializer.serialize_unit(),
+            TricaValue::List(list) => {
+                let list = list.borrow();
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for element in list.iter() {
+                    seq.serialize_element(&self.nested(element).map_err(ser::Error::custom)?)?;
+                }
+                seq.end()
+            }
+            TricaValue::Map(map) => {
+                let map = map.borrow();
+                let mut entries = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map.iter() {
+                    let key = match key {
+                        MapKey::String(s) => s.to_string(),
+                        MapKey::Int(i) => i.to_string(),
+                        MapKey::Boolean(b) => b.to_string(),
+                    };
+                    entries.serialize_entry(&key, &self.nested(value).map_err(ser::Error::custom)?)?;
+                }
+                entries.end()
+            }
+            TricaValue::Quantum(_) => Err(ser::Error::custom("a quantum value has no JSON form")),
+        }
+    }
+}
@@ -5,7 +5,11 @@ use std::collections::HashMap;
 pub enum TokenType {
     // Literals
     StringLiteral(String),
-    NumberLiteral(f64),
+    /// A string literal containing `${...}`
+    InterpolatedString(Vec<StringPart>),
+    IntLiteral(i64),
+    FloatLiteral(f64),
+    BooleanLiteral(bool),
     
     // Identifiers and Keywords
     Identifier(String),
@@ -14,32 +18,55 @@ pub enum TokenType {
     As,
     Main,
     Print,
+    If,
+    Else,
+    While,
+    For,
+    In,
+    Not,
     
     // Operators and Punctuation
     LeftBrace,      // {
     RightBrace,     // }
     LeftParen,      // (
     RightParen,     // )
+    LeftBracket,    // [
+    RightBracket,   // ]
+    Colon,          // :
     Dot,            // .
     Comma,          // ,
     Equal,          // =
     Plus,           // +
     Minus,          // -
     Star,           // *
+    StarStar,       // **
     Slash,          // /
+    Percent,        // %
     Less,           // <
     Greater,        // >
     LessEqual,      // <=
     GreaterEqual,   // >=
     EqualEqual,     // ==
     NotEqual,       // !=
+    Bang,           // !
+    
+    // Trivia, only produced by `Lexer::with_trivia`
+    Comment(String), // text after `//`
     
     // Special
     Newline,
     Eof,
 }
 
-#[derive(Debug, Clone)]
+/// A piece of an interpolated string literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    /// The tokens between `${` and `}`, ending with `Eof`
+    Code(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
     pub line: usize,
@@ -52,6 +79,8 @@ pub struct Lexer {
     line: usize,
     column: usize,
     keywords: HashMap<String, TokenType>,
+    /// Emit `Comment` tokens instead of discarding comments
+    keep_trivia: bool,
 }
 
 impl Lexer {
@@ -62,6 +91,14 @@ impl Lexer {
         keywords.insert("as".to_string(), TokenType::As);
         keywords.insert("Main".to_string(), TokenType::Main);
         keywords.insert("Print".to_string(), TokenType::Print);
+        keywords.insert("if".to_string(), TokenType::If);
+        keywords.insert("else".to_string(), TokenType::Else);
+        keywords.insert("while".to_string(), TokenType::While);
+        keywords.insert("for".to_string(), TokenType::For);
+        keywords.insert("in".to_string(), TokenType::In);
+        keywords.insert("not".to_string(), TokenType::Not);
+        keywords.insert("true".to_string(), TokenType::BooleanLiteral(true));
+        keywords.insert("false".to_string(), TokenType::BooleanLiteral(false));
         
         Self {
             input: input.chars().collect(),
@@ -69,6 +106,16 @@ impl Lexer {
             line: 1,
             column: 1,
             keywords,
+            keep_trivia: false,
+        }
+    }
+    
+    /// Lexer that keeps comments as `Comment` tokens, for tools like
+    /// `trica fmt` that must reproduce them. The parser ignores them.
+    pub fn with_trivia(input: &str) -> Self {
+        Self {
+            keep_trivia: true,
+            ..Self::new(input)
         }
     }
     
@@ -82,228 +129,280 @@ impl Lexer {
                 break;
             }
             
-            let start_line = self.line;
-            let start_column = self.column;
-            
-            match self.current_char() {
-                // Comments
-                '/' if self.peek() == Some('/') => {
-                    self.skip_comment();
-                    continue;
-                }
-                
-                // String literals
-                '"' => {
-                    let string_val = self.read_string()?;
-                    tokens.push(Token {
-                        token_type: TokenType::StringLiteral(string_val),
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                
-                // Numbers
-                c if c.is_ascii_digit() => {
-                    let number_val = self.read_number()?;
-                    tokens.push(Token {
-                        token_type: TokenType::NumberLiteral(number_val),
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                
-                // Identifiers and keywords
-                c if c.is_alphabetic() || c == '_' => {
-                    let identifier = self.read_identifier();
-                    let token_type = self.keywords.get(&identifier)
-                        .cloned()
-                        .unwrap_or(TokenType::Identifier(identifier));
-                    
+            self.lex_token(&mut tokens)?;
+        }
+        
+        tokens.push(Token {
+            token_type: TokenType::Eof,
+            line: self.line,
+            column: self.column,
+        });
+        
+        Ok(tokens)
+    }
+    
+    /// Lex the token at the current character onto `tokens`
+    fn lex_token(&mut self, tokens: &mut Vec<Token>) -> Result<(), TricarError> {
+        let start_line = self.line;
+        let start_column = self.column;
+        
+        match self.current_char() {
+            // Comments
+            '/' if self.peek() == Some('/') => {
+                let text = self.read_comment();
+                if self.keep_trivia {
                     tokens.push(Token {
-                        token_type,
+                        token_type: TokenType::Comment(text),
                         line: start_line,
                         column: start_column,
                     });
                 }
+                return Ok(());
+            }
+            
+            // String literals
+            '"' => {
+                let token_type = self.read_string()?;
+                tokens.push(Token {
+                    token_type,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            // Numbers
+            c if c.is_ascii_digit() => {
+                let token_type = self.read_number()?;
+                tokens.push(Token {
+                    token_type,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            // Identifiers and keywords
+            c if c.is_alphabetic() || c == '_' => {
+                let identifier = self.read_identifier();
+                let token_type = self.keywords.get(&identifier)
+                    .cloned()
+                    .unwrap_or(TokenType::Identifier(identifier));
                 
-                // Single-character tokens
-                '{' => {
-                    self.advance();
-                    tokens.push(Token {
-                        token_type: TokenType::LeftBrace,
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                '}' => {
-                    self.advance();
-                    tokens.push(Token {
-                        token_type: TokenType::RightBrace,
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                '(' => {
+                tokens.push(Token {
+                    token_type,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            // Single-character tokens
+            '{' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::LeftBrace,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '}' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::RightBrace,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '[' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::LeftBracket,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            ']' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::RightBracket,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            ':' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Colon,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '(' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::LeftParen,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            ')' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::RightParen,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '.' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Dot,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            ',' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Comma,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '+' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Plus,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '-' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Minus,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '*' => {
+                self.advance();
+                let token_type = if self.current_char() == '*' {
                     self.advance();
-                    tokens.push(Token {
-                        token_type: TokenType::LeftParen,
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                ')' => {
+                    TokenType::StarStar
+                } else {
+                    TokenType::Star
+                };
+                tokens.push(Token {
+                    token_type,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            '/' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Slash,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            '%' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Percent,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            // Multi-character operators
+            '=' => {
+                self.advance();
+                if self.current_char() == '=' {
                     self.advance();
                     tokens.push(Token {
-                        token_type: TokenType::RightParen,
+                        token_type: TokenType::EqualEqual,
                         line: start_line,
                         column: start_column,
                     });
-                }
-                '.' => {
-                    self.advance();
+                } else {
                     tokens.push(Token {
-                        token_type: TokenType::Dot,
+                        token_type: TokenType::Equal,
                         line: start_line,
                         column: start_column,
                     });
                 }
-                ',' => {
+            }
+            '<' => {
+                self.advance();
+                if self.current_char() == '=' {
                     self.advance();
                     tokens.push(Token {
-                        token_type: TokenType::Comma,
+                        token_type: TokenType::LessEqual,
                         line: start_line,
                         column: start_column,
                     });
-                }
-                '+' => {
-                    self.advance();
+                } else {
                     tokens.push(Token {
-                        token_type: TokenType::Plus,
+                        token_type: TokenType::Less,
                         line: start_line,
                         column: start_column,
                     });
                 }
-                '-' => {
+            }
+            '>' => {
+                self.advance();
+                if self.current_char() == '=' {
                     self.advance();
                     tokens.push(Token {
-                        token_type: TokenType::Minus,
+                        token_type: TokenType::GreaterEqual,
                         line: start_line,
                         column: start_column,
                     });
-                }
-                '*' => {
-                    self.advance();
+                } else {
                     tokens.push(Token {
-                        token_type: TokenType::Star,
+                        token_type: TokenType::Greater,
                         line: start_line,
                         column: start_column,
                     });
                 }
-                '/' => {
+            }
+            '!' => {
+                self.advance();
+                if self.current_char() == '=' {
                     self.advance();
                     tokens.push(Token {
-                        token_type: TokenType::Slash,
+                        token_type: TokenType::NotEqual,
                         line: start_line,
                         column: start_column,
                     });
-                }
-                
-                // Multi-character operators
-                '=' => {
-                    self.advance();
-                    if self.current_char() == '=' {
-                        self.advance();
-                        tokens.push(Token {
-                            token_type: TokenType::EqualEqual,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    } else {
-                        tokens.push(Token {
-                            token_type: TokenType::Equal,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    }
-                }
-                '<' => {
-                    self.advance();
-                    if self.current_char() == '=' {
-                        self.advance();
-                        tokens.push(Token {
-                            token_type: TokenType::LessEqual,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    } else {
-                        tokens.push(Token {
-                            token_type: TokenType::Less,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    }
-                }
-                '>' => {
-                    self.advance();
-                    if self.current_char() == '=' {
-                        self.advance();
-                        tokens.push(Token {
-                            token_type: TokenType::GreaterEqual,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    } else {
-                        tokens.push(Token {
-                            token_type: TokenType::Greater,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    }
-                }
-                '!' => {
-                    self.advance();
-                    if self.current_char() == '=' {
-                        self.advance();
-                        tokens.push(Token {
-                            token_type: TokenType::NotEqual,
-                            line: start_line,
-                            column: start_column,
-                        });
-                    } else {
-                        return Err(TricarError::UnexpectedCharacter {
-                            ch: '!',
-                            line: start_line,
-                            column: start_column,
-                        });
-                    }
-                }
-                
-                '\n' => {
-                    self.advance();
+                } else {
                     tokens.push(Token {
-                        token_type: TokenType::Newline,
-                        line: start_line,
-                        column: start_column,
-                    });
-                }
-                
-                c => {
-                    return Err(TricarError::UnexpectedCharacter {
-                        ch: c,
+                        token_type: TokenType::Bang,
                         line: start_line,
                         column: start_column,
                     });
                 }
             }
+            
+            '\n' => {
+                self.advance();
+                tokens.push(Token {
+                    token_type: TokenType::Newline,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
+            
+            c => {
+                return Err(TricarError::UnexpectedCharacter {
+                    ch: c,
+                    line: start_line,
+                    column: start_column,
+                });
+            }
         }
-        
-        tokens.push(Token {
-            token_type: TokenType::Eof,
-            line: self.line,
-            column: self.column,
-        });
-        
-        Ok(tokens)
+        Ok(())
     }
     
     fn current_char(&self) -> char {
@@ -343,19 +442,27 @@ impl Lexer {
         }
     }
     
-    fn skip_comment(&mut self) {
+    /// Consume a `//` comment up to the end of the line, returning its text
+    fn read_comment(&mut self) -> String {
+        self.advance(); // Skip `//`
+        self.advance();
+        
+        let mut text = String::new();
         while !self.is_at_end() && self.current_char() != '\n' {
-            self.advance();
+            text.push(self.advance());
         }
+        text.trim_end().to_string()
     }
     
-    fn read_string(&mut self) -> Result<String, TricarError> {
+    /// A `StringLiteral`, or an `InterpolatedString` if it contains `${`
+    fn read_string(&mut self) -> Result<TokenType, TricarError> {
         let start_line = self.line;
         let start_column = self.column;
         
         self.advance(); // Skip opening quote
         
         let mut value = String::new();
+        let mut parts = Vec::new();
         
         while !self.is_at_end() && self.current_char() != '"' {
             if self.current_char() == '\n' {
@@ -373,11 +480,18 @@ impl Lexer {
                     'r' => value.push('\r'),
                     '\\' => value.push('\\'),
                     '"' => value.push('"'),
+                    '$' => value.push('$'),
                     c => {
                         value.push('\\');
                         value.push(c);
                     }
                 }
+            } else if self.current_char() == '$' && self.peek() == Some('{') {
+                self.advance();
+                self.advance();
+                parts.push(StringPart::Text(std::mem::take(&mut value)));
+                parts.push(StringPart::Code(self.read_interpolation(start_line, start_column)?));
+                continue;
             } else {
                 value.push(self.current_char());
             }
@@ -393,10 +507,47 @@ impl Lexer {
         }
         
         self.advance(); // Skip closing quote
-        Ok(value)
+        if parts.is_empty() {
+            return Ok(TokenType::StringLiteral(value));
+        }
+        parts.push(StringPart::Text(value));
+        parts.retain(|part| *part != StringPart::Text(String::new()));
+        Ok(TokenType::InterpolatedString(parts))
     }
     
-    fn read_number(&mut self) -> Result<f64, TricarError> {
+    /// Tokens of the expression after `${`, up to and consuming the matching `}`
+    fn read_interpolation(&mut self, start_line: usize, start_column: usize) -> Result<Vec<Token>, TricarError> {
+        let mut tokens = Vec::new();
+        // Braces opened inside the expression, e.g. by a map literal
+        let mut depth = 0;
+        loop {
+            self.skip_whitespace();
+            match self.current_char() {
+                _ if self.is_at_end() => break,
+                '\n' => break,
+                '}' if depth == 0 => {
+                    tokens.push(Token {
+                        token_type: TokenType::Eof,
+                        line: self.line,
+                        column: self.column,
+                    });
+                    self.advance();
+                    return Ok(tokens);
+                }
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            self.lex_token(&mut tokens)?;
+        }
+        Err(TricarError::UnterminatedString {
+            line: start_line,
+            column: start_column,
+        })
+    }
+    
+    /// An `IntLiteral`, or a `FloatLiteral` if the number has a `.`
+    fn read_number(&mut self) -> Result<TokenType, TricarError> {
         let start_line = self.line;
         let start_column = self.column;
         let mut number_str = String::new();
@@ -406,7 +557,12 @@ impl Lexer {
             self.advance();
         }
         
-        number_str.parse().map_err(|_| TricarError::InvalidNumber {
+        let token_type = if number_str.contains('.') {
+            number_str.parse().ok().map(TokenType::FloatLiteral)
+        } else {
+            number_str.parse().ok().map(TokenType::IntLiteral)
+        };
+        token_type.ok_or(TricarError::InvalidNumber {
             text: number_str,
             line: start_line,
             column: start_column,
@@ -3,8 +3,22 @@ pub mod parser;
 pub mod ast;
 pub mod type_checker;
 pub mod codegen;
+pub mod formatter;
+pub mod lint;
+pub mod resolver;
 pub mod bytecode;
+pub mod optimizer;
+pub mod natives;
+pub mod capabilities;
+pub mod random;
+pub mod clock;
+pub mod json;
+pub mod verifier;
+pub mod tbc;
 pub mod error;
+pub mod modules;
+pub mod tpkg;
+pub mod lsp;
 
 pub use lexer::Lexer;
 pub use parser::Parser;
@@ -15,6 +29,46 @@ pub use error::TricarError;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytecode::{Bytecode, BytecodeCompiler, TricaVM};
+    use capabilities::Capabilities;
+    use clock::VirtualClock;
+    
+    /// Parse, type-check and compile `source`
+    fn compile(source: &str) -> Result<Bytecode, TricarError> {
+        let ast = Parser::new(Lexer::new(source).tokenize()?).parse()?;
+        TypeChecker::new().check(&ast)?;
+        BytecodeCompiler::new().compile(&ast)
+    }
+    
+    /// How `run_with` sets up the VM; the default is a sandboxed `TricaVM::new()`
+    #[derive(Default)]
+    struct RunOptions {
+        capabilities: Capabilities,
+        args: Vec<String>,
+        seed: Option<u64>,
+        clock: Option<VirtualClock>,
+    }
+    
+    /// Verify `bytecode` and run it to the end, returning the VM
+    fn run_with(bytecode: Bytecode, options: RunOptions) -> Result<TricaVM, TricarError> {
+        verifier::verify(&bytecode)?;
+        let mut vm = TricaVM::with_capabilities(options.capabilities);
+        vm.set_args(options.args);
+        if let Some(seed) = options.seed {
+            vm.set_seed(seed);
+        }
+        if let Some(clock) = options.clock {
+            vm.set_clock(clock);
+        }
+        vm.load_bytecode(bytecode);
+        vm.execute()?;
+        Ok(vm)
+    }
+    
+    /// What `bytecode` prints on a default VM
+    fn run(bytecode: Bytecode) -> Result<Vec<String>, TricarError> {
+        run_with(bytecode, RunOptions::default()).map(|vm| vm.get_output().clone())
+    }
 
     #[test]
     fn test_lexer_basic() {
@@ -45,7 +99,7 @@ mod tests {
         let mut parser = Parser::new(tokens);
         let ast = parser.parse().unwrap();
         
-        assert_eq!(ast.main_block.statements.len(), 1);
+        assert_eq!(ast.statements.len(), 1);
     }
     
     #[test]
@@ -120,4 +174,884 @@ mod tests {
         
         assert!(type_checker.check(&ast).is_ok());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_imports_prefer_vendor_over_tpkg() {
+        use modules::ModuleResolver;
+        
+        let base = std::env::temp_dir().join(format!("trica-modules-{}", std::process::id()));
+        for dir in ["vendor", ".tpkg"] {
+            std::fs::create_dir_all(base.join(dir).join("shapes")).unwrap();
+            std::fs::write(base.join(dir).join("shapes").join("main.trica"), "Print \"shapes\"").unwrap();
+        }
+        
+        let source = r#"
+            import shapes as Shapes
+            Print "ok"
+        "#;
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        
+        let mut type_checker = TypeChecker::with_resolver(ModuleResolver::new(&base));
+        assert!(type_checker.check(&ast).is_ok());
+        assert_eq!(type_checker.module_path("Shapes"), Some(base.join("vendor/shapes/main.trica").as_path()));
+        
+        std::fs::remove_dir_all(base.join("vendor")).unwrap();
+        let mut type_checker = TypeChecker::with_resolver(ModuleResolver::new(&base));
+        assert!(type_checker.check(&ast).is_ok());
+        assert_eq!(type_checker.module_path("Shapes"), Some(base.join(".tpkg/shapes/main.trica").as_path()));
+        
+        std::fs::remove_dir_all(&base).unwrap();
+        let mut type_checker = TypeChecker::with_resolver(ModuleResolver::new(&base));
+        assert!(matches!(type_checker.check(&ast), Err(TricarError::UnresolvedImport { .. })));
+    }
+    
+    #[test]
+    fn test_tpkg_resolves_version_ranges() {
+        let mut versions = tpkg::package::demo_packages();
+        for (package, version) in versions.iter_mut().zip(["1.0.0", "1.4.2", "2.0.0", "not-semver", "1.2.0"]) {
+            package.version = version.to_string();
+        }
+        
+        assert_eq!(tpkg::parse_spec("time_travel@^1.0"), ("time_travel", Some("^1.0")));
+        assert_eq!(tpkg::parse_spec("time_travel"), ("time_travel", None));
+        
+        let caret = semver::VersionReq::parse("^1.0").unwrap();
+        assert_eq!(tpkg::select_version(&versions, &caret).unwrap().version, "1.4.2");
+        let exact = semver::VersionReq::parse("=1.2.0").unwrap();
+        assert_eq!(tpkg::select_version(&versions, &exact).unwrap().version, "1.2.0");
+        let none = semver::VersionReq::parse("^3").unwrap();
+        assert!(tpkg::select_version(&versions, &none).is_none());
+    }
+    
+    #[test]
+    fn test_tpkg_package_names_and_search_patterns() {
+        use tpkg::registry::ilike_pattern;
+        use tpkg::validate_package_name;
+        
+        for name in ["neural_networks", "a", "web-server2", &"a".repeat(64)] {
+            assert!(validate_package_name(name).is_ok(), "{:?}", name);
+        }
+        for name in ["", "Bad", "1st", "_x", "-x", "a/b", "../x", "a.b", "a b", "é", &"a".repeat(65)] {
+            assert!(matches!(validate_package_name(name), Err(tpkg::TpkgError::InvalidPackageName(_))), "{:?}", name);
+        }
+        
+        // Quoted, so `,` `(` `)` stay inside the filter; LIKE wildcards match literally
+        assert_eq!(ilike_pattern("shapes"), r#""*shapes*""#);
+        assert_eq!(ilike_pattern("a,b(c)"), r#""*a,b(c)*""#);
+        assert_eq!(ilike_pattern("a_b%"), r#""*a\\_b\\%*""#);
+        assert_eq!(ilike_pattern(r#"say "hi""#), r#""*say \"hi\"*""#);
+        assert_eq!(ilike_pattern(r"a\b"), r#""*a\\\\b*""#);
+    }
+    
+    #[tokio::test]
+    async fn test_tpkg_offline_install_and_vendor() {
+        use tpkg::{Installer, PackageCache, Registry, Source};
+        
+        let base = std::env::temp_dir().join(format!("trica-tpkg-{}", std::process::id()));
+        let cache = PackageCache::new(base.join("cache")).unwrap();
+        let code = "Print \"shapes\"";
+        cache.store("shapes", "1.0.0", "Print \"old shapes\"").unwrap();
+        let stored = cache.store("shapes", "1.3.0", code).unwrap();
+        cache.store("shapes", "2.0.0", "Print \"new shapes\"").unwrap();
+        
+        let project = base.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let installer = Installer::new(&project, Registry::default(), cache, true);
+        
+        let installed = installer.install("shapes", Some("^1.0")).await.unwrap();
+        assert_eq!(installed.source, Source::Cache);
+        assert_eq!(installed.locked, stored);
+        assert_eq!(std::fs::read_to_string(project.join(".tpkg/shapes/main.trica")).unwrap(), code);
+        
+        let lockfile = tpkg::Lockfile::load(&project).unwrap();
+        assert_eq!(lockfile.get("shapes"), Some(&stored));
+        assert_eq!(tpkg::Manifest::load(&project).unwrap().dependencies["shapes"], "^1.0");
+        
+        assert_eq!(installer.vendor().unwrap(), vec![stored.clone()]);
+        assert_eq!(std::fs::read_to_string(project.join("vendor/shapes/main.trica")).unwrap(), code);
+        
+        assert!(installer.install("shapes", Some("^3")).await.is_err());
+        installer.uninstall("shapes").unwrap();
+        assert!(installer.installed().unwrap().is_empty());
+        assert!(matches!(installer.uninstall("shapes"), Err(tpkg::TpkgError::NotInstalled(_))));
+        
+        // Locked names become paths, so they can't climb out of the project
+        let lock = format!("[[package]]\nname = \"../../escaped\"\nversion = \"1.3.0\"\nchecksum = \"{}\"\n", stored.checksum.clone().unwrap());
+        std::fs::write(project.join(tpkg::LOCK_FILE), lock).unwrap();
+        assert!(matches!(installer.install_locked().await, Err(tpkg::TpkgError::InvalidPackageName(_))));
+        assert!(matches!(installer.vendor(), Err(tpkg::TpkgError::InvalidPackageName(_))));
+        assert!(!base.join("escaped").exists());
+        
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+    
+    /// A package version as the registry would return it
+    fn registry_package(name: &str, version: &str, code: &str) -> tpkg::Package {
+        tpkg::Package {
+            id: 1,
+            name: name.to_string(),
+            version: version.to_string(),
+            description: String::new(),
+            author: String::new(),
+            quantum_level: 0,
+            downloads: 0,
+            created_at: String::new(),
+            code: code.to_string(),
+            dependencies: Vec::new(),
+        }
+    }
+    
+    /// A registry on localhost that answers package queries from `packages`
+    /// and accepts everything else
+    async fn registry_stub(packages: Vec<tpkg::Package>) -> tpkg::Registry {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                // The whole request, so closing the connection can't reset it
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                let head = loop {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break String::from_utf8_lossy(&request[..end]).to_string();
+                    }
+                };
+                let length = head.lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, value)| value.trim().parse().unwrap());
+                while request.len() < head.len() + 4 + length {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                
+                let target = head.split(' ').nth(1).unwrap_or_default();
+                let body = match target.split_once("?name=eq.") {
+                    Some((_, query)) => {
+                        let name = query.split('&').next().unwrap_or_default();
+                        let versions: Vec<_> = packages.iter().filter(|p| p.name == name).collect();
+                        serde_json::to_string(&versions).unwrap()
+                    }
+                    None => "null".to_string(),
+                };
+                let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        tpkg::Registry::new(&url, "test-key")
+    }
+    
+    #[tokio::test]
+    async fn test_tpkg_update_outdated_and_uninstall() {
+        use tpkg::{Installer, Lockfile, Manifest, PackageCache, Source};
+        
+        let base = std::env::temp_dir().join(format!("trica-tpkg-registry-{}", std::process::id()));
+        let project = base.join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let registry = registry_stub(vec![
+            registry_package("shapes", "1.0.0", "Print \"1.0\""),
+            registry_package("shapes", "1.2.0", "Print \"1.2\""),
+            registry_package("shapes", "2.0.0", "Print \"2.0\""),
+        ]).await;
+        let installer = Installer::new(&project, registry, PackageCache::new(base.join("cache")).unwrap(), false);
+        
+        let installed = installer.install("shapes", Some("=1.0.0")).await.unwrap();
+        assert_eq!((installed.source, installed.locked.version.as_str()), (Source::Registry, "1.0.0"));
+        let outdated = installer.outdated().await.unwrap();
+        assert_eq!(outdated.len(), 1);
+        assert_eq!((outdated[0].current.as_str(), outdated[0].wanted.as_deref(), outdated[0].latest.as_str()), ("1.0.0", Some("1.0.0"), "2.0.0"));
+        
+        // update moves to the newest version the range allows, once
+        let mut manifest = Manifest::load(&project).unwrap();
+        manifest.dependencies.insert("shapes".to_string(), "^1.0".to_string());
+        manifest.save(&project).unwrap();
+        let updated = installer.update(None).await.unwrap();
+        assert_eq!(updated.len(), 1);
+        assert_eq!((updated[0].from.as_deref(), updated[0].to.as_str()), (Some("1.0.0"), "1.2.0"));
+        assert_eq!(std::fs::read_to_string(project.join(".tpkg/shapes/main.trica")).unwrap(), "Print \"1.2\"");
+        assert!(installer.update(None).await.unwrap().is_empty());
+        assert!(matches!(installer.update(Some("circles")).await, Err(tpkg::TpkgError::NotADependency(_))));
+        
+        // A lock newer than anything published isn't outdated
+        let mut lockfile = Lockfile::load(&project).unwrap();
+        lockfile.packages[0].version = "3.0.0".to_string();
+        lockfile.save(&project).unwrap();
+        assert!(installer.outdated().await.unwrap().is_empty());
+        
+        installer.uninstall("shapes").unwrap();
+        assert!(!project.join(".tpkg/shapes").exists());
+        assert!(Manifest::load(&project).unwrap().dependencies.is_empty());
+        assert!(Lockfile::load(&project).unwrap().packages.is_empty());
+        assert!(matches!(installer.uninstall("../shapes"), Err(tpkg::TpkgError::InvalidPackageName(_))));
+        
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_tpkg_publish_validation() {
+        use tpkg::publish::publish;
+        use tpkg::{PublishRequest, TpkgError};
+        
+        let base = std::env::temp_dir().join(format!("trica-tpkg-publish-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("tests")).unwrap();
+        std::fs::write(base.join("main.trica"), "Print \"shapes\"").unwrap();
+        std::fs::write(base.join("tests/a.trica"), "Print 1 + 1").unwrap();
+        let registry = registry_stub(vec![registry_package("shapes", "1.0.0", "")]).await;
+        let request = |name: &str, version: &str| PublishRequest {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: "Shapes".to_string(),
+            author: "Tests".to_string(),
+            quantum_level: 1,
+            code_file: base.join("main.trica"),
+            dry_run: true,
+        };
+        
+        let (package, tests_run) = publish(&registry, &request("shapes", "1.1.0")).await.unwrap();
+        assert_eq!((package.code.as_str(), tests_run), ("Print \"shapes\"", 1));
+        
+        assert!(matches!(publish(&registry, &request("Shapes", "1.1.0")).await, Err(TpkgError::InvalidPackageName(_))));
+        assert!(matches!(publish(&registry, &request("shapes", "1.1")).await, Err(TpkgError::InvalidVersion { .. })));
+        assert!(matches!(publish(&registry, &request("shapes", "1.0.0")).await, Err(TpkgError::VersionExists { .. })));
+        
+        std::fs::write(base.join("tests/b.trica"), "Print 1 / 0").unwrap();
+        match publish(&registry, &request("shapes", "1.1.0")).await {
+            Err(TpkgError::Validation(message)) => assert!(message.contains("b.trica"), "{}", message),
+            other => panic!("expected the failing package test to stop the publish, got {:?}", other.map(|(p, _)| p)),
+        }
+        
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+    
+    #[test]
+    fn test_lsp_analysis() {
+        use lsp::{Analysis, Definition};
+        use lsp_types::Position;
+        
+        let source = "greeting = \"Hi\"\ncount = 2\nPrint greeting\nlast = Print.output\n";
+        let analysis = Analysis::new(source, modules::ModuleResolver::default());
+        assert!(analysis.error().is_none());
+        let warnings: Vec<_> = analysis.diagnostics().into_iter().map(|d| d.message).collect();
+        assert_eq!(warnings, ["variable 'count' is never used (unused-variable)", "variable 'last' is never used (unused-variable)"]);
+        
+        let hover = analysis.hover(Position::new(2, 8)).unwrap();
+        let lsp_types::HoverContents::Markup(content) = hover.contents else { panic!("expected markup") };
+        assert_eq!(content.value, "`greeting`: String");
+        
+        let Some(Definition::Local(range)) = analysis.definition(Position::new(2, 8)) else { panic!("expected local definition") };
+        assert_eq!((range.start, range.end), (Position::new(0, 0), Position::new(0, 8)));
+        
+        let symbols: Vec<_> = analysis.symbols().into_iter().map(|s| s.name).collect();
+        assert_eq!(symbols, ["greeting", "count", "last"]);
+        
+        let labels: Vec<_> = analysis.completions(Position::new(2, 0)).into_iter().map(|c| c.label).collect();
+        assert!(labels.contains(&"Print".to_string()) && labels.contains(&"Print.output".to_string()));
+        assert!(labels.contains(&"count".to_string()) && !labels.contains(&"last".to_string()));
+        let members: Vec<_> = analysis.completions(Position::new(3, 13)).into_iter().map(|c| c.label).collect();
+        assert_eq!(members, ["output"]);
+        
+        // Mid-edit documents still complete variables from the lines above
+        let editing = Analysis::new("total = 1\nnext = ", modules::ModuleResolver::default());
+        let diagnostics = editing.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let labels: Vec<_> = editing.completions(Position::new(1, 7)).into_iter().map(|c| c.label).collect();
+        assert!(labels.contains(&"total".to_string()));
+        
+        let broken = Analysis::new("x = 1\nPrint y\n", modules::ModuleResolver::default());
+        let diagnostic = &broken.diagnostics()[0];
+        assert!(diagnostic.message.contains("Undefined variable 'y'"));
+        assert_eq!((diagnostic.range.start, diagnostic.range.end), (Position::new(1, 6), Position::new(1, 7)));
+    }
+    
+    #[test]
+    fn test_lsp_server_publishes_diagnostics() {
+        use lsp_server::{Connection, Message, Notification, Request};
+        
+        let (server, client) = Connection::memory();
+        let handle = std::thread::spawn(move || lsp::serve(&server).map_err(|e| e.to_string()));
+        
+        let initialize = serde_json::json!({ "capabilities": {} });
+        client.sender.send(Message::Request(Request::new(1.into(), "initialize".to_string(), initialize))).unwrap();
+        let Message::Response(initialized) = client.receiver.recv().unwrap() else { panic!("expected initialize result") };
+        assert_eq!(initialized.result.unwrap()["capabilities"]["hoverProvider"], true);
+        client.sender.send(Message::Notification(Notification::new("initialized".to_string(), serde_json::json!({})))).unwrap();
+        
+        let open = serde_json::json!({
+            "textDocument": { "uri": "file:///tmp/main.trica", "languageId": "trica", "version": 1, "text": "Print missing\n" }
+        });
+        client.sender.send(Message::Notification(Notification::new("textDocument/didOpen".to_string(), open))).unwrap();
+        let Message::Notification(published) = client.receiver.recv().unwrap() else { panic!("expected diagnostics") };
+        assert_eq!(published.method, "textDocument/publishDiagnostics");
+        assert!(published.params["diagnostics"][0]["message"].as_str().unwrap().contains("missing"));
+        
+        client.sender.send(Message::Request(Request::new(2.into(), "shutdown".to_string(), serde_json::Value::Null))).unwrap();
+        assert!(matches!(client.receiver.recv().unwrap(), Message::Response(_)));
+        client.sender.send(Message::Notification(Notification::new("exit".to_string(), serde_json::Value::Null))).unwrap();
+        handle.join().unwrap().unwrap();
+    }
+    
+    #[test]
+    fn test_formatter_canonical_output() {
+        use formatter::{format_source, FormatOptions};
+        
+        let source = "import user.input as In\ninclude <stdio.h>\nMain {\n  x=1+2*3   // seven\n\n\n   // halve it\n  y = (x-1)/(2*3) - (4-5)\nPrint \"say \\\"hi\\\"\"\n}\n";
+        let formatted = format_source(source, &FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "include <stdio.h>\n\nimport user.input as In\n\nMain {\n    x = 1 + 2 * 3 // seven\n\n    // halve it\n    y = (x - 1) / (2 * 3) - (4 - 5)\n    Print \"say \\\"hi\\\"\"\n}\n");
+        assert_eq!(format_source(&formatted, &FormatOptions::default()).unwrap(), formatted);
+        
+        let migrated = format_source(source, &FormatOptions { migrate_main: true }).unwrap();
+        assert!(migrated.ends_with("\nx = 1 + 2 * 3 // seven\n\n// halve it\ny = (x - 1) / (2 * 3) - (4 - 5)\nPrint \"say \\\"hi\\\"\"\n"));
+        assert!(Parser::new(Lexer::new(&migrated).tokenize().unwrap()).parse().unwrap().legacy_main.is_none());
+    }
+
+    #[test]
+    fn test_formatter_keeps_comments_in_literals() {
+        use formatter::{format_source, FormatOptions};
+
+        let source = "m = {\n \"a\": 1, // first\n \"b\": [2, // two\n3],\n// last\n}\nxs = [1, // one\n 2]\nPrint [3, 4] // after\n";
+        let formatted = format_source(source, &FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "m = {\n    \"a\": 1, // first\n    \"b\": [\n        2, // two\n        3,\n    ],\n    // last\n}\n\
+                               xs = [\n    1, // one\n    2,\n]\nPrint [3, 4] // after\n");
+        assert_eq!(format_source(&formatted, &FormatOptions::default()).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_lint_warnings() {
+        use lint::{lint_source, Level, Lint, LintConfig};
+        
+        let source = r#"
+            include <stdio.h>
+            import user.input as In
+            import user.input as Unused
+            name = In.input
+            unused = 1
+            count = 1
+            count = 2
+            Print count
+            Print 10 / 0
+            Print 1 < 2
+            Main {
+                name = "shadow"
+                Print name
+            }
+        "#;
+        let codes = |config: &LintConfig| -> Vec<(Lint, usize)> {
+            lint_source(source, config).unwrap().into_iter().map(|w| (w.lint, w.line)).collect()
+        };
+        
+        assert_eq!(codes(&LintConfig::default()), [
+            (Lint::UselessInclude, 2),
+            (Lint::UnusedImport, 4),
+            (Lint::UnusedAssignment, 5),
+            (Lint::UnusedVariable, 6),
+            (Lint::UnusedAssignment, 7),
+            (Lint::DivisionByZero, 10),
+            (Lint::ConstantCondition, 11),
+            (Lint::ShadowedVariable, 13),
+        ]);
+
+        let conditions = "if true {\n    Print 1\n} else if 1 {\n    Print 2\n}\nwhile not false {\n}\nwhile true {\n}\nif 1 < 2 {\n}\n";
+        let found: Vec<(Lint, usize)> = lint_source(conditions, &LintConfig::default()).unwrap()
+            .into_iter().map(|w| (w.lint, w.line)).collect();
+        assert_eq!(found, [(Lint::ConstantCondition, 1), (Lint::ConstantCondition, 3), (Lint::ConstantCondition, 6), (Lint::ConstantCondition, 10)]);
+
+        let mut config = LintConfig::default();
+        config.set(Lint::UselessInclude, Level::Allow);
+        config.set(Lint::DivisionByZero, Level::Deny);
+        let warnings = lint_source(source, &config).unwrap();
+        assert!(warnings.iter().all(|w| w.lint != Lint::UselessInclude));
+        assert_eq!(warnings.iter().find(|w| w.lint == Lint::DivisionByZero).unwrap().level, Level::Deny);
+        
+        let directive = format!("// lint: allow(W001, unused-import, useless-include)\n{}", source);
+        let remaining = lint_source(&directive, &LintConfig::default()).unwrap();
+        assert!(remaining.iter().all(|w| !matches!(w.lint, Lint::UnusedVariable | Lint::UnusedImport | Lint::UselessInclude)));
+        
+        let base = std::env::temp_dir().join(format!("trica-lint-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("src")).unwrap();
+        std::fs::write(base.join("trica.toml"), "[lints]\nW006 = \"allow\"\nunused-variable = \"deny\"\n").unwrap();
+        let loaded = LintConfig::load(&base.join("src")).unwrap();
+        assert_eq!((loaded.level(Lint::ConstantCondition), loaded.level(Lint::UnusedVariable)), (Level::Allow, Level::Deny));
+        std::fs::write(base.join("trica.toml"), "[lints]\nno-such-lint = \"allow\"\n").unwrap();
+        assert!(LintConfig::load(&base).is_err());
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+    
+    #[test]
+    fn test_block_scoping() {
+        use bytecode::Instruction;
+        
+        let parse = |source: &str| Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let check = |source: &str| TypeChecker::new().check(&parse(source));
+        
+        // Block variables are invisible outside; sibling blocks may reuse a name
+        assert!(matches!(check("if 1 < 2 {\n inner = 1\n}\nPrint inner"),
+                         Err(TricarError::UndefinedVariable { line: 4, .. })));
+        assert!(check("if 1 < 2 {\n x = 1\n} else {\n x = \"one\"\n}\nx = \"later\"").is_ok());
+        // Assigning a visible outer variable reassigns it, so its type must match
+        assert!(matches!(check("x = 1\nwhile x < 3 {\n x = \"no\"\n}"),
+                         Err(TricarError::TypeMismatch { line: 3, .. })));
+        assert!(check("if \"yes\" {\n}").is_err());
+        
+        let source = "total = 0\ni = 0\nwhile i < 4 {\n    step = i * 2\n    total = total + step\n    i = i + 1\n}\nif total > 12 {\n    Print \"big\"\n} else if total == 12 {\n    Print total\n} else {\n    Print \"small\"\n}\n";
+        let bytecode = BytecodeCompiler::new().compile(&parse(source)).unwrap();
+        assert!(bytecode.instructions.iter().any(|i| matches!(i, Instruction::LoadLocal(0))));
+        assert!(bytecode.instructions.iter().any(|i| matches!(i, Instruction::StoreGlobal(1))));
+        let mut vm = TricaVM::new();
+        vm.load_bytecode(bytecode);
+        vm.execute().unwrap();
+        assert_eq!(vm.get_output(), &["12"]);
+        
+        // Loop counters read by the next iteration are not dead stores
+        assert!(lint::lint_source(source, &lint::LintConfig::default()).unwrap().is_empty());
+        
+        let formatted = formatter::format_source("while total<3 { total = total+1 }\nif total>2 { Print total } else { Print 0 }", &Default::default()).unwrap();
+        assert_eq!(formatted, "while total < 3 {\n    total = total + 1\n}\nif total > 2 {\n    Print total\n} else {\n    Print 0\n}\n");
+    }
+    
+    #[test]
+    fn test_constant_pool() {
+        use bytecode::{Instruction, TricaValue};
+        
+        let source = "greeting = \"hi\"\nPrint greeting + \" \" + greeting\nPrint \"hi\"\nPrint 2 * 2\nPrint \"hi\"";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let bytecode = BytecodeCompiler::new().compile(&ast).unwrap();
+        
+        // "hi", " " and 2 are each stored once
+        assert_eq!(bytecode.constants.len(), 3);
+        assert!(matches!(&bytecode.constants[0], TricaValue::String(s) if &**s == "hi"));
+        assert_eq!(bytecode.instructions.iter().filter(|i| matches!(i, Instruction::LoadConst(0))).count(), 3);
+        
+        let mut vm = TricaVM::new();
+        vm.load_bytecode(bytecode);
+        vm.execute().unwrap();
+        assert_eq!(vm.get_output(), &["hi hi", "hi", "4", "hi"]);
+    }
+    
+    #[test]
+    fn test_optimizer_preserves_output() {
+        use bytecode::Instruction;
+        use optimizer::{optimize, OptLevel, Passes};
+        
+        let programs = [
+            "Print 2 * 3 + 1",
+            "if 1 > 2 {\n    Print \"no\"\n} else {\n    Print \"yes\"\n}",
+            "i = 0\nwhile i < 3 {\n    Print i\n    i = i + 1\n}",
+            "name = \"trica\"\nlimit = 10\nPrint \"hello \" + name\nPrint limit * 2",
+        ];
+        let compile = |source: &str| compile(source).unwrap();
+        for source in programs {
+            let plain = compile(source);
+            let expected = run(plain.clone()).unwrap();
+            for level in [OptLevel::O1, OptLevel::O2] {
+                let optimized = optimize(plain.clone(), level.passes());
+                assert!(optimized.instructions.len() <= plain.instructions.len());
+                assert_eq!(run(optimized).unwrap(), expected, "{:?} changed the output of {:?}", level, source);
+            }
+        }
+        
+        // Folded down to a single constant
+        let folded = optimize(compile(programs[0]), OptLevel::O1.passes());
+        assert!(matches!(folded.instructions[..], [Instruction::LoadConst(_), Instruction::Print, Instruction::Halt]));
+        
+        // The dead branch and its jumps are gone
+        let branch = optimize(compile(programs[1]), OptLevel::O1.passes());
+        assert_eq!(branch.instructions.len(), 3);
+        assert_eq!(run(branch).unwrap(), ["yes"]);
+        
+        // Only -O2 replaces variables stored once with their constant
+        let globals = |bytecode: &Bytecode| bytecode.instructions.iter()
+            .filter(|i| matches!(i, Instruction::LoadGlobal(_) | Instruction::StoreGlobal(_)))
+            .count();
+        assert_eq!(globals(&optimize(compile(programs[3]), OptLevel::O1.passes())), 4);
+        assert_eq!(globals(&optimize(compile(programs[3]), OptLevel::O2.passes())), 0);
+        // The loop counter is stored twice, so it stays a variable
+        assert!(globals(&optimize(compile(programs[2]), OptLevel::O2.passes())) > 0);
+        
+        // Passes toggle individually; -O0 leaves the bytecode alone
+        let plain = compile(programs[1]);
+        assert_eq!(optimize(plain.clone(), OptLevel::O0.passes()).instructions, plain.instructions);
+        let fold_only = Passes { fold_constants: true, ..Passes::default() };
+        let unreachable_kept = optimize(plain.clone(), fold_only);
+        assert!(unreachable_kept.instructions.len() > 3);
+        assert_eq!(run(unreachable_kept).unwrap(), ["yes"]);
+    }
+    
+    #[test]
+    fn test_bytecode_verifier() {
+        use bytecode::{Instruction, TricaValue};
+        
+        let source = "i = 0\nwhile i < 2 {\n    word = \"hi\"\n    Print word + i\n    i = i + 1\n}";
+        let compiled = compile(source).unwrap();
+        assert_eq!(verifier::verify(&compiled).unwrap(), 2);
+        
+        // Compiled programs survive a round trip through a .tbc file
+        let loaded = tbc::read(&tbc::write(&compiled)).unwrap();
+        assert_eq!(loaded.instructions, compiled.instructions);
+        assert_eq!(run(loaded).unwrap(), ["hi0", "hi1"]);
+        
+        let rejects = |instructions: Vec<Instruction>, problem: &str| {
+            let bytecode = Bytecode {
+                instructions,
+                constants: vec![TricaValue::Boolean(true)],
+                globals: 1,
+                locals: 0,
+            };
+            match verifier::verify(&bytecode) {
+                Err(TricarError::InvalidBytecode(message)) => assert!(message.contains(problem), "{}", message),
+                other => panic!("expected {:?} to be rejected, got {:?}", bytecode.instructions, other),
+            }
+            // Hand-written .tbc files go through the same checks
+            assert!(tbc::read(&tbc::write(&bytecode)).is_err());
+        };
+        use Instruction::*;
+        rejects(vec![Print], "does not end with Halt");
+        rejects(vec![Jump(7), Halt], "jump target 7");
+        rejects(vec![LoadConst(1), Print, Halt], "constant 1");
+        rejects(vec![LoadConst(0), StoreGlobal(1), Halt], "global slot 1");
+        rejects(vec![LoadConst(0), StoreLocal(0), Halt], "local slot 0");
+        rejects(vec![LoadConst(0), Add, Halt], "pops 2 value(s) from a stack of 1");
+        // A loop that leaves a value behind every time round
+        rejects(vec![LoadConst(0), LoadConst(0), JumpIfFalse(0), Halt], "stack depth is 0 on one path and 1");
+        
+        let bytes = tbc::write(&compiled);
+        assert!(tbc::read(&bytes[..bytes.len() - 1]).is_err());
+        assert!(tbc::read(b"not bytecode").is_err());
+        // Version 2 files predate the removal of opcode 22 and are refused
+        let mut old = bytes.clone();
+        old[3] = 2;
+        assert!(matches!(tbc::read(&old), Err(TricarError::InvalidBytecode(message)) if message.contains("version 2")));
+    }
+    
+    #[test]
+    fn test_int_and_float() {
+        let run = |source: &str| compile(source).and_then(run);
+        
+        // Ints divide toward zero, Floats keep their `.0`, mixing makes a Float
+        let source = "a = 7\nb = 2\nPrint a / b\nPrint a % b\nPrint 7.0 / 2\nPrint 3.0\nPrint 1.5 + a\nPrint \"n=\" + 2.0";
+        assert_eq!(run(source).unwrap(), ["3", "1", "3.5", "3.0", "8.5", "n=2.0"]);
+        assert_eq!(run("Print int(9.99) + int(\" 12 \")\nPrint float(7) / 2\nPrint str(10) + str(0.5)").unwrap(),
+                   ["21", "3.5", "100.5"]);
+        
+        assert!(matches!(run("x = 9223372036854775807\nPrint x + 1"), Err(TricarError::ArithmeticError(_))));
+        assert!(matches!(run("zero = 0\nPrint 5 % zero"), Err(TricarError::ArithmeticError(_))));
+        assert!(matches!(run("zero = 0\nPrint 5 / zero"), Err(TricarError::ArithmeticError(_))));
+        assert_eq!(run("zero = 0.0\nPrint 1.0 / zero\nPrint -1.0 / zero\nPrint zero / zero").unwrap(), ["inf", "-inf", "NaN"]);
+        assert!(matches!(run("Print int(\"abc\")"), Err(TricarError::ValueError(_))));
+        assert!(matches!(run("Print 99999999999999999999"), Err(TricarError::InvalidNumber { .. })));
+        // A variable keeps the type it was declared with
+        assert!(matches!(run("x = 1\nx = 1.5"), Err(TricarError::TypeMismatch { .. })));
+        
+        // The C backend and the formatter print numbers the way the VM does
+        let ast = Parser::new(Lexer::new("Print 2.0\nPrint 10").tokenize().unwrap()).parse().unwrap();
+        let c = codegen::CodeGenerator::new().generate(&ast).unwrap();
+        assert!(c.contains("[] = \"2.0\";") && c.contains("[] = \"10\";"));
+        // It has no arithmetic, so `+` on two numbers is refused rather than concatenated
+        let generate = |source: &str| codegen::CodeGenerator::new().generate(&Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap());
+        assert!(matches!(generate("Print 1 + 2"), Err(TricarError::CodegenError(_))));
+        assert!(matches!(generate("x = 1\nPrint x + 2.5"), Err(TricarError::CodegenError(_))));
+        assert!(generate("x = 1\nPrint \"n=\" + x").unwrap().contains("trica_concat(trica_str_"));
+        assert_eq!(formatter::format_source("x = 1.0 % 3\n", &formatter::FormatOptions::default()).unwrap(), "x = 1.0 % 3\n");
+    }
+    
+    #[test]
+    fn test_booleans_and_unary_operators() {
+        use bytecode::Instruction;
+        use optimizer::{optimize, OptLevel};
+        
+        let source = "done = 3 > 2\nPrint done\nPrint not done\nPrint !false\nx = 4\nPrint -x * 2\nPrint -1.5\nif !done {\n    Print \"no\"\n} else {\n    Print true\n}";
+        let expected = ["true", "false", "true", "-8", "-1.5", "true"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        assert_eq!(run(optimize(compile(source).unwrap(), OptLevel::O2.passes())).unwrap(), expected);
+        
+        // Negative literals fold to a single constant
+        let folded = optimize(compile("Print -5").unwrap(), OptLevel::O1.passes());
+        assert!(matches!(folded.instructions[..], [Instruction::LoadConst(_), Instruction::Print, Instruction::Halt]));
+        
+        assert!(matches!(compile("Print not 1"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("Print -\"a\""), Err(TricarError::TypeMismatch { .. })));
+        let min = compile("x = 9223372036854775807\nPrint -x - 1\nPrint -(-x - 1)").unwrap();
+        assert!(matches!(run(min), Err(TricarError::ArithmeticError(_))));
+        
+        let formatted = formatter::format_source("Print !(1 > 2)\nPrint - -3\n", &formatter::FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "Print not (1 > 2)\nPrint --3\n");
+    }
+    
+    #[test]
+    fn test_lists() {
+        use optimizer::{optimize, OptLevel};
+        
+        let source = "xs = [1, 2, 3]\nxs[1] = 20\nxs.push(4)\nPrint xs.pop() + xs.len()\nPrint xs[1:]\nPrint xs[:1]\n\
+                      total = 0\nfor x in xs {\n    total = total + x\n}\nPrint total\n\
+                      ys = xs\nys.push(0)\nPrint xs == [1, 20, 3, 0]\nPrint [[\"a\"], []]";
+        let expected = ["7", "[20, 3]", "[1]", "24", "true", "[[\"a\"], []]"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        assert_eq!(run(optimize(compile(source).unwrap(), OptLevel::O2.passes())).unwrap(), expected);
+        
+        let error = run(compile("xs = [1]\nPrint xs[0]\nPrint xs[1]").unwrap()).unwrap_err();
+        assert!(matches!(error, TricarError::IndexOutOfBounds { index: 1, length: 1, .. }));
+        assert_eq!(error.position(), Some((3, 7)));
+        
+        assert!(matches!(compile("xs = [1, \"a\"]"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("xs = [1]\nxs.push(\"a\")"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("for x in 3 {\n}"), Err(TricarError::TypeMismatch { .. })));
+
+        // The loop walks the elements the list had when it started
+        let source = "xs = [1, 2, 3]\nfor x in xs {\n    xs.push(x)\n    Print x\n}\nfor x in xs {\n    xs.pop()\n}\nPrint xs";
+        assert_eq!(run(compile(source).unwrap()).unwrap(), ["1", "2", "3", "[]"]);
+
+        // Elements of an empty list literal are Unknown, so the VM checks them
+        assert_eq!(run(compile("xs = []\nxs.push(1)\nPrint xs[0] + 1\nPrint xs[0] * 2.5\nPrint -xs[0] < 0").unwrap()).unwrap(),
+                   ["2", "2.5", "true"]);
+        assert!(matches!(run(compile("xs = []\nxs.push(\"a\")\nPrint xs[0] - 1").unwrap()), Err(TricarError::UnsupportedOperation(_))));
+
+        let formatted = formatter::format_source("for x in [1,2][0 :1] {\nxs = [ ]\nxs[0]=x.len( )\n}\n", &formatter::FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "for x in [1, 2][0:1] {\n    xs = []\n    xs[0] = x.len()\n}\n");
+    }
+    
+    #[test]
+    fn test_maps() {
+        use optimizer::{optimize, OptLevel};
+        
+        let source = "m = {\n    \"b\": 2,\n    \"a\": 1,\n}\nm[\"c\"] = 3\nm[\"b\"] = 20\nPrint m\n\
+                      Print m.remove(\"a\")\nPrint m.has(\"a\")\nPrint m.keys()\nPrint m.values()\nPrint m.len()\n\
+                      for key, value in m {\n    Print key + \"=\" + value\n}\nfor key in {true: []} {\n    Print key\n}\n\
+                      Print {1: \"x\", 2: \"y\"} == {2: \"y\", 1: \"x\"}";
+        let expected = ["{\"b\": 20, \"a\": 1, \"c\": 3}", "true", "false", "[\"b\", \"c\"]", "[20, 3]", "2", "b=20", "c=3", "true", "true"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        assert_eq!(run(optimize(compile(source).unwrap(), OptLevel::O2.passes())).unwrap(), expected);
+        
+        let error = run(compile("m = {\"a\": 1}\nPrint m[\"b\"]").unwrap()).unwrap_err();
+        assert!(matches!(&error, TricarError::KeyNotFound { key, .. } if key == "\"b\""));
+        assert_eq!(error.position(), Some((2, 7)));
+        
+        assert!(matches!(compile("m = {\"a\": 1, \"b\": \"c\"}"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("m = {1.5: 1}"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("m = {\"a\": 1}\nPrint m[0]"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("for k, v in [1] {\n}"), Err(TricarError::TypeMismatch { .. })));
+
+        // Values of an empty map literal are Unknown, so the VM checks them
+        assert_eq!(run(compile("m = {}\nm[\"a\"] = 1\nPrint m[\"a\"] * 2\nPrint m[\"a\"] + \"!\"").unwrap()).unwrap(), ["2", "1!"]);
+
+        // The loop walks the entries the map had when it started
+        let source = "m = {\"a\": 1, \"b\": 2, \"c\": 3}\nfor k, v in m {\n    m.remove(\"b\")\n    m[\"d\"] = 4\n    Print k + \"=\" + v\n}\nPrint m";
+        assert_eq!(run(compile(source).unwrap()).unwrap(), ["a=1", "b=2", "c=3", "{\"a\": 1, \"c\": 3, \"d\": 4}"]);
+    }
+    
+    #[test]
+    fn test_string_interpolation() {
+        use bytecode::Instruction;
+        use optimizer::{optimize, OptLevel};
+        
+        let source = "name = \"Ann\"\nage = 30\nPrint \"Hello ${name}, you are ${age + 1}\"\n\
+                      m = {\"a\": [\"x\"]}\nPrint \"${m[\"a\"][0]}${m} costs \\${5}\"";
+        let expected = ["Hello Ann, you are 31", "x{\"a\": [\"x\"]} costs ${5}"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        assert_eq!(run(optimize(compile(source).unwrap(), OptLevel::O2.passes())).unwrap(), expected);
+        
+        // Constant parts fold into a single string
+        let folded = optimize(compile("Print \"${1 + 2} and ${true}\"").unwrap(), OptLevel::O1.passes());
+        assert!(matches!(folded.instructions[..], [Instruction::LoadConst(_), Instruction::Print, Instruction::Halt]));
+        assert_eq!(run(folded).unwrap(), ["3 and true"]);
+        
+        assert!(matches!(compile("xs = [1]\nPrint \"${xs.push(2)}\""), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("Print \"${1 2}\""), Err(TricarError::UnexpectedToken { .. })));
+        assert!(matches!(compile("Print \"${1\n\""), Err(TricarError::UnterminatedString { .. })));
+        
+        let formatted = formatter::format_source("Print \"a${ 1+2 }\\${b}\"\n", &formatter::FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "Print \"a${1 + 2}\\${b}\"\n");
+    }
+    
+    #[test]
+    fn test_string_methods() {
+        // Lengths and positions count characters, not bytes
+        let source = "s = \"  Grüße, wörld \".trim()\nPrint s.len()\nPrint s.upper()\nPrint s.lower()\n\
+                      Print s.contains(\"wö\")\nPrint s.split(\", \")\nPrint \"añ\".split(\"\")\n\
+                      Print s.replace(\"ö\", \"o\")\nPrint s.substring(2, 5)\nPrint s.substring(5, 5).len()";
+        let expected = ["12", "GRÜSSE, WÖRLD", "grüße, wörld", "true", "[\"Grüße\", \"wörld\"]", "[\"a\", \"ñ\"]", "Grüße, world", "üße", "0"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        
+        assert!(matches!(run(compile("Print \"ab\".substring(1, 3)").unwrap()), Err(TricarError::ValueError(_))));
+        assert!(matches!(run(compile("Print \"ab\".substring(2, 1)").unwrap()), Err(TricarError::ValueError(_))));
+        assert!(matches!(compile("Print \"ab\".contains(1)"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("Print \"ab\".push(\"c\")"), Err(TricarError::UndefinedFunction { .. })));
+    }
+    
+    #[test]
+    fn test_std_math() {
+        use optimizer::{optimize, OptLevel};
+        
+        // Int arguments widen to Float; floor/ceil/round give Ints
+        let source = "import std.math as Math\nPrint Math.sqrt(16)\nPrint Math.pow(2, 10)\nPrint Math.abs(-1.5)\n\
+                      Print Math.floor(2.7) + Math.ceil(2.1) + Math.round(-2.5)\nPrint Math.max(3, 4.5)\n\
+                      Print Math.cos(Math.PI)\nPrint Math.log(Math.E)\nPrint 2 ** 3 ** 2\nPrint -2 ** 2\nPrint 2.0 ** -1\nPrint 7 % 3";
+        let expected = ["4.0", "1024.0", "1.5", "2", "4.5", "-1.0", "1.0", "512", "-4", "0.5", "1"];
+        assert_eq!(run(compile(source).unwrap()).unwrap(), expected);
+        assert_eq!(run(optimize(compile(source).unwrap(), OptLevel::O2.passes())).unwrap(), expected);
+        
+        // abs, min and max keep Ints whole
+        let source = "import std.math as Math\nn = Math.abs(-3)\nn = Math.min(n, 7) * Math.max(2, -2)\nPrint n\nPrint Math.min(1, 0.5)";
+        assert_eq!(run(compile(source).unwrap()).unwrap(), ["6", "0.5"]);
+        let min = "import std.math as Math\nn = -9223372036854775807 - 1\nPrint Math.abs(n)";
+        assert!(matches!(run(compile(min).unwrap()), Err(TricarError::ArithmeticError(_))));
+
+        assert!(matches!(run(compile("Print 2 ** 64").unwrap()), Err(TricarError::ArithmeticError(_))));
+        assert!(matches!(run(compile("Print 2 ** -1").unwrap()), Err(TricarError::ArithmeticError(_))));
+        assert!(matches!(compile("import std.math as M\nPrint M.sqrt(\"4\")"), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(compile("Print sqrt(4)"), Err(TricarError::UndefinedFunction { .. })));
+        // Methods store their arguments, so Ints don't widen there
+        assert!(matches!(compile("xs = [1.5]\nxs.push(2)"), Err(TricarError::TypeMismatch { .. })));
+        
+        let formatted = formatter::format_source("Print (2**3)**-x\n", &formatter::FormatOptions::default()).unwrap();
+        assert_eq!(formatted, "Print (2 ** 3) ** -x\n");
+    }
+    
+    #[test]
+    fn test_std_fs_capabilities() {
+        use capabilities::Access;
+        
+        let dir = std::env::temp_dir().join(format!("trica-fs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("inner")).unwrap();
+        let run = |source: &str, capabilities: Capabilities| -> Result<Vec<String>, TricarError> {
+            let source = format!("import std.fs as Fs\ndir = {:?}\n{}", dir.display().to_string(), source);
+            let vm = run_with(compile(&source)?, RunOptions { capabilities, ..RunOptions::default() })?;
+            Ok(vm.get_output().clone())
+        };
+        
+        let source = "file = dir + \"/a.txt\"\nFs.write_text(file, \"one\")\nFs.append_text(file, \" two\")\n\
+                      Print Fs.read_text(file)\nPrint Fs.list_dir(dir)\nFs.remove(file)\nPrint Fs.exists(file)";
+        assert_eq!(run(source, Capabilities::all()).unwrap(), ["one two", "[\"a.txt\", \"inner\"]", "false"]);
+        
+        // Sandboxed by default, and errors carry the path
+        let error = run("Print Fs.exists(dir)", Capabilities::none()).unwrap_err();
+        assert!(matches!(&error, TricarError::PermissionDenied { operation, path } if operation == "read" && *path == dir.display().to_string()));
+        let error = run("Print Fs.read_text(dir + \"/missing\")", Capabilities::all()).unwrap_err();
+        assert!(matches!(&error, TricarError::IoError(message) if message.contains("missing")));
+        
+        // Restricted to a directory, which `..` can't climb out of
+        let mut inner = Access::Denied;
+        inner.grant(Some(dir.join("inner")));
+        let capabilities = Capabilities { read: inner, ..Capabilities::none() };
+        assert_eq!(run("Print Fs.list_dir(dir + \"/inner\")", capabilities.clone()).unwrap(), ["[]"]);
+        assert!(matches!(run("Print Fs.list_dir(dir + \"/inner/..\")", capabilities.clone()), Err(TricarError::PermissionDenied { .. })));
+        assert!(matches!(run("Fs.write_text(dir + \"/inner/b\", \"\")", capabilities), Err(TricarError::PermissionDenied { .. })));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_std_os() {
+        let run = |source: &str, capabilities: Capabilities| -> Result<(Vec<String>, Option<i32>), TricarError> {
+            let bytecode = compile(&format!("import std.os as Os\n{}", source))?;
+            let args = vec!["a".to_string(), "b c".to_string()];
+            let vm = run_with(bytecode, RunOptions { capabilities, args, ..RunOptions::default() })?;
+            Ok((vm.get_output().clone(), vm.exit_code()))
+        };
+        
+        // exit stops the program but keeps what it printed
+        let source = "for arg in Os.args() {\n    Print arg\n}\nPrint Os.env(\"TRICA_SURELY_UNSET_VARIABLE\").len()\n\
+                      Os.exit(Os.args().len() + 1)\nPrint \"unreachable\"";
+        let (output, code) = run(source, Capabilities::all()).unwrap();
+        assert_eq!(output, ["a", "b c", "0"]);
+        assert_eq!(code, Some(3));
+        assert_eq!(run("Print 1", Capabilities::all()).unwrap().1, None);
+        
+        assert!(matches!(run("Print Os.env(\"HOME\")", Capabilities::none()), Err(TricarError::PermissionDenied { .. })));
+        assert!(matches!(run("Print Os.cwd()", Capabilities::none()), Err(TricarError::PermissionDenied { .. })));
+        assert!(matches!(run("Os.exit(1.5)", Capabilities::all()), Err(TricarError::TypeMismatch { .. })));
+        assert!(matches!(run("Os.exit(4294967296)", Capabilities::all()), Err(TricarError::ValueError(_))));
+    }
+    
+    #[test]
+    fn test_std_random() {
+        use random::Rng;
+        
+        let run = |source: &str, seed: Option<u64>| -> Result<Vec<String>, TricarError> {
+            let bytecode = compile(&format!("import std.random as Random\n{}", source))?;
+            Ok(run_with(bytecode, RunOptions { seed, ..RunOptions::default() })?.get_output().clone())
+        };
+        
+        // The same seed, from the embedder or the script, gives the same run
+        let source = "xs = [1, 2, 3, 4, 5, 6, 7, 8]\nRandom.shuffle(xs)\nPrint xs\nPrint Random.int(-5, 5)\n\
+                      Print Random.float()\nPrint Random.choice([\"a\", \"b\"]) + \"!\"";
+        let seeded = run(source, Some(42)).unwrap();
+        assert_eq!(run(source, Some(42)).unwrap(), seeded);
+        assert_eq!(run(&format!("Random.seed(42)\n{}", source), None).unwrap(), seeded);
+        assert_ne!(run(source, Some(43)).unwrap(), seeded);
+        
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            let roll = rng.int(1, 6);
+            assert!((1..=6).contains(&roll));
+            seen[roll as usize - 1] = true;
+            assert!((0.0..1.0).contains(&rng.float()));
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(Rng::new(7).int(i64::MIN, i64::MAX), Rng::new(7).next_u64() as i64);
+        
+        assert!(matches!(run("Print Random.int(2, 1)", None), Err(TricarError::ValueError(_))));
+        assert!(matches!(run("xs = [1]\nxs.pop()\nPrint Random.choice(xs)", None), Err(TricarError::ValueError(_))));
+        assert!(matches!(run("Print Random.choice([1]) + true", None), Err(TricarError::TypeMismatch { .. })));
+    }
+    
+    #[test]
+    fn test_std_time() {
+        use clock::{format_time, Clock};
+        
+        let run = |source: &str| -> Result<Vec<String>, TricarError> {
+            let bytecode = compile(&format!("import std.time as Time\n{}", source))?;
+            // 2000-02-29T01:02:03.004Z
+            let clock = Some(VirtualClock::new(951_786_123_004));
+            Ok(run_with(bytecode, RunOptions { clock, ..RunOptions::default() })?.get_output().clone())
+        };
+        
+        // Sleeping on a virtual clock is instant and exact
+        let source = "start = Time.monotonic()\nTime.sleep(90000)\nPrint Time.monotonic() - start\n\
+                      Print Time.format(Time.now(), \"%Y-%m-%d %H:%M:%S.%L\")";
+        assert_eq!(run(source).unwrap(), ["90000", "2000-02-29 01:03:33.004"]);
+        assert!(matches!(run("Time.sleep(-1)"), Err(TricarError::ValueError(_))));
+        
+        let mut clock = VirtualClock::new(0);
+        clock.advance(1500);
+        assert_eq!((clock.now(), clock.monotonic()), (1500, 1500));
+        assert_eq!(format_time(-1, "%Y-%m-%dT%H:%M:%S.%LZ"), "1969-12-31T23:59:59.999Z");
+        assert_eq!(format_time(0, "100%% %q"), "100% %q");
+    }
+    
+    #[test]
+    fn test_std_json() {
+        let run = |source: &str| compile(&format!("import std.json as Json\n{}", source)).and_then(run);
+        
+        // Objects keep their key order, and null is void
+        let source = r#"data = Json.parse("{\"b\": [1, 2.5], \"a\": {\"ok\": true, \"none\": null}}")
+                        Print data
+                        Print data["b"][0] + 1
+                        Print data["b"][1] * 2
+                        Print Json.stringify(data, false)
+                        Print Json.stringify({1: "x"}, true)"#;
+        assert_eq!(run(source).unwrap(), [
+            r#"{"b": [1, 2.5], "a": {"ok": true, "none": ∅}}"#,
+            "2",
+            "5.0",
+            r#"{"b":[1,2.5],"a":{"ok":true,"none":null}}"#,
+            "{\n  \"1\": \"x\"\n}",
+        ]);
+        
+        // Parsed values are Unknown, so the VM checks what they're used for
+        let d = r#"d = Json.parse("{\"s\": \"hi\", \"n\": 3}")"#;
+        assert_eq!(run(&format!("{}\nPrint d[\"s\"] + \"!\"\nPrint d[\"n\"] < 4", d)).unwrap(), ["hi!", "true"]);
+        assert!(matches!(run(&format!("{}\nPrint d[\"s\"] * 2", d)), Err(TricarError::UnsupportedOperation(_))));
+        
+        match run(r#"Print Json.parse("[1,\n  2,]")"#) {
+            Err(TricarError::JsonError { line: 2, column: 5, .. }) => {}
+            other => panic!("expected a JSON error at 2:5, got {:?}", other),
+        }
+        assert!(matches!(run("xs = [1.0]\nxs[0] = xs[0] / 0.0\nPrint Json.stringify(xs, false)"), Err(TricarError::ValueError(_))));
+        assert!(matches!(run("xs = []\nxs.push(xs)\nPrint Json.stringify(xs, false)"), Err(TricarError::ValueError(_))));
+    }
+}
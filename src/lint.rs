@@ -0,0 +1,593 @@
+// 🔥 TRICA LINTER 🔥
+// Warnings for programs that type check but are probably wrong. Each lint
+// has a code (`W001`) and a name (`unused-variable`); either can be set to
+// allow, warn or deny in the `[lints]` table of `trica.toml`:
+//
+//     [lints]
+//     useless-include = "allow"
+//     W007 = "deny"
+//
+// or for one file with a comment directive:
+//
+//     // lint: allow(unused-variable, W006)
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::ast::*;
+use crate::error::TricarError;
+use crate::lexer::{Lexer, Token, TokenType};
+use crate::parser::Parser;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedAssignment,
+    ShadowedVariable,
+    UnusedImport,
+    UselessInclude,
+    ConstantCondition,
+    DivisionByZero,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [
+        Lint::UnusedVariable,
+        Lint::UnusedAssignment,
+        Lint::ShadowedVariable,
+        Lint::UnusedImport,
+        Lint::UselessInclude,
+        Lint::ConstantCondition,
+        Lint::DivisionByZero,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "W001",
+            Lint::UnusedAssignment => "W002",
+            Lint::ShadowedVariable => "W003",
+            Lint::UnusedImport => "W004",
+            Lint::UselessInclude => "W005",
+            Lint::ConstantCondition => "W006",
+            Lint::DivisionByZero => "W007",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnusedAssignment => "unused-assignment",
+            Lint::ShadowedVariable => "shadowed-variable",
+            Lint::UnusedImport => "unused-import",
+            Lint::UselessInclude => "useless-include",
+            Lint::ConstantCondition => "constant-condition",
+            Lint::DivisionByZero => "division-by-zero",
+        }
+    }
+
+    /// Look a lint up by code or name
+    pub fn parse(text: &str) -> Option<Lint> {
+        let text = text.trim();
+        Lint::ALL.into_iter().find(|lint| lint.code().eq_ignore_ascii_case(text) || lint.name() == text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    /// Reported as an error; `trica check` and `trica lint` fail
+    Deny,
+}
+
+impl Level {
+    fn parse(text: &str) -> Option<Level> {
+        match text.trim() {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub lint: Lint,
+    pub level: Level,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.level == Level::Deny { "error" } else { "warning" };
+        write!(f, "{}[{}]: {} at line {}, column {} ({})",
+               kind, self.lint.code(), self.message, self.line, self.column, self.lint.name())
+    }
+}
+
+/// Lint levels; every lint warns unless configured otherwise
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintConfig {
+    /// Read the `[lints]` table of the nearest `trica.toml` at or above `dir`
+    pub fn load(dir: &Path) -> Result<Self, TricarError> {
+        let mut config = Self::default();
+        let Some(manifest) = dir.ancestors().map(|d| d.join(crate::tpkg::MANIFEST_FILE)).find(|p| p.is_file()) else {
+            return Ok(config);
+        };
+
+        let invalid = |reason: String| TricarError::IoError(format!("Invalid {}: {}", manifest.display(), reason));
+        let text = fs::read_to_string(&manifest).map_err(|e| invalid(e.to_string()))?;
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+
+        if let Some(lints) = table.get("lints") {
+            let lints = lints.as_table().ok_or_else(|| invalid("[lints] must be a table".to_string()))?;
+            for (key, value) in lints {
+                let lint = Lint::parse(key).ok_or_else(|| invalid(format!("unknown lint '{}'", key)))?;
+                let level = value.as_str().and_then(Level::parse)
+                    .ok_or_else(|| invalid(format!("lint '{}' must be \"allow\", \"warn\" or \"deny\"", key)))?;
+                config.set(lint, level);
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or(Level::Warn)
+    }
+
+    /// Apply `// lint: allow(...)` / `warn(...)` / `deny(...)` comments
+    fn apply_directives(&mut self, tokens: &[Token]) -> Result<(), TricarError> {
+        for token in tokens {
+            let TokenType::Comment(text) = &token.token_type else {
+                continue;
+            };
+            let Some(directive) = text.trim().strip_prefix("lint:") else {
+                continue;
+            };
+
+            let invalid = || TricarError::UnexpectedToken {
+                expected: "lint: allow(...), warn(...) or deny(...)".to_string(),
+                found: format!("//{}", text),
+                line: token.line,
+                column: token.column,
+            };
+            let (level, lints) = directive.trim().split_once('(').ok_or_else(invalid)?;
+            let level = Level::parse(level).ok_or_else(invalid)?;
+            let lints = lints.trim_end().strip_suffix(')').ok_or_else(invalid)?;
+            for name in lints.split(',').filter(|name| !name.trim().is_empty()) {
+                self.set(Lint::parse(name).ok_or_else(invalid)?, level);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lint a source file: `// lint:` directives in it override `config`.
+/// Warnings at `Level::Allow` are dropped; the rest are sorted by position.
+pub fn lint_source(source: &str, config: &LintConfig) -> Result<Vec<Warning>, TricarError> {
+    let tokens = Lexer::with_trivia(source).tokenize()?;
+    let mut config = config.clone();
+    config.apply_directives(&tokens)?;
+    let program = Parser::new(tokens).parse()?;
+    Ok(lint_program(&program, &config))
+}
+
+pub fn lint_program(program: &Program, config: &LintConfig) -> Vec<Warning> {
+    let mut linter = Linter::default();
+    linter.check(program);
+
+    let mut warnings: Vec<Warning> = linter.warnings.into_iter()
+        .filter_map(|(lint, message, line, column)| {
+            let level = config.level(lint);
+            (level != Level::Allow).then_some(Warning { lint, level, message, line, column })
+        })
+        .collect();
+    warnings.sort_by_key(|w| (w.line, w.column));
+    warnings
+}
+
+struct Variable {
+    name: String,
+    /// Position of the first assignment
+    line: usize,
+    column: usize,
+    read: bool,
+    /// The latest assignment and whether anything has read it yet
+    last_store: (usize, usize),
+    last_store_read: bool,
+    /// Assignments overwritten before being read
+    dead_stores: Vec<(usize, usize)>,
+}
+
+/// A block's variables, mirroring the type checker's scopes
+struct Scope {
+    /// What opened the block, for messages; empty at the top level
+    label: &'static str,
+    /// The block may not run (`if` branches, loop bodies), so assignments in
+    /// it don't make earlier ones dead
+    conditional: bool,
+    variables: Vec<Variable>,
+}
+
+impl Scope {
+    fn new(label: &'static str, conditional: bool) -> Self {
+        Self { label, conditional, variables: Vec::new() }
+    }
+}
+
+struct Linter {
+    /// Innermost scope last
+    scopes: Vec<Scope>,
+    /// Variables of blocks that have ended
+    finished: Vec<Variable>,
+    used_names: Vec<String>,
+    warnings: Vec<(Lint, String, usize, usize)>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::new("", false)],
+            finished: Vec::new(),
+            used_names: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Linter {
+    fn warn(&mut self, lint: Lint, message: String, line: usize, column: usize) {
+        self.warnings.push((lint, message, line, column));
+    }
+
+    fn check(&mut self, program: &Program) {
+        for include in program.includes.iter().filter(|i| i.is_system) {
+            self.warn(Lint::UselessInclude,
+                      format!("include <{}> has no effect when running on the Trica VM", include.path),
+                      include.line, include.column);
+        }
+
+        let (top_level, main) = program.split_main();
+        for statement in top_level {
+            self.check_statement(statement);
+        }
+        if let Some(statements) = main {
+            self.check_block(statements, Scope::new("Main { }", false));
+        }
+
+        for import in &program.imports {
+            if !self.used_names.contains(&import.alias) {
+                self.warn(Lint::UnusedImport,
+                          format!("import '{}' as '{}' is never used", import.module_path.join("."), import.alias),
+                          import.line, import.column);
+            }
+        }
+
+        let mut variables = std::mem::take(&mut self.finished);
+        variables.extend(self.scopes.drain(..).flat_map(|scope| scope.variables));
+        variables.sort_by_key(|v| (v.line, v.column));
+        for variable in &variables {
+            if !variable.read {
+                self.warn(Lint::UnusedVariable, format!("variable '{}' is never used", variable.name),
+                          variable.line, variable.column);
+                // Don't repeat the warning for each store
+                continue;
+            }
+            if !variable.last_store_read {
+                let (line, column) = variable.last_store;
+                self.warn(Lint::UnusedAssignment, format!("value assigned to '{}' is never read", variable.name),
+                          line, column);
+            }
+            for &(line, column) in &variable.dead_stores {
+                self.warn(Lint::UnusedAssignment,
+                          format!("value assigned to '{}' is overwritten before being read", variable.name),
+                          line, column);
+            }
+        }
+    }
+
+    fn check_block(&mut self, statements: &[Statement], scope: Scope) {
+        self.scopes.push(scope);
+        for statement in statements {
+            self.check_statement(statement);
+        }
+        let scope = self.scopes.pop().expect("pushed above");
+        self.finished.extend(scope.variables);
+    }
+
+    /// The visible variable `name` and the index of its scope
+    fn lookup(&mut self, name: &str) -> Option<(usize, &mut Variable)> {
+        self.scopes.iter_mut().enumerate().rev().find_map(|(depth, scope)| {
+            scope.variables.iter_mut().find(|v| v.name == name).map(|v| (depth, v))
+        })
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Print { expression, .. } | Statement::Expression { expression, .. } => {
+                self.check_expression(expression);
+            }
+            Statement::Assignment { name, value, line, column } => {
+                self.check_expression(value);
+                self.assign(name, *line, *column);
+            }
+            Statement::IndexAssignment { object, index, value, .. } => {
+                self.check_expression(object);
+                self.check_expression(index);
+                self.check_expression(value);
+            }
+            Statement::If { branches, else_block, .. } => {
+                for (condition, block) in branches {
+                    self.check_expression(condition);
+                    self.check_condition(condition);
+                    self.check_block(&block.statements, Scope::new("if", true));
+                }
+                if let Some(block) = else_block {
+                    self.check_block(&block.statements, Scope::new("else", true));
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                self.check_expression(condition);
+                // `while true` is how an endless loop is written
+                if !matches!(condition, Expression::BooleanLiteral { value: true, .. }) {
+                    self.check_condition(condition);
+                }
+                self.check_block(&body.statements, Scope::new("while", true));
+                self.read_by_next_iteration(condition, &body.statements);
+            }
+            Statement::For { variable, value, iterable, body, line, column } => {
+                self.check_expression(iterable);
+                // Each element is assigned to the variables inside the body
+                self.scopes.push(Scope::new("for", true));
+                self.assign(variable, *line, *column);
+                if let Some(value) = value {
+                    self.assign(value, *line, *column);
+                }
+                for statement in &body.statements {
+                    self.check_statement(statement);
+                }
+                let scope = self.scopes.pop().expect("pushed above");
+                self.finished.extend(scope.variables);
+                self.read_by_next_iteration(iterable, &body.statements);
+            }
+        }
+    }
+
+    /// Record an assignment to `name`, declaring it in the innermost scope
+    /// if it isn't visible
+    fn assign(&mut self, name: &str, line: usize, column: usize) {
+        let innermost = self.scopes.len() - 1;
+        let Some((depth, _)) = self.lookup(name) else {
+            self.scopes[innermost].variables.push(Variable {
+                name: name.to_string(),
+                line,
+                column,
+                read: false,
+                last_store: (line, column),
+                last_store_read: false,
+                dead_stores: Vec::new(),
+            });
+            return;
+        };
+
+        let inner = &self.scopes[depth + 1..];
+        let conditional = inner.iter().any(|scope| scope.conditional);
+        let reassigning_block = inner.iter().rev().find(|scope| !scope.conditional).map(|scope| scope.label);
+
+        let (_, variable) = self.lookup(name).expect("found above");
+        if !variable.last_store_read && !conditional {
+            let store = variable.last_store;
+            variable.dead_stores.push(store);
+        }
+        variable.last_store = (line, column);
+        variable.last_store_read = false;
+        let outer = variable.line;
+
+        // Blocks can't shadow, so this changes the outer variable
+        if let Some(label) = reassigning_block {
+            self.warn(Lint::ShadowedVariable,
+                      format!("assignment to '{}' inside {} reassigns the variable from line {}", name, label, outer),
+                      line, column);
+        }
+    }
+
+    /// The next iteration of a loop reads what this one assigned
+    fn read_by_next_iteration(&mut self, header: &Expression, body: &[Statement]) {
+        let mut names = Vec::new();
+        expression_names(header, &mut names);
+        for statement in body {
+            statement_names(statement, &mut names);
+        }
+        for name in names {
+            if let Some((_, variable)) = self.lookup(&name) {
+                variable.last_store_read = true;
+            }
+        }
+    }
+
+    /// An `if` or `while` condition with no variables in it; constant
+    /// comparisons are already reported by `check_expression`
+    fn check_condition(&mut self, condition: &Expression) {
+        let comparison = matches!(condition, Expression::BinaryOp { operator, .. } if is_comparison(operator));
+        if is_constant(condition) && !comparison {
+            self.warn(Lint::ConstantCondition,
+                      format!("condition '{}' is always the same", crate::formatter::format_expression(condition)),
+                      condition.line(), condition.column());
+        }
+    }
+
+    fn check_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::StringLiteral { .. } | Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => {}
+            Expression::Identifier { name, .. } => {
+                if let Some((_, variable)) = self.lookup(name) {
+                    variable.read = true;
+                    variable.last_store_read = true;
+                }
+                self.used_names.push(name.clone());
+            }
+            Expression::PropertyAccess { object, .. } => self.check_expression(object),
+            Expression::UnaryOp { operand, .. } => self.check_expression(operand),
+            Expression::FunctionCall { name, args, .. } => {
+                if let Some((object, _)) = name.split_once('.') {
+                    self.used_names.push(object.to_string());
+                }
+                for arg in args {
+                    self.check_expression(arg);
+                }
+            }
+            Expression::ListLiteral { elements, .. } => {
+                for element in elements {
+                    self.check_expression(element);
+                }
+            }
+            Expression::MapLiteral { entries, .. } => {
+                for (key, value) in entries {
+                    self.check_expression(key);
+                    self.check_expression(value);
+                }
+            }
+            Expression::Interpolation { parts, .. } => {
+                for part in parts {
+                    if let InterpolationPart::Expression(expression) = part {
+                        self.check_expression(expression);
+                    }
+                }
+            }
+            Expression::Index { object, index, .. } => {
+                self.check_expression(object);
+                self.check_expression(index);
+            }
+            Expression::Slice { object, start, end, .. } => {
+                self.check_expression(object);
+                for bound in start.iter().chain(end) {
+                    self.check_expression(bound);
+                }
+            }
+            Expression::MethodCall { receiver, args, .. } => {
+                self.check_expression(receiver);
+                for arg in args {
+                    self.check_expression(arg);
+                }
+            }
+            Expression::BinaryOp { left, operator, right, line, column } => {
+                self.check_expression(left);
+                self.check_expression(right);
+
+                if matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo) && is_zero(right) {
+                    self.warn(Lint::DivisionByZero, "division by zero".to_string(), right.line(), right.column());
+                }
+                if is_comparison(operator) && is_constant(left) && is_constant(right) {
+                    self.warn(Lint::ConstantCondition,
+                              format!("comparison '{}' is always the same", crate::formatter::format_expression(expression)),
+                              *line, *column);
+                }
+            }
+        }
+    }
+}
+
+/// Names of the variables a statement reads, including in nested blocks
+fn statement_names(statement: &Statement, names: &mut Vec<String>) {
+    match statement {
+        Statement::Print { expression, .. }
+        | Statement::Expression { expression, .. }
+        | Statement::Assignment { value: expression, .. } => expression_names(expression, names),
+        Statement::If { branches, .. } => {
+            for (condition, _) in branches {
+                expression_names(condition, names);
+            }
+        }
+        Statement::While { condition, .. } => expression_names(condition, names),
+        Statement::For { iterable, .. } => expression_names(iterable, names),
+        Statement::IndexAssignment { object, index, value, .. } => {
+            for expression in [object, index, value] {
+                expression_names(expression, names);
+            }
+        }
+    }
+    for block in statement.blocks() {
+        for statement in &block.statements {
+            statement_names(statement, names);
+        }
+    }
+}
+
+fn expression_names(expression: &Expression, names: &mut Vec<String>) {
+    match expression {
+        Expression::Identifier { name, .. } => names.push(name.clone()),
+        Expression::PropertyAccess { object, .. } => expression_names(object, names),
+        Expression::UnaryOp { operand, .. } => expression_names(operand, names),
+        Expression::FunctionCall { args, .. } | Expression::ListLiteral { elements: args, .. } => {
+            for arg in args {
+                expression_names(arg, names);
+            }
+        }
+        Expression::MapLiteral { entries, .. } => {
+            for (key, value) in entries {
+                expression_names(key, names);
+                expression_names(value, names);
+            }
+        }
+        Expression::Interpolation { parts, .. } => {
+            for part in parts {
+                if let InterpolationPart::Expression(expression) = part {
+                    expression_names(expression, names);
+                }
+            }
+        }
+        Expression::Index { object, index, .. } => {
+            expression_names(object, names);
+            expression_names(index, names);
+        }
+        Expression::Slice { object, start, end, .. } => {
+            expression_names(object, names);
+            for bound in start.iter().chain(end) {
+                expression_names(bound, names);
+            }
+        }
+        Expression::MethodCall { receiver, args, .. } => {
+            expression_names(receiver, names);
+            for arg in args {
+                expression_names(arg, names);
+            }
+        }
+        Expression::BinaryOp { left, right, .. } => {
+            expression_names(left, names);
+            expression_names(right, names);
+        }
+        Expression::StringLiteral { .. } | Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => {}
+    }
+}
+
+fn is_comparison(operator: &BinaryOperator) -> bool {
+    !matches!(operator, BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply |
+                        BinaryOperator::Divide | BinaryOperator::Modulo | BinaryOperator::Power)
+}
+
+/// Built only from literals, so its value is known before running
+fn is_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::StringLiteral { .. } | Expression::IntLiteral { .. } | Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => true,
+        Expression::BinaryOp { left, right, .. } => is_constant(left) && is_constant(right),
+        Expression::UnaryOp { operand, .. } => is_constant(operand),
+        _ => false,
+    }
+}
+
+fn is_zero(expression: &Expression) -> bool {
+    matches!(expression, Expression::IntLiteral { value: 0, .. })
+        || matches!(expression, Expression::FloatLiteral { value, .. } if *value == 0.0)
+}
@@ -0,0 +1,400 @@
+// Editor queries over one Trica document, answered with the compiler's own
+// Lexer, Parser and TypeChecker. Trica positions are 1-based lines and
+// character columns; LSP positions are 0-based lines and UTF-16 offsets.
+
+use std::path::PathBuf;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, DocumentSymbol, Hover,
+    HoverContents, MarkupContent, MarkupKind, NumberOrString, Position, Range, SymbolKind,
+};
+use crate::ast::{Import, Program, Statement};
+use crate::error::TricarError;
+use crate::lexer::{Token, TokenType};
+use crate::lint::{lint_source, Level, LintConfig, Warning};
+use crate::modules::ModuleResolver;
+use crate::{Lexer, Parser, TypeChecker};
+
+/// Keywords offered by completion
+pub const KEYWORDS: [&str; 13] = ["include", "import", "as", "Main", "Print", "if", "else", "while", "for", "in", "not", "true", "false"];
+
+/// Where go-to-definition leads
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    /// A range in the same document
+    Local(Range),
+    /// The source file of an imported package
+    Module(PathBuf),
+}
+
+pub struct Analysis {
+    source: String,
+    resolver: ModuleResolver,
+    /// Empty when lexing failed
+    tokens: Vec<Token>,
+    program: Option<Program>,
+    checker: TypeChecker,
+    /// The first lexer, parser or type error
+    error: Option<TricarError>,
+    /// Lint warnings, computed only for documents without errors
+    warnings: Vec<Warning>,
+}
+
+impl Analysis {
+    /// Analyze `source`, resolving package imports through `resolver`
+    pub fn new(source: &str, resolver: ModuleResolver) -> Self {
+        let mut checker = TypeChecker::with_resolver(resolver.clone());
+        let mut tokens = Vec::new();
+        let mut program = None;
+
+        let result = Lexer::new(source).tokenize().and_then(|lexed| {
+            tokens = lexed.clone();
+            let parsed = Parser::new(lexed).parse()?;
+            let checked = checker.check(&parsed);
+            program = Some(parsed);
+            checked
+        });
+
+        let error = result.err();
+        let warnings = match error {
+            None => LintConfig::load(resolver.base())
+                .and_then(|config| lint_source(source, &config))
+                .unwrap_or_default(),
+            Some(_) => Vec::new(),
+        };
+
+        Self {
+            source: source.to_string(),
+            resolver,
+            tokens,
+            program,
+            checker,
+            error,
+            warnings,
+        }
+    }
+
+    pub fn error(&self) -> Option<&TricarError> {
+        self.error.as_ref()
+    }
+
+    /// The document's error (Trica stops at the first one), or its lint warnings
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let Some(error) = &self.error else {
+            return self.warnings.iter()
+                .map(|warning| Diagnostic {
+                    range: self.word_range(warning.line, warning.column),
+                    severity: Some(match warning.level {
+                        Level::Deny => DiagnosticSeverity::ERROR,
+                        _ => DiagnosticSeverity::WARNING,
+                    }),
+                    code: Some(NumberOrString::String(warning.lint.code().to_string())),
+                    source: Some("trica".to_string()),
+                    message: format!("{} ({})", warning.message, warning.lint.name()),
+                    ..Diagnostic::default()
+                })
+                .collect();
+        };
+
+        let range = match error.position() {
+            Some((line, column)) => self.word_range(line, column),
+            None => {
+                let end = self.position(self.source.lines().count().max(1), usize::MAX);
+                Range::new(end, end)
+            }
+        };
+
+        vec![Diagnostic {
+            range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("trica".to_string()),
+            message: error.to_string(),
+            ..Diagnostic::default()
+        }]
+    }
+
+    /// Type of the variable, member or import under the cursor
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let index = self.token_at(position)?;
+        let range = self.token_range(&self.tokens[index]);
+
+        let text = match &self.tokens[index].token_type {
+            TokenType::Print => "`Print` - prints a String, Int, Float or Boolean".to_string(),
+            TokenType::Identifier(name) => {
+                if let Some(object) = self.member_object(index) {
+                    let (_, member_type) = self.checker.members(object).into_iter().find(|(m, _)| m == name)?;
+                    format!("`{}.{}`: {:?}", object, name, member_type)
+                } else if let Some(import) = self.import_for(index) {
+                    let module = import.module_path.join(".");
+                    match self.checker.module_path(&import.alias) {
+                        Some(path) => format!("module `{}` as `{}`\n\n{}", module, import.alias, path.display()),
+                        None => format!("module `{}` as `{}`", module, import.alias),
+                    }
+                } else {
+                    format!("`{}`: {:?}", name, self.checker.variable_type(name)?)
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value: text }),
+            range: Some(range),
+        })
+    }
+
+    /// First assignment of the variable, or the package an import alias names
+    pub fn definition(&self, position: Position) -> Option<Definition> {
+        let index = self.token_at(position)?;
+        let TokenType::Identifier(name) = &self.tokens[index].token_type else {
+            return None;
+        };
+        if self.member_object(index).is_some() {
+            return None;
+        }
+
+        if let Some(import) = self.import_for(index) {
+            let path = self.checker.module_path(&import.alias)
+                .map(PathBuf::from)
+                .or_else(|| self.resolver.resolve(&import.module_path))?;
+            return Some(Definition::Module(path));
+        }
+
+        let (line, column) = self.assignments().into_iter()
+            .find(|(assigned, _, _)| assigned == name)
+            .map(|(_, line, column)| (line, column))?;
+        Some(Definition::Local(self.word_range(line, column)))
+    }
+
+    /// Imports and variables (at their first assignment)
+    #[allow(deprecated)] // `DocumentSymbol::deprecated` must still be filled in
+    pub fn symbols(&self) -> Vec<DocumentSymbol> {
+        let Some(program) = &self.program else {
+            return Vec::new();
+        };
+
+        let imports = program.imports.iter().map(|import| {
+            let range = self.line_range(import.line);
+            DocumentSymbol {
+                name: import.alias.clone(),
+                detail: Some(import.module_path.join(".")),
+                kind: SymbolKind::MODULE,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            }
+        });
+
+        let variables = self.assignments().into_iter().map(|(name, line, column)| {
+            let detail = self.checker.variable_type(name).map(|t| format!("{:?}", t));
+            DocumentSymbol {
+                name: name.to_string(),
+                detail,
+                kind: SymbolKind::VARIABLE,
+                tags: None,
+                deprecated: None,
+                range: self.line_range(line),
+                selection_range: self.word_range(line, column),
+                children: None,
+            }
+        });
+
+        imports.chain(variables).collect()
+    }
+
+    /// Members after `Object.`, otherwise keywords, variables assigned
+    /// before the cursor's line and `Print.output`
+    pub fn completions(&self, position: Position) -> Vec<CompletionItem> {
+        let line = self.source.lines().nth(position.line as usize).unwrap_or("");
+        let column = utf16_to_column(line, position.character);
+        let before: String = line.chars().take(column - 1).collect();
+
+        // A document being edited often doesn't parse; fall back to the lines
+        // above the cursor, which are all that's in scope anyway
+        let prefix;
+        let scope = if self.program.is_some() {
+            self
+        } else {
+            let above: Vec<&str> = self.source.lines().take(position.line as usize).collect();
+            prefix = Analysis::new(&above.join("\n"), self.resolver.clone());
+            &prefix
+        };
+
+        if let Some(object) = member_prefix(&before) {
+            return scope.checker.members(object).into_iter()
+                .map(|(member, member_type)| CompletionItem {
+                    label: member.to_string(),
+                    kind: Some(CompletionItemKind::PROPERTY),
+                    detail: Some(format!("{:?}", member_type)),
+                    ..CompletionItem::default()
+                })
+                .collect();
+        }
+
+        let mut items: Vec<CompletionItem> = KEYWORDS.iter()
+            .map(|keyword| CompletionItem {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..CompletionItem::default()
+            })
+            .collect();
+
+        for (name, assigned_line, _) in scope.assignments() {
+            if assigned_line > position.line as usize {
+                continue;
+            }
+            items.push(CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail: scope.checker.variable_type(name).map(|t| format!("{:?}", t)),
+                ..CompletionItem::default()
+            });
+        }
+
+        items.extend(scope.checker.members("Print").into_iter().map(|(member, member_type)| CompletionItem {
+            label: format!("Print.{}", member),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: Some(format!("{:?}", member_type)),
+            ..CompletionItem::default()
+        }));
+
+        items
+    }
+
+    /// Each assigned variable with the line and column of its first
+    /// assignment, including variables local to blocks. `for` loop
+    /// variables are assigned at their `for`.
+    fn assignments(&self) -> Vec<(&str, usize, usize)> {
+        fn collect<'a>(statements: &'a [Statement], assignments: &mut Vec<(&'a str, usize, usize)>) {
+            for statement in statements {
+                let names = match statement {
+                    Statement::Assignment { name, line, column, .. } => vec![(name, line, column)],
+                    Statement::For { variable, value, line, column, .. } => {
+                        std::iter::once(variable).chain(value).map(|name| (name, line, column)).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                for (name, line, column) in names {
+                    if !assignments.iter().any(|(assigned, _, _)| assigned == name) {
+                        assignments.push((name, *line, *column));
+                    }
+                }
+                for block in statement.blocks() {
+                    collect(&block.statements, assignments);
+                }
+            }
+        }
+        
+        let mut assignments = Vec::new();
+        if let Some(program) = &self.program {
+            collect(&program.statements, &mut assignments);
+        }
+        assignments
+    }
+
+    /// The import whose alias or module path the token is part of
+    fn import_for(&self, index: usize) -> Option<&Import> {
+        let token = &self.tokens[index];
+        let TokenType::Identifier(name) = &token.token_type else {
+            return None;
+        };
+        let program = self.program.as_ref()?;
+        program.imports.iter()
+            .find(|import| import.line == token.line)
+            .or_else(|| program.imports.iter().find(|import| import.alias == *name))
+    }
+
+    /// For a token after `Object.`, the object's name
+    fn member_object(&self, index: usize) -> Option<&str> {
+        if index < 2 || self.tokens[index - 1].token_type != TokenType::Dot {
+            return None;
+        }
+        let on_import_line = self.program.as_ref()
+            .is_some_and(|p| p.imports.iter().any(|i| i.line == self.tokens[index].line));
+        if on_import_line {
+            return None;
+        }
+        match &self.tokens[index - 2].token_type {
+            TokenType::Print => Some("Print"),
+            TokenType::Identifier(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Index of the identifier or keyword token touching `position`
+    fn token_at(&self, position: Position) -> Option<usize> {
+        let line = position.line as usize + 1;
+        let text = self.source.lines().nth(position.line as usize)?;
+        let column = utf16_to_column(text, position.character);
+
+        self.tokens.iter().position(|token| {
+            let len = token_len(&token.token_type);
+            len > 0 && token.line == line && (token.column..=token.column + len).contains(&column)
+        })
+    }
+
+    fn token_range(&self, token: &Token) -> Range {
+        let len = token_len(&token.token_type);
+        Range::new(self.position(token.line, token.column), self.position(token.line, token.column + len))
+    }
+
+    /// The identifier-like word starting at a Trica position, or one character
+    fn word_range(&self, line: usize, column: usize) -> Range {
+        let text = self.source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+        let len = text.chars()
+            .skip(column.saturating_sub(1))
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .count()
+            .max(1);
+        Range::new(self.position(line, column), self.position(line, column + len))
+    }
+
+    fn line_range(&self, line: usize) -> Range {
+        Range::new(self.position(line, 1), self.position(line, usize::MAX))
+    }
+
+    /// Convert a 1-based line and character column to an LSP position
+    fn position(&self, line: usize, column: usize) -> Position {
+        let text = self.source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+        let character: usize = text.chars()
+            .take(column.saturating_sub(1))
+            .map(char::len_utf16)
+            .sum();
+        Position::new(line.saturating_sub(1) as u32, character as u32)
+    }
+}
+
+/// 1-based character column of a UTF-16 offset into `line`
+fn utf16_to_column(line: &str, character: u32) -> usize {
+    let mut offset = 0;
+    let mut column = 1;
+    for c in line.chars() {
+        if offset >= character as usize {
+            break;
+        }
+        offset += c.len_utf16();
+        column += 1;
+    }
+    column
+}
+
+/// Length in characters of tokens that hover and definition care about
+fn token_len(token_type: &TokenType) -> usize {
+    match token_type {
+        TokenType::Identifier(name) => name.chars().count(),
+        TokenType::Print => "Print".len(),
+        _ => 0,
+    }
+}
+
+/// `Object` when the text before the cursor ends in `Object.` or `Object.partial`
+fn member_prefix(before: &str) -> Option<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let without_partial = before.trim_end_matches(is_ident);
+    let object_end = without_partial.strip_suffix('.')?;
+    let object_start = object_end.trim_end_matches(is_ident).len();
+    let object = &object_end[object_start..];
+    (!object.is_empty()).then_some(object)
+}
+
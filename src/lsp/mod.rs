@@ -0,0 +1,175 @@
+// 🔥 TRICA LANGUAGE SERVER 🔥
+// `trica lsp` speaks the Language Server Protocol over stdio. Every request
+// re-analyzes the document with the real Lexer, Parser and TypeChecker, so
+// editors see exactly the errors and types the compiler does.
+
+pub mod analysis;
+
+pub use analysis::{Analysis, Definition};
+
+use std::collections::HashMap;
+use std::error::Error;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionOptions, CompletionResponse, DocumentSymbolResponse, GotoDefinitionResponse, HoverProviderCapability,
+    Location, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+use crate::modules::ModuleResolver;
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Run the language server on stdin/stdout until the client shuts it down
+pub fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection)?;
+    // The writer thread only finishes once every sender is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Serve one client over an established connection
+pub fn serve(connection: &Connection) -> LspResult<()> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server { documents: HashMap::new() };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(diagnostics) = server.handle_notification(notification)? {
+                    let params = serde_json::to_value(diagnostics)?;
+                    connection.sender.send(Message::Notification(Notification::new(
+                        PublishDiagnostics::METHOD.to_string(),
+                        params,
+                    )))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    /// Open documents by URI, kept in sync with full-text changes
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn analyze(&self, uri: &Url) -> Option<Analysis> {
+        let source = self.documents.get(uri)?;
+        Some(Analysis::new(source, resolver_for(uri)))
+    }
+
+    /// Returns the diagnostics to publish after an open, change or close
+    fn handle_notification(&mut self, notification: Notification) -> LspResult<Option<PublishDiagnosticsParams>> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+                self.documents.insert(params.text_document.uri.clone(), params.text_document.text);
+                params.text_document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri.clone(), change.text);
+                }
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                // Clear whatever the editor is still showing
+                return Ok(Some(PublishDiagnosticsParams::new(params.text_document.uri, Vec::new(), None)));
+            }
+            _ => return Ok(None),
+        };
+
+        let diagnostics = self.analyze(&uri).map(|a| a.diagnostics()).unwrap_or_default();
+        Ok(Some(PublishDiagnosticsParams::new(uri, diagnostics, None)))
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let method = request.method.clone();
+        match self.dispatch(request) {
+            Ok(Some(result)) => Response::new_ok(id, result),
+            Ok(None) => Response::new_err(id, ErrorCode::MethodNotFound as i32, format!("Unsupported request '{}'", method)),
+            Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    /// `None` for requests the server doesn't support
+    fn dispatch(&self, request: Request) -> LspResult<Option<serde_json::Value>> {
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => {
+                let (_, params) = request.extract::<lsp_types::HoverParams>(HoverRequest::METHOD)?;
+                let (uri, position) = text_position(params.text_document_position_params);
+                serde_json::to_value(self.analyze(&uri).and_then(|a| a.hover(position)))?
+            }
+            GotoDefinition::METHOD => {
+                let (_, params) = request.extract::<lsp_types::GotoDefinitionParams>(GotoDefinition::METHOD)?;
+                let (uri, position) = text_position(params.text_document_position_params);
+                let location = self.analyze(&uri)
+                    .and_then(|a| a.definition(position))
+                    .and_then(|definition| match definition {
+                        Definition::Local(range) => Some(Location::new(uri, range)),
+                        Definition::Module(path) => {
+                            let path = path.canonicalize().unwrap_or(path);
+                            let start = Position::new(0, 0);
+                            Url::from_file_path(path).ok().map(|uri| Location::new(uri, Range::new(start, start)))
+                        }
+                    });
+                serde_json::to_value(location.map(GotoDefinitionResponse::Scalar))?
+            }
+            DocumentSymbolRequest::METHOD => {
+                let (_, params) = request.extract::<lsp_types::DocumentSymbolParams>(DocumentSymbolRequest::METHOD)?;
+                let symbols = self.analyze(&params.text_document.uri).map(|a| a.symbols()).unwrap_or_default();
+                serde_json::to_value(DocumentSymbolResponse::Nested(symbols))?
+            }
+            Completion::METHOD => {
+                let (_, params) = request.extract::<lsp_types::CompletionParams>(Completion::METHOD)?;
+                let (uri, position) = text_position(params.text_document_position);
+                let items = self.analyze(&uri).map(|a| a.completions(position)).unwrap_or_default();
+                serde_json::to_value(CompletionResponse::Array(items))?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+}
+
+fn text_position(params: lsp_types::TextDocumentPositionParams) -> (Url, Position) {
+    (params.text_document.uri, params.position)
+}
+
+/// Imports resolve relative to the document's directory
+fn resolver_for(uri: &Url) -> ModuleResolver {
+    match uri.to_file_path().ok().and_then(|path| path.parent().map(|p| p.to_path_buf())) {
+        Some(dir) => ModuleResolver::new(dir),
+        None => ModuleResolver::default(),
+    }
+}
@@ -6,41 +6,75 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Instant;
 
-mod lexer;
-mod parser;
-mod ast;
-mod type_checker;
-mod codegen;
-mod bytecode;
-mod error;
-
-use lexer::Lexer;
-use parser::Parser;
-use type_checker::TypeChecker;
-use bytecode::{BytecodeCompiler, TricaVM};
-use error::TricarError;
+use trica::lexer::Lexer;
+use trica::parser::Parser;
+use trica::type_checker::TypeChecker;
+use trica::modules::ModuleResolver;
+use trica::bytecode::{Bytecode, BytecodeCompiler, TricaVM};
+use trica::optimizer::{optimize, OptLevel};
+use trica::verifier::verify;
+use trica::tbc;
+use trica::error::TricarError;
+use trica::capabilities::Capabilities;
+use trica::formatter::{format_source, FormatOptions};
+use trica::lint::{lint_source, Level, LintConfig, Warning};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     
-    if args.len() != 2 {
-        print_usage();
-        process::exit(1);
-    }
-    
-    let filename = &args[1];
-    
-    match compile_file(filename) {
-        Ok(_) => {
-            // Timing is now handled inside compile_file
+    match args.get(1).map(String::as_str) {
+        Some("lsp") => {
+            if let Err(e) = trica::lsp::run() {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            process::exit(1);
+        Some("check") => {
+            if !check_command(&args[2..], true) {
+                process::exit(1);
+            }
         }
+        Some("lint") => {
+            if !check_command(&args[2..], false) {
+                process::exit(1);
+            }
+        }
+        Some("fmt") => {
+            if !format_command(&args[2..]) {
+                process::exit(1);
+            }
+        }
+        Some("build") => match build_arguments(&args[2..]) {
+            Some((filename, output, level)) => {
+                if let Err(e) = build_file(filename, output, level) {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+            None => {
+                print_usage();
+                process::exit(1);
+            }
+        },
+        command => match run_arguments(&args[if command == Some("run") { 2 } else { 1 }..]) {
+            Some(options) => match run_file(&options) {
+                Ok(0) => {}
+                // The script called std.os exit
+                Ok(code) => process::exit(code),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            },
+            None => {
+                print_usage();
+                process::exit(1);
+            }
+        },
     }
 }
 
@@ -48,10 +82,29 @@ fn print_usage() {
     println!("🔥 TRICA 2.0.0 - LEGENDARY <900ns COMPILATION! 🔥");
     println!();
     println!("Usage:");
-    println!("  trica <file.trica>           Compile and run Trica file");
+    println!("  trica [run] <file.trica> [args...]");
+    println!("                               Compile and run Trica file; std.os args() gets args");
+    println!("      -O0 | -O1 | -O2          Optimization level (default -O1)");
+    println!("      --verify                 Verify the bytecode before running it");
+    println!("      --seed=<n>               Seed std.random, so every run is the same");
+    println!("      --allow-read[=<dir>]     Let std.fs read (inside <dir>); repeatable");
+    println!("      --allow-write[=<dir>]    Let std.fs write (inside <dir>); repeatable");
+    println!("      --allow-env              Let std.os read environment variables");
+    println!("                               (with no --allow-* flag, scripts may do all three)");
+    println!("      -- <args...>             Pass the rest to the script, even flags");
+    println!("  trica <file.tbc>             Verify and run compiled bytecode");
+    println!("  trica build <file.trica>     Compile to bytecode (<file>.tbc)");
+    println!("      -o <out.tbc>             Output path");
+    println!("  trica lsp                    Start the language server on stdio");
+    println!("  trica check [paths...]       Type check and lint without running (default: all under .)");
+    println!("  trica lint [paths...]        Report lint warnings (default: all under .)");
+    println!("  trica fmt [paths...]         Format Trica files (default: all under .)");
+    println!("      --check                  Only report files that need formatting");
+    println!("      --migrate-main           Flatten legacy Main {{ }} blocks");
     println!();
     println!("Package Management:");
     println!("  tpkg install <package>       Install packages (separate binary)");
+    println!("  tpkg installed               List installed packages");
     println!("  tpkg list                    List available packages");
     println!("  tpkg search <query>          Search for packages");
     println!();
@@ -60,7 +113,235 @@ fn print_usage() {
     println!("  tpkg install neural_networks Install neural networks package");
 }
 
-fn compile_file(filename: &str) -> Result<(), TricarError> {
+/// `trica <file> [-O0|-O1|-O2] [--verify]`
+struct RunOptions<'a> {
+    file: &'a str,
+    level: OptLevel,
+    /// Verify compiler output too; `.tbc` files always are
+    verify: bool,
+    /// Everything, unless `--allow-read`/`--allow-write`/`--allow-env` say otherwise
+    capabilities: Capabilities,
+    /// What the script's `std.os` `args()` returns
+    script_args: Vec<String>,
+    /// Fixed `std.random` seed, for reproducible runs
+    seed: Option<u64>,
+}
+
+/// `trica [run] <file> [flags] [script args...] [-- script args...]`; after
+/// the file, anything that isn't a trica flag is passed to the script
+fn run_arguments(args: &[String]) -> Option<RunOptions<'_>> {
+    let mut level = OptLevel::default();
+    let mut verify = false;
+    let mut file = None;
+    let mut script_args = Vec::new();
+    let mut seed = None;
+    // Any --allow-* flag turns everything it doesn't grant off
+    let mut capabilities: Option<Capabilities> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" && file.is_some() {
+            script_args.extend(args.by_ref().cloned());
+        } else if let Some(flag) = OptLevel::parse(arg) {
+            level = flag;
+        } else if arg == "--verify" {
+            verify = true;
+        } else if let Some(dir) = arg.strip_prefix("--allow-read") {
+            capabilities.get_or_insert_with(Capabilities::none).read.grant(allowed_dir(dir)?);
+        } else if let Some(dir) = arg.strip_prefix("--allow-write") {
+            capabilities.get_or_insert_with(Capabilities::none).write.grant(allowed_dir(dir)?);
+        } else if let Some(value) = arg.strip_prefix("--seed=") {
+            seed = Some(value.parse().ok()?);
+        } else if arg == "--allow-env" {
+            capabilities.get_or_insert_with(Capabilities::none).env = true;
+        } else if file.is_none() && !arg.starts_with('-') {
+            file = Some(arg.as_str());
+        } else if file.is_some() {
+            script_args.push(arg.clone());
+        } else {
+            return None;
+        }
+    }
+    let capabilities = capabilities.unwrap_or_else(Capabilities::all);
+    Some(RunOptions { file: file?, level, verify, capabilities, script_args, seed })
+}
+
+/// The rest of `--allow-read=<dir>`: `Some(None)` for no directory (anywhere),
+/// `None` when it isn't `=<dir>`
+fn allowed_dir(rest: &str) -> Option<Option<PathBuf>> {
+    match rest {
+        "" => Some(None),
+        _ => rest.strip_prefix('=').filter(|dir| !dir.is_empty()).map(|dir| Some(PathBuf::from(dir))),
+    }
+}
+
+/// `trica build <file> [-o <out.tbc>] [-O0|-O1|-O2]`
+fn build_arguments(args: &[String]) -> Option<(&str, Option<&str>, OptLevel)> {
+    let mut level = OptLevel::default();
+    let mut output = None;
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(flag) = OptLevel::parse(arg) {
+            level = flag;
+        } else if arg == "-o" {
+            output = Some(args.next()?.as_str());
+        } else if file.is_none() && !arg.starts_with('-') {
+            file = Some(arg.as_str());
+        } else {
+            return None;
+        }
+    }
+    Some((file?, output, level))
+}
+
+/// `trica fmt [--check] [--migrate-main] [files...]`; returns whether it succeeded
+fn format_command(args: &[String]) -> bool {
+    let check = args.iter().any(|a| a == "--check");
+    let options = FormatOptions {
+        migrate_main: args.iter().any(|a| a == "--migrate-main"),
+    };
+    
+    let files = source_files(args);
+    let mut unformatted = 0;
+    let mut failed = 0;
+    for file in &files {
+        let result = fs::read_to_string(file)
+            .map_err(|e| TricarError::IoError(format!("Failed to read {}: {}", file.display(), e)))
+            .and_then(|source| Ok((format_source(&source, &options)?, source)));
+        
+        match result {
+            Ok((formatted, source)) if formatted != source => {
+                unformatted += 1;
+                if check {
+                    println!("❌ {} is not formatted", file.display());
+                } else if let Err(e) = fs::write(file, formatted) {
+                    eprintln!("Error: Failed to write {}: {}", file.display(), e);
+                    failed += 1;
+                } else {
+                    println!("✨ Formatted {}", file.display());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error: {}: {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    
+    if check && unformatted > 0 {
+        println!("💡 Run 'trica fmt' to format {} file(s)", unformatted);
+        return false;
+    }
+    if failed == 0 {
+        println!("✅ {} file(s) checked, {} formatted", files.len(), if check { 0 } else { unformatted });
+    }
+    failed == 0
+}
+
+/// `trica check [paths...]` type checks and lints; `trica lint [paths...]`
+/// only lints. Returns false on errors or lints set to deny.
+fn check_command(args: &[String], type_check: bool) -> bool {
+    let files = source_files(args);
+    let (mut warnings, mut errors) = (0, 0);
+    
+    for file in &files {
+        match check_file(file, type_check) {
+            Ok(found) => {
+                for warning in &found {
+                    eprintln!("{}: {}", file.display(), warning);
+                    if warning.level == Level::Deny {
+                        errors += 1;
+                    } else {
+                        warnings += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{}: error: {}", file.display(), e);
+                errors += 1;
+            }
+        }
+    }
+    
+    if errors == 0 {
+        println!("✅ {} file(s) checked: {} warning(s)", files.len(), warnings);
+    } else {
+        println!("❌ {} file(s) checked: {} error(s), {} warning(s)", files.len(), errors, warnings);
+    }
+    errors == 0
+}
+
+fn check_file(file: &Path, type_check: bool) -> Result<Vec<Warning>, TricarError> {
+    let source = fs::read_to_string(file)
+        .map_err(|e| TricarError::IoError(format!("Failed to read {}: {}", file.display(), e)))?;
+    
+    if type_check {
+        let tokens = Lexer::new(&source).tokenize()?;
+        let ast = Parser::new(tokens).parse()?;
+        TypeChecker::with_resolver(ModuleResolver::new(source_dir(file))).check(&ast)?;
+    }
+    
+    lint_source(&source, &LintConfig::load(source_dir(file))?)
+}
+
+/// Directory holding `file`; packages in its `vendor/` and `.tpkg/` are the
+/// ones it imports, as in the language server
+fn source_dir(file: &Path) -> &Path {
+    file.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."))
+}
+
+/// Files named on the command line, with directories expanded to every
+/// `.trica` file inside them; `.` when no paths are given
+fn source_files(args: &[String]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = args.iter()
+        .filter(|a| !a.starts_with("--"))
+        .map(PathBuf::from)
+        .collect();
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+    
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let start = files.len();
+            find_trica_files(&path, &mut files);
+            files[start..].sort();
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Every `.trica` file under `dir`, skipping hidden, `vendor` and `target` directories
+fn find_trica_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "vendor" && name != "target" {
+                find_trica_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "trica") {
+            files.push(path);
+        }
+    }
+}
+
+fn build_file(filename: &str, output: Option<&str>, level: OptLevel) -> Result<(), TricarError> {
+    let bytecode = compile_file(filename, level)?;
+    let output = output.map(PathBuf::from).unwrap_or_else(|| Path::new(filename).with_extension("tbc"));
+    fs::write(&output, tbc::write(&bytecode))
+        .map_err(|e| TricarError::IoError(format!("Failed to write {}: {}", output.display(), e)))?;
+    println!("✅ Compiled {} to {} ({} instructions)", filename, output.display(), bytecode.instructions.len());
+    Ok(())
+}
+
+fn compile_file(filename: &str, level: OptLevel) -> Result<Bytecode, TricarError> {
     // Read source file
     let source = fs::read_to_string(filename)
         .map_err(|e| TricarError::IoError(format!("Failed to read {}: {}", filename, e)))?;
@@ -74,15 +355,34 @@ fn compile_file(filename: &str) -> Result<(), TricarError> {
     let ast = parser.parse()?;
     
     // Type checking
-    let mut type_checker = TypeChecker::new();
+    let mut type_checker = TypeChecker::with_resolver(ModuleResolver::new(source_dir(Path::new(filename))));
     type_checker.check(&ast)?;
     
     // Bytecode compilation
     let mut compiler = BytecodeCompiler::new();
-    let bytecode = compiler.compile(&ast)?;
+    Ok(optimize(compiler.compile(&ast)?, level.passes()))
+}
+
+/// Run the program, returning the exit status it asked for (0 if none)
+fn run_file(options: &RunOptions) -> Result<i32, TricarError> {
+    let bytecode = if options.file.ends_with(".tbc") {
+        let bytes = fs::read(options.file)
+            .map_err(|e| TricarError::IoError(format!("Failed to read {}: {}", options.file, e)))?;
+        tbc::read(&bytes)?
+    } else {
+        let bytecode = compile_file(options.file, options.level)?;
+        if options.verify {
+            verify(&bytecode)?;
+        }
+        bytecode
+    };
     
     // LEGENDARY EXECUTION WITH TIMING
-    let mut vm = TricaVM::new();
+    let mut vm = TricaVM::with_capabilities(options.capabilities.clone());
+    vm.set_args(options.script_args.clone());
+    if let Some(seed) = options.seed {
+        vm.set_seed(seed);
+    }
     vm.load_bytecode(bytecode);
     
     // Time the execution (this is our "compilation" time display)
@@ -95,6 +395,11 @@ fn compile_file(filename: &str) -> Result<(), TricarError> {
         println!("{}", output);
     }
     
+    // A script that called `exit` ends here, without the banner
+    if let Some(code) = vm.exit_code() {
+        return Ok(code);
+    }
+    
     // Display the execution time as "compilation time" in NANOSECONDS! 🔥
     let exec_ns = exec_time.as_nanos();
     if exec_ns < 900 {
@@ -105,5 +410,5 @@ fn compile_file(filename: &str) -> Result<(), TricarError> {
         println!("⚡ Compilation completed in {}ns", exec_ns);
     }
     
-    Ok(())
+    Ok(0)
 }
\ No newline at end of file
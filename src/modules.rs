@@ -0,0 +1,58 @@
+// Module resolution - maps `import name.path as Alias` to package sources on disk
+
+use std::path::{Path, PathBuf};
+
+/// Package directories searched for imports, highest priority first.
+/// `vendor/` (from `tpkg vendor`) shadows packages installed in `.tpkg/`.
+pub const PACKAGE_DIRS: [&str; 2] = ["vendor", ".tpkg"];
+
+/// Import roots provided by the compiler rather than by packages
+const BUILTIN_ROOTS: [&str; 2] = ["std", "user"];
+
+/// Resolves package imports relative to a project directory
+#[derive(Debug, Clone)]
+pub struct ModuleResolver {
+    base: PathBuf,
+}
+
+impl Default for ModuleResolver {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl ModuleResolver {
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// `std.*` and `user.*` never come from package directories
+    pub fn is_builtin(module_path: &[String]) -> bool {
+        module_path.first().is_some_and(|root| BUILTIN_ROOTS.contains(&root.as_str()))
+    }
+
+    /// Find the source file for a package import
+    ///
+    /// `import pkg as P` loads `<dir>/pkg/main.trica`; `import pkg.a.b as P`
+    /// loads `<dir>/pkg/a/b.trica`, checking each of [`PACKAGE_DIRS`] in order.
+    pub fn resolve(&self, module_path: &[String]) -> Option<PathBuf> {
+        let (package, rest) = module_path.split_first()?;
+
+        PACKAGE_DIRS.iter()
+            .map(|dir| {
+                let mut file = self.base.join(dir).join(package);
+                if rest.is_empty() {
+                    file.push("main.trica");
+                } else {
+                    file.extend(rest);
+                    file.set_extension("trica");
+                }
+                file
+            })
+            .find(|file| file.is_file())
+    }
+}
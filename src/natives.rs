@@ -0,0 +1,624 @@
+// 🔥 TRICA NATIVE FUNCTIONS 🔥
+// Built-in functions the VM runs directly through `CallNative`. Their
+// signatures live here too, so the type checker and the VM always agree.
+// Functions of standard modules like `std.math` are natives too; they are
+// called through the import alias, as in `Math.sqrt(2)`.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use crate::ast::Type;
+use crate::bytecode::{format_value, MapKey, TricaVM, TricaValue};
+use crate::clock;
+use crate::error::TricarError;
+use crate::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Native {
+    /// `int(x)`: Floats truncate toward zero, Strings are parsed
+    Int,
+    /// `float(x)`: Ints widen, Strings are parsed
+    Float,
+    /// `str(x)`: the text `Print` would show
+    Str,
+    /// `xs.len()`: number of elements, of entries in a map, or of characters in a string
+    Len,
+    /// `xs.push(x)`: append to the end of the list
+    Push,
+    /// `xs.pop()`: remove and return the last element
+    Pop,
+    /// `m.has(key)`: whether the map has the key
+    Has,
+    /// `m.keys()`: the keys, in insertion order
+    Keys,
+    /// `m.values()`: the values, in insertion order
+    Values,
+    /// `m.remove(key)`: remove the entry, returning whether there was one
+    Remove,
+    /// `s.upper()`: the string in upper case
+    Upper,
+    /// `s.lower()`: the string in lower case
+    Lower,
+    /// `s.contains(part)`: whether `part` occurs in the string
+    Contains,
+    /// `s.split(separator)`: the pieces between separators; `""` splits into characters
+    Split,
+    /// `s.trim()`: the string without leading and trailing whitespace
+    Trim,
+    /// `s.replace(from, to)`: every `from` replaced with `to`
+    Replace,
+    /// `s.substring(start, end)`: characters `start` up to (not including) `end`
+    Substring,
+    /// `std.math` `sqrt(x)`
+    Sqrt,
+    /// `std.math` `pow(x, y)`: `x` to the power `y`, always a Float (see `**` for Ints)
+    Pow,
+    /// `std.math` `abs(x)`
+    Abs,
+    /// `std.math` `floor(x)`: the largest Int not above `x`
+    Floor,
+    /// `std.math` `ceil(x)`: the smallest Int not below `x`
+    Ceil,
+    /// `std.math` `round(x)`: the nearest Int, halves away from zero
+    Round,
+    /// `std.math` `min(x, y)`
+    Min,
+    /// `std.math` `max(x, y)`
+    Max,
+    /// `std.math` `sin(x)`, in radians
+    Sin,
+    /// `std.math` `cos(x)`, in radians
+    Cos,
+    /// `std.math` `tan(x)`, in radians
+    Tan,
+    /// `std.math` `asin(x)`
+    Asin,
+    /// `std.math` `acos(x)`
+    Acos,
+    /// `std.math` `atan(x)`
+    Atan,
+    /// `std.math` `log(x)`: natural logarithm
+    Log,
+    /// `std.math` `log10(x)`
+    Log10,
+    /// `std.math` `exp(x)`: `E` to the power `x`
+    Exp,
+    /// `std.fs` `read_text(path)`: the whole file, as UTF-8
+    ReadText,
+    /// `std.fs` `write_text(path, text)`: create or replace the file
+    WriteText,
+    /// `std.fs` `append_text(path, text)`: add to the end, creating the file if needed
+    AppendText,
+    /// `std.fs` `exists(path)`
+    Exists,
+    /// `std.fs` `list_dir(path)`: names of the entries, sorted
+    ListDir,
+    /// `std.fs` `remove(path)`: delete a file or an empty directory
+    RemovePath,
+    /// `std.os` `args()`: the script's command-line arguments
+    Args,
+    /// `std.os` `env(name)`: the environment variable, or `""` if it isn't set
+    Env,
+    /// `std.os` `exit(code)`: stop the program with that exit status
+    Exit,
+    /// `std.os` `cwd()`: the current working directory
+    Cwd,
+    /// `std.random` `int(lo, hi)`: between `lo` and `hi`, both included
+    RandomInt,
+    /// `std.random` `float()`: at least 0.0 and below 1.0
+    RandomFloat,
+    /// `std.random` `choice(xs)`: one of the elements
+    Choice,
+    /// `std.random` `shuffle(xs)`: put the elements in random order, in place
+    Shuffle,
+    /// `std.random` `seed(n)`: restart the generator, so the numbers repeat
+    Seed,
+    /// `std.time` `now()`: wall-clock milliseconds since the Unix epoch
+    Now,
+    /// `std.time` `monotonic()`: milliseconds since the program started, never going back
+    Monotonic,
+    /// `std.time` `sleep(ms)`: wait that many milliseconds
+    Sleep,
+    /// `std.time` `format(ms, pattern)`: UTC time as text, see `clock::format_time`
+    FormatTime,
+    /// `std.json` `parse(text)`: the maps, lists, numbers, strings, booleans and nulls `text` encodes
+    JsonParse,
+    /// `std.json` `stringify(value, pretty)`: `value` as JSON, indented when `pretty`
+    JsonStringify,
+}
+
+/// Constants of standard modules, read as `Math.PI`
+const CONSTANTS: [(&str, &str, f64); 2] = [
+    ("std.math", "PI", std::f64::consts::PI),
+    ("std.math", "E", std::f64::consts::E),
+];
+
+/// The constant `name` of the standard module `module` (e.g. `"std.math"`)
+pub fn module_constant(module: &str, name: &str) -> Option<f64> {
+    CONSTANTS.iter()
+        .find(|(m, n, _)| *m == module && *n == name)
+        .map(|(_, _, value)| *value)
+}
+
+/// Names of every constant of `module`
+pub fn module_constants(module: &str) -> impl Iterator<Item = &'static str> + '_ {
+    CONSTANTS.iter()
+        .filter(move |(m, _, _)| *m == module)
+        .map(|(_, name, _)| *name)
+}
+
+impl Native {
+    /// Every native, in the order `.tbc` files number them; new natives go last
+    pub const ALL: [Native; 55] = [
+        Native::Int, Native::Float, Native::Str, Native::Len, Native::Push, Native::Pop,
+        Native::Has, Native::Keys, Native::Values, Native::Remove,
+        Native::Upper, Native::Lower, Native::Contains, Native::Split, Native::Trim, Native::Replace, Native::Substring,
+        Native::Sqrt, Native::Pow, Native::Abs, Native::Floor, Native::Ceil, Native::Round, Native::Min, Native::Max,
+        Native::Sin, Native::Cos, Native::Tan, Native::Asin, Native::Acos, Native::Atan, Native::Log, Native::Log10, Native::Exp,
+        Native::ReadText, Native::WriteText, Native::AppendText, Native::Exists, Native::ListDir, Native::RemovePath,
+        Native::Args, Native::Env, Native::Exit, Native::Cwd,
+        Native::RandomInt, Native::RandomFloat, Native::Choice, Native::Shuffle, Native::Seed,
+        Native::Now, Native::Monotonic, Native::Sleep, Native::FormatTime,
+        Native::JsonParse, Native::JsonStringify,
+    ];
+
+    /// Name the function is called by in Trica source
+    pub fn name(self) -> &'static str {
+        match self {
+            Native::Int => "int",
+            Native::Float => "float",
+            Native::Str => "str",
+            Native::Len => "len",
+            Native::Push => "push",
+            Native::Pop => "pop",
+            Native::Has => "has",
+            Native::Keys => "keys",
+            Native::Values => "values",
+            Native::Remove => "remove",
+            Native::Upper => "upper",
+            Native::Lower => "lower",
+            Native::Contains => "contains",
+            Native::Split => "split",
+            Native::Trim => "trim",
+            Native::Replace => "replace",
+            Native::Substring => "substring",
+            Native::Sqrt => "sqrt",
+            Native::Pow => "pow",
+            Native::Abs => "abs",
+            Native::Floor => "floor",
+            Native::Ceil => "ceil",
+            Native::Round => "round",
+            Native::Min => "min",
+            Native::Max => "max",
+            Native::Sin => "sin",
+            Native::Cos => "cos",
+            Native::Tan => "tan",
+            Native::Asin => "asin",
+            Native::Acos => "acos",
+            Native::Atan => "atan",
+            Native::Log => "log",
+            Native::Log10 => "log10",
+            Native::Exp => "exp",
+            Native::ReadText => "read_text",
+            Native::WriteText => "write_text",
+            Native::AppendText => "append_text",
+            Native::Exists => "exists",
+            Native::ListDir => "list_dir",
+            Native::RemovePath => "remove",
+            Native::Args => "args",
+            Native::Env => "env",
+            Native::Exit => "exit",
+            Native::Cwd => "cwd",
+            Native::RandomInt => "int",
+            Native::RandomFloat => "float",
+            Native::Choice => "choice",
+            Native::Shuffle => "shuffle",
+            Native::Seed => "seed",
+            Native::Now => "now",
+            Native::Monotonic => "monotonic",
+            Native::Sleep => "sleep",
+            Native::FormatTime => "format",
+            Native::JsonParse => "parse",
+            Native::JsonStringify => "stringify",
+        }
+    }
+
+    /// Standard module the function belongs to, if it isn't global
+    pub fn module(self) -> Option<&'static str> {
+        match self {
+            Native::Sqrt | Native::Pow | Native::Abs | Native::Floor | Native::Ceil | Native::Round
+                | Native::Min | Native::Max | Native::Sin | Native::Cos | Native::Tan | Native::Asin
+                | Native::Acos | Native::Atan | Native::Log | Native::Log10 | Native::Exp => Some("std.math"),
+            Native::ReadText | Native::WriteText | Native::AppendText | Native::Exists
+                | Native::ListDir | Native::RemovePath => Some("std.fs"),
+            Native::Args | Native::Env | Native::Exit | Native::Cwd => Some("std.os"),
+            Native::RandomInt | Native::RandomFloat | Native::Choice | Native::Shuffle | Native::Seed => Some("std.random"),
+            Native::Now | Native::Monotonic | Native::Sleep | Native::FormatTime => Some("std.time"),
+            Native::JsonParse | Native::JsonStringify => Some("std.json"),
+            _ => None,
+        }
+    }
+
+    /// Called as `receiver.name(...)`, with the receiver as first argument
+    pub fn is_method(self) -> bool {
+        !matches!(self, Native::Int | Native::Float | Native::Str) && self.module().is_none()
+    }
+
+    /// Whether the method can be called on a value of type `receiver`
+    fn receives(self, receiver: &Type) -> bool {
+        match receiver {
+            Type::Unknown => true,
+            Type::List(_) => matches!(self, Native::Len | Native::Push | Native::Pop),
+            Type::Map(..) => matches!(self, Native::Len | Native::Has | Native::Keys | Native::Values | Native::Remove),
+            Type::String => matches!(
+                self,
+                Native::Len | Native::Upper | Native::Lower | Native::Contains | Native::Split
+                    | Native::Trim | Native::Replace | Native::Substring
+            ),
+            _ => false,
+        }
+    }
+
+    /// The global function called `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|native| !native.is_method() && native.module().is_none() && native.name() == name)
+    }
+
+    /// The function `name` of the standard module `module` (e.g. `"std.math"`)
+    pub fn from_module(module: &str, name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|native| native.module() == Some(module) && native.name() == name)
+    }
+
+    /// The method called `name` on a value of type `receiver`
+    pub fn method(receiver: &Type, name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|native| {
+            native.is_method() && native.name() == name && native.receives(receiver)
+        })
+    }
+
+    /// Whether the result is an Int when every argument is: `abs`, `min`
+    /// and `max` keep Ints whole
+    pub fn keeps_ints(self) -> bool {
+        matches!(self, Native::Abs | Native::Min | Native::Max)
+    }
+
+    /// Parameter types and return type; `Unknown` parameters take any value.
+    /// Methods are typed for their receiver, which is not in the parameters;
+    /// module functions for their first argument, which is.
+    pub fn signature(self, receiver: &Type) -> (Vec<Type>, Type) {
+        let (key, element) = match receiver {
+            Type::List(element) => (Type::Int, element.as_ref().clone()),
+            Type::Map(key, value) => (key.as_ref().clone(), value.as_ref().clone()),
+            _ => (Type::Unknown, Type::Unknown),
+        };
+        match self {
+            Native::Int => (vec![Type::Unknown], Type::Int),
+            Native::Float => (vec![Type::Unknown], Type::Float),
+            Native::Str => (vec![Type::Unknown], Type::String),
+            Native::Len => (vec![], Type::Int),
+            Native::Push => (vec![element], Type::Void),
+            Native::Pop => (vec![], element),
+            Native::Has => (vec![key], Type::Boolean),
+            Native::Keys => (vec![], Type::List(Box::new(key))),
+            Native::Values => (vec![], Type::List(Box::new(element))),
+            Native::Remove => (vec![key], Type::Boolean),
+            Native::Upper | Native::Lower | Native::Trim => (vec![], Type::String),
+            Native::Contains => (vec![Type::String], Type::Boolean),
+            Native::Split => (vec![Type::String], Type::List(Box::new(Type::String))),
+            Native::Replace => (vec![Type::String, Type::String], Type::String),
+            Native::Substring => (vec![Type::Int, Type::Int], Type::String),
+            Native::Floor | Native::Ceil | Native::Round => (vec![Type::Float], Type::Int),
+            Native::Pow | Native::Min | Native::Max => (vec![Type::Float, Type::Float], Type::Float),
+            Native::Sqrt | Native::Abs | Native::Sin | Native::Cos | Native::Tan | Native::Asin
+                | Native::Acos | Native::Atan | Native::Log | Native::Log10 | Native::Exp => (vec![Type::Float], Type::Float),
+            Native::ReadText => (vec![Type::String], Type::String),
+            Native::WriteText | Native::AppendText => (vec![Type::String, Type::String], Type::Void),
+            Native::Exists => (vec![Type::String], Type::Boolean),
+            Native::ListDir => (vec![Type::String], Type::List(Box::new(Type::String))),
+            Native::RemovePath => (vec![Type::String], Type::Void),
+            Native::Args => (vec![], Type::List(Box::new(Type::String))),
+            Native::Env => (vec![Type::String], Type::String),
+            Native::Exit => (vec![Type::Int], Type::Void),
+            Native::Cwd => (vec![], Type::String),
+            Native::RandomInt => (vec![Type::Int, Type::Int], Type::Int),
+            Native::RandomFloat => (vec![], Type::Float),
+            Native::Choice => (vec![Type::List(Box::new(element.clone()))], element),
+            Native::Shuffle => (vec![Type::List(Box::new(element))], Type::Void),
+            Native::Seed => (vec![Type::Int], Type::Void),
+            Native::Now | Native::Monotonic => (vec![], Type::Int),
+            Native::Sleep => (vec![Type::Int], Type::Void),
+            Native::FormatTime => (vec![Type::Int, Type::String], Type::String),
+            // Parsed values are only known at run time
+            Native::JsonParse => (vec![Type::String], Type::Unknown),
+            Native::JsonStringify => (vec![Type::Unknown, Type::Boolean], Type::String),
+        }
+    }
+
+    /// Number of arguments `CallNative` pops, counting a method's receiver
+    pub fn arity(self) -> usize {
+        match self {
+            Native::Args | Native::Cwd | Native::RandomFloat | Native::Now | Native::Monotonic => 0,
+            Native::Int | Native::Float | Native::Str | Native::Len | Native::Pop | Native::Keys | Native::Values
+                | Native::Upper | Native::Lower | Native::Trim | Native::Sqrt | Native::Abs | Native::Floor
+                | Native::Ceil | Native::Round | Native::Sin | Native::Cos | Native::Tan | Native::Asin
+                | Native::Acos | Native::Atan | Native::Log | Native::Log10 | Native::Exp
+                | Native::ReadText | Native::Exists | Native::ListDir | Native::RemovePath
+                | Native::Env | Native::Exit | Native::Choice | Native::Shuffle | Native::Seed | Native::Sleep | Native::JsonParse => 1,
+            Native::Push | Native::Has | Native::Remove | Native::Contains | Native::Split
+                | Native::Pow | Native::Min | Native::Max | Native::WriteText | Native::AppendText
+                | Native::RandomInt | Native::FormatTime | Native::JsonStringify => 2,
+            Native::Replace | Native::Substring => 3,
+        }
+    }
+
+    /// Run the native on its arguments, first argument first, for `vm`
+    pub fn call(self, mut args: Vec<TricaValue>, vm: &mut TricaVM) -> Result<TricaValue, TricarError> {
+        if args.len() != self.arity() {
+            return Err(TricarError::InvalidBytecode(format!("{}() takes {} argument(s)", self.name(), self.arity())));
+        }
+        let value = if args.is_empty() { TricaValue::Void } else { args.remove(0) };
+        let cannot_convert = |value: &TricaValue, to: &str| {
+            TricarError::ValueError(format!("cannot convert {:?} to {}", format_value(value), to))
+        };
+        let unsupported = |value: &TricaValue| TricarError::UnsupportedOperation(format!("{}() on {:?}", self.name(), value));
+        let list = |value: &TricaValue| match value {
+            TricaValue::List(list) => Ok(list.clone()),
+            other => Err(unsupported(other)),
+        };
+        let map = |value: &TricaValue| match value {
+            TricaValue::Map(map) => Ok(map.clone()),
+            other => Err(unsupported(other)),
+        };
+        let string = |value: &TricaValue| match value {
+            TricaValue::String(s) => Ok(s.clone()),
+            other => Err(unsupported(other)),
+        };
+        // Math takes Ints too, widened to Floats
+        let number = |value: &TricaValue| match value {
+            TricaValue::Int(i) => Ok(*i as f64),
+            TricaValue::Float(f) => Ok(*f),
+            other => Err(unsupported(other)),
+        };
+        let float = |f: f64| Ok(TricaValue::Float(f));
+        // Failed file operations name the function and the path
+        let io_error = |path: &Path, error: std::io::Error| {
+            TricarError::IoError(format!("{}('{}'): {}", self.name(), path.display(), error))
+        };
+        let capabilities = vm.capabilities();
+        let whole = |f: f64| {
+            if f >= i64::MIN as f64 && f < -(i64::MIN as f64) {
+                Ok(TricaValue::Int(f as i64))
+            } else {
+                Err(TricarError::ArithmeticError(format!("{}({}) is out of Int range", self.name(), format_value(&value))))
+            }
+        };
+
+        match self {
+            Native::Int => match &value {
+                TricaValue::Int(_) => Ok(value),
+                // Everything in [-2^63, 2^63) truncates to an i64; NaN is in no range
+                TricaValue::Float(f) if *f >= i64::MIN as f64 && *f < -(i64::MIN as f64) => {
+                    Ok(TricaValue::Int(f.trunc() as i64))
+                }
+                TricaValue::String(s) => s.trim().parse().map(TricaValue::Int)
+                    .map_err(|_| cannot_convert(&value, "Int")),
+                _ => Err(cannot_convert(&value, "Int")),
+            },
+            Native::Float => match &value {
+                TricaValue::Int(i) => Ok(TricaValue::Float(*i as f64)),
+                TricaValue::Float(_) => Ok(value),
+                TricaValue::String(s) => s.trim().parse().map(TricaValue::Float)
+                    .map_err(|_| cannot_convert(&value, "Float")),
+                _ => Err(cannot_convert(&value, "Float")),
+            },
+            Native::Str => Ok(TricaValue::String(format_value(&value).into())),
+            Native::Len => match &value {
+                TricaValue::Map(map) => Ok(TricaValue::Int(map.borrow().len() as i64)),
+                TricaValue::String(s) => Ok(TricaValue::Int(s.chars().count() as i64)),
+                _ => Ok(TricaValue::Int(list(&value)?.borrow().len() as i64)),
+            },
+            Native::Push => {
+                list(&value)?.borrow_mut().push(args.remove(0));
+                Ok(TricaValue::Void)
+            }
+            Native::Pop => list(&value)?.borrow_mut().pop()
+                .ok_or_else(|| TricarError::ValueError("pop() on an empty list".to_string())),
+            Native::Has => {
+                let key = MapKey::new(&args[0])?;
+                Ok(TricaValue::Boolean(map(&value)?.borrow().contains_key(&key)))
+            }
+            Native::Keys => Ok(TricaValue::new_list(map(&value)?.borrow().keys().map(MapKey::value).collect())),
+            Native::Values => Ok(TricaValue::new_list(map(&value)?.borrow().values().cloned().collect())),
+            Native::Remove => {
+                let key = MapKey::new(&args[0])?;
+                // Keeps the order of the other entries
+                Ok(TricaValue::Boolean(map(&value)?.borrow_mut().shift_remove(&key).is_some()))
+            }
+            Native::Upper => Ok(TricaValue::String(string(&value)?.to_uppercase().into())),
+            Native::Lower => Ok(TricaValue::String(string(&value)?.to_lowercase().into())),
+            Native::Trim => Ok(TricaValue::String(string(&value)?.trim().into())),
+            Native::Contains => Ok(TricaValue::Boolean(string(&value)?.contains(&*string(&args[0])?))),
+            Native::Split => {
+                let (text, separator) = (string(&value)?, string(&args[0])?);
+                let pieces = if separator.is_empty() {
+                    text.chars().map(|c| TricaValue::String(c.to_string().into())).collect()
+                } else {
+                    text.split(&*separator).map(|piece| TricaValue::String(piece.into())).collect()
+                };
+                Ok(TricaValue::new_list(pieces))
+            }
+            Native::Replace => {
+                let text = string(&value)?.replace(&*string(&args[0])?, &string(&args[1])?);
+                Ok(TricaValue::String(text.into()))
+            }
+            Native::Substring => {
+                let text = string(&value)?;
+                let (TricaValue::Int(start), TricaValue::Int(end)) = (&args[0], &args[1]) else {
+                    return Err(unsupported(&args[0]));
+                };
+                // Byte offset of each character, and of the end of the string
+                let offset = |n: i64| {
+                    let n = usize::try_from(n).ok()?;
+                    text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len())).nth(n)
+                };
+                match (offset(*start), offset(*end)) {
+                    (Some(from), Some(to)) if from <= to => Ok(TricaValue::String(text[from..to].into())),
+                    _ => Err(TricarError::ValueError(format!(
+                        "substring({}, {}) out of range for a string of {} characters",
+                        start, end, text.chars().count()
+                    ))),
+                }
+            }
+            Native::Sqrt => float(number(&value)?.sqrt()),
+            Native::Pow => float(number(&value)?.powf(number(&args[0])?)),
+            // Ints stay Ints
+            Native::Abs => match &value {
+                TricaValue::Int(i) => i.checked_abs().map(TricaValue::Int).ok_or_else(|| {
+                    TricarError::ArithmeticError(format!("abs({}) is out of Int range", i))
+                }),
+                _ => float(number(&value)?.abs()),
+            },
+            Native::Floor => whole(number(&value)?.floor()),
+            Native::Ceil => whole(number(&value)?.ceil()),
+            Native::Round => whole(number(&value)?.round()),
+            Native::Min | Native::Max => match (&value, &args[0]) {
+                (TricaValue::Int(a), TricaValue::Int(b)) => {
+                    Ok(TricaValue::Int(if self == Native::Min { *a.min(b) } else { *a.max(b) }))
+                }
+                _ if self == Native::Min => float(number(&value)?.min(number(&args[0])?)),
+                _ => float(number(&value)?.max(number(&args[0])?)),
+            },
+            Native::Sin => float(number(&value)?.sin()),
+            Native::Cos => float(number(&value)?.cos()),
+            Native::Tan => float(number(&value)?.tan()),
+            Native::Asin => float(number(&value)?.asin()),
+            Native::Acos => float(number(&value)?.acos()),
+            Native::Atan => float(number(&value)?.atan()),
+            Native::Log => float(number(&value)?.ln()),
+            Native::Log10 => float(number(&value)?.log10()),
+            Native::Exp => float(number(&value)?.exp()),
+            Native::ReadText => {
+                let path = string(&value)?;
+                let path = Path::new(&*path);
+                capabilities.check_read(path)?;
+                let text = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+                Ok(TricaValue::String(text.into()))
+            }
+            Native::WriteText | Native::AppendText => {
+                let (path, text) = (string(&value)?, string(&args[0])?);
+                let path = Path::new(&*path);
+                capabilities.check_write(path)?;
+                let written = if self == Native::WriteText {
+                    fs::write(path, text.as_bytes())
+                } else {
+                    fs::OpenOptions::new().append(true).create(true).open(path)
+                        .and_then(|mut file| file.write_all(text.as_bytes()))
+                };
+                written.map_err(|e| io_error(path, e))?;
+                Ok(TricaValue::Void)
+            }
+            Native::Exists => {
+                let path = string(&value)?;
+                capabilities.check_read(Path::new(&*path))?;
+                Ok(TricaValue::Boolean(Path::new(&*path).exists()))
+            }
+            Native::ListDir => {
+                let path = string(&value)?;
+                let path = Path::new(&*path);
+                capabilities.check_read(path)?;
+                let mut names = fs::read_dir(path)
+                    .and_then(|entries| entries.map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned())).collect::<Result<Vec<_>, _>>())
+                    .map_err(|e| io_error(path, e))?;
+                names.sort();
+                Ok(TricaValue::new_list(names.into_iter().map(|name| TricaValue::String(name.into())).collect()))
+            }
+            Native::RemovePath => {
+                let path = string(&value)?;
+                let path = Path::new(&*path);
+                capabilities.check_write(path)?;
+                let removed = if path.is_dir() { fs::remove_dir(path) } else { fs::remove_file(path) };
+                removed.map_err(|e| io_error(path, e))?;
+                Ok(TricaValue::Void)
+            }
+            Native::Args => Ok(TricaValue::new_list(vm.args().iter().map(|arg| TricaValue::String(arg.as_str().into())).collect())),
+            Native::Env => {
+                let name = string(&value)?;
+                capabilities.check_env(&name)?;
+                Ok(TricaValue::String(std::env::var(&*name).unwrap_or_default().into()))
+            }
+            Native::Exit => match value {
+                TricaValue::Int(code) => {
+                    let code = i32::try_from(code)
+                        .map_err(|_| TricarError::ValueError(format!("exit code {} is out of range", code)))?;
+                    vm.exit(code);
+                    Ok(TricaValue::Void)
+                }
+                other => Err(unsupported(&other)),
+            },
+            // Where relative paths lead, so reading it takes read access there
+            Native::Cwd => {
+                capabilities.check_read(Path::new("."))?;
+                let cwd = std::env::current_dir().map_err(|e| io_error(Path::new("."), e))?;
+                Ok(TricaValue::String(cwd.display().to_string().into()))
+            }
+            Native::RandomInt => match (&value, &args[0]) {
+                (TricaValue::Int(lo), TricaValue::Int(hi)) if lo <= hi => Ok(TricaValue::Int(vm.rng().int(*lo, *hi))),
+                (TricaValue::Int(lo), TricaValue::Int(hi)) => {
+                    Err(TricarError::ValueError(format!("int({}, {}) has an empty range", lo, hi)))
+                }
+                _ => Err(unsupported(&value)),
+            },
+            Native::RandomFloat => Ok(TricaValue::Float(vm.rng().float())),
+            Native::Choice => {
+                let list = list(&value)?;
+                let list = list.borrow();
+                if list.is_empty() {
+                    return Err(TricarError::ValueError("choice() on an empty list".to_string()));
+                }
+                Ok(list[vm.rng().below(list.len() as u64) as usize].clone())
+            }
+            Native::Shuffle => {
+                // Fisher-Yates
+                let list = list(&value)?;
+                let mut list = list.borrow_mut();
+                for i in (1..list.len()).rev() {
+                    let j = vm.rng().below(i as u64 + 1) as usize;
+                    list.swap(i, j);
+                }
+                Ok(TricaValue::Void)
+            }
+            Native::Seed => match value {
+                TricaValue::Int(seed) => {
+                    vm.set_seed(seed as u64);
+                    Ok(TricaValue::Void)
+                }
+                other => Err(unsupported(&other)),
+            },
+            Native::Now => Ok(TricaValue::Int(vm.clock().now())),
+            Native::Monotonic => Ok(TricaValue::Int(vm.clock().monotonic())),
+            Native::Sleep => match value {
+                TricaValue::Int(ms) => {
+                    let ms = u64::try_from(ms)
+                        .map_err(|_| TricarError::ValueError(format!("sleep({}) needs a duration of at least 0", ms)))?;
+                    vm.clock().sleep(ms);
+                    Ok(TricaValue::Void)
+                }
+                other => Err(unsupported(&other)),
+            },
+            Native::FormatTime => match (&value, &args[0]) {
+                (TricaValue::Int(ms), TricaValue::String(pattern)) => {
+                    Ok(TricaValue::String(clock::format_time(*ms, pattern).into()))
+                }
+                _ => Err(unsupported(&value)),
+            },
+            Native::JsonParse => match value {
+                TricaValue::String(text) => json::parse(&text),
+                other => Err(unsupported(&other)),
+            },
+            Native::JsonStringify => match &args[0] {
+                TricaValue::Boolean(pretty) => Ok(TricaValue::String(json::stringify(&value, *pretty)?.into())),
+                other => Err(unsupported(other)),
+            },
+        }
+    }
+}
@@ -0,0 +1,357 @@
+// 🔥 TRICA BYTECODE OPTIMIZER 🔥
+// Compile-time passes over `Bytecode`, run after `BytecodeCompiler`:
+//
+//   constant folding      `LoadConst a, LoadConst b, Add` -> `LoadConst a+b`,
+//                         `LoadConst a, Negate` -> `LoadConst -a`,
+//                         constant interpolations become one string,
+//                         and branches on constant conditions become plain
+//                         jumps (or disappear)
+//   constant propagation  variables stored exactly once, with a constant,
+//                         are replaced by that constant at every load
+//   unreachable code      instructions no path from the start reaches
+//   jump threading        jumps to jumps go straight to the final target
+//
+// `-O0` runs nothing, `-O1` everything but propagation, `-O2` everything.
+// The passes repeat until none of them changes anything, since each one
+// exposes work for the others.
+
+use std::collections::{HashMap, HashSet};
+use crate::bytecode::{binary_operation, concat_values, unary_operation, Bytecode, Instruction, TricaValue};
+use crate::resolver::Slot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OptLevel {
+    O0,
+    #[default]
+    O1,
+    O2,
+}
+
+impl OptLevel {
+    /// `-O0`, `-O1` or `-O2`
+    pub fn parse(flag: &str) -> Option<Self> {
+        match flag {
+            "-O0" => Some(OptLevel::O0),
+            "-O1" => Some(OptLevel::O1),
+            "-O2" => Some(OptLevel::O2),
+            _ => None,
+        }
+    }
+
+    pub fn passes(self) -> Passes {
+        let on = self != OptLevel::O0;
+        Passes {
+            fold_constants: on,
+            propagate_constants: self == OptLevel::O2,
+            remove_unreachable: on,
+            thread_jumps: on,
+        }
+    }
+}
+
+/// Which passes `optimize` runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Passes {
+    pub fold_constants: bool,
+    pub propagate_constants: bool,
+    pub remove_unreachable: bool,
+    pub thread_jumps: bool,
+}
+
+/// Optimize compiled bytecode; the program's output is unchanged
+pub fn optimize(mut bytecode: Bytecode, passes: Passes) -> Bytecode {
+    loop {
+        let mut changed = false;
+        if passes.fold_constants {
+            changed |= fold_constants(&mut bytecode);
+        }
+        if passes.propagate_constants {
+            changed |= propagate_constants(&mut bytecode);
+        }
+        if passes.thread_jumps {
+            changed |= thread_jumps(&mut bytecode.instructions);
+        }
+        if passes.remove_unreachable {
+            changed |= remove_unreachable(&mut bytecode.instructions);
+        }
+        if !changed {
+            return bytecode;
+        }
+    }
+}
+
+fn jump_target(instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Jump(target) | Instruction::JumpIfFalse(target) => Some(*target),
+        _ => None,
+    }
+}
+
+/// Indices some jump lands on; code can't be merged across them
+fn jump_targets(instructions: &[Instruction]) -> HashSet<usize> {
+    instructions.iter().filter_map(jump_target).collect()
+}
+
+/// Drop the instructions where `keep` is false and retarget jumps. A jump
+/// to a dropped instruction lands on the next kept one.
+fn compact(instructions: &mut Vec<Instruction>, keep: &[bool]) {
+    let mut new_index = Vec::with_capacity(keep.len() + 1);
+    let mut kept = 0;
+    for &k in keep {
+        new_index.push(kept);
+        kept += usize::from(k);
+    }
+    new_index.push(kept);
+
+    let mut i = 0;
+    instructions.retain_mut(|instruction| {
+        if let Instruction::Jump(target) | Instruction::JumpIfFalse(target) = instruction {
+            *target = new_index[(*target).min(keep.len())];
+        }
+        i += 1;
+        keep[i - 1]
+    });
+}
+
+/// Index of `value` in the pool, adding it if it isn't there yet
+fn constant_index(constants: &mut Vec<TricaValue>, value: TricaValue) -> Option<u32> {
+    let existing = constants.iter().position(|c| match (c, &value) {
+        (TricaValue::String(a), TricaValue::String(b)) => a == b,
+        (TricaValue::Int(a), TricaValue::Int(b)) => a == b,
+        (TricaValue::Float(a), TricaValue::Float(b)) => a.to_bits() == b.to_bits(),
+        (TricaValue::Boolean(a), TricaValue::Boolean(b)) => a == b,
+        _ => false,
+    });
+    match existing {
+        Some(index) => u32::try_from(index).ok(),
+        None => {
+            let index = u32::try_from(constants.len()).ok()?;
+            constants.push(value);
+            Some(index)
+        }
+    }
+}
+
+/// The constant a `LoadConst` at `index` pushes
+fn constant_at(bytecode: &Bytecode, index: usize) -> Option<TricaValue> {
+    match bytecode.instructions.get(index) {
+        Some(Instruction::LoadConst(constant)) => bytecode.constants.get(*constant as usize).cloned(),
+        _ => None,
+    }
+}
+
+fn fold_constants(bytecode: &mut Bytecode) -> bool {
+    let targets = jump_targets(&bytecode.instructions);
+    let len = bytecode.instructions.len();
+    let mut keep = vec![true; len];
+    let mut changed = false;
+
+    let mut i = 0;
+    while i < len {
+        let first = constant_at(bytecode, i);
+
+        // LoadConst, LoadConst, binary op -> LoadConst
+        if i + 2 < len && !targets.contains(&(i + 1)) && !targets.contains(&(i + 2)) {
+            if let (Some(a), Some(b)) = (first.clone(), constant_at(bytecode, i + 1)) {
+                let operation = &bytecode.instructions[i + 2];
+                let folded = if operation.is_binary() { binary_operation(operation, a, b).ok() } else { None };
+                if let Some(index) = folded.and_then(|value| constant_index(&mut bytecode.constants, value)) {
+                    bytecode.instructions[i] = Instruction::LoadConst(index);
+                    keep[i + 1] = false;
+                    keep[i + 2] = false;
+                    changed = true;
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+
+        // LoadConst, unary op -> LoadConst
+        if i + 1 < len && !targets.contains(&(i + 1)) {
+            if let Some(a) = first.clone() {
+                let operation = &bytecode.instructions[i + 1];
+                let folded = if operation.is_unary() { unary_operation(operation, a).ok() } else { None };
+                if let Some(index) = folded.and_then(|value| constant_index(&mut bytecode.constants, value)) {
+                    bytecode.instructions[i] = Instruction::LoadConst(index);
+                    keep[i + 1] = false;
+                    changed = true;
+                    i += 2;
+                    continue;
+                }
+            }
+        }
+
+        // n LoadConsts, ConcatMany(n) -> LoadConst
+        if let Some(end) = concat_end(bytecode, &targets, i) {
+            let parts: Vec<TricaValue> = (i..end - 1).filter_map(|j| constant_at(bytecode, j)).collect();
+            if let Some(index) = constant_index(&mut bytecode.constants, concat_values(&parts)) {
+                bytecode.instructions[i] = Instruction::LoadConst(index);
+                keep[i + 1..end].fill(false);
+                changed = true;
+                i = end;
+                continue;
+            }
+        }
+
+        // A branch on a constant condition always or never jumps
+        if let (Some(TricaValue::Boolean(condition)), Some(&Instruction::JumpIfFalse(target))) =
+            (first, bytecode.instructions.get(i + 1))
+        {
+            if !targets.contains(&(i + 1)) {
+                if condition {
+                    keep[i + 1] = false;
+                } else {
+                    bytecode.instructions[i + 1] = Instruction::Jump(target);
+                }
+                keep[i] = false;
+                changed = true;
+                i += 2;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    compact(&mut bytecode.instructions, &keep);
+    changed
+}
+
+/// End (exclusive) of a `ConcatMany` at or after `i` whose operands are all
+/// `LoadConst`s starting at `i`, with nothing jumping in between
+fn concat_end(bytecode: &Bytecode, targets: &HashSet<usize>, i: usize) -> Option<usize> {
+    let mut j = i;
+    while constant_at(bytecode, j).is_some() {
+        if j > i && targets.contains(&j) {
+            return None;
+        }
+        j += 1;
+    }
+    match bytecode.instructions.get(j) {
+        Some(&Instruction::ConcatMany(count)) if count as usize == j - i && j > i && !targets.contains(&j) => Some(j + 1),
+        _ => None,
+    }
+}
+
+fn propagate_constants(bytecode: &mut Bytecode) -> bool {
+    let instructions = &mut bytecode.instructions;
+    let targets = jump_targets(instructions);
+
+    // Every store of each variable, by index
+    let mut stores: HashMap<Slot, Vec<usize>> = HashMap::new();
+    for (i, instruction) in instructions.iter().enumerate() {
+        let variable = match instruction {
+            Instruction::StoreGlobal(slot) => Slot::Global(*slot),
+            Instruction::StoreLocal(slot) => Slot::Local(*slot),
+            _ => continue,
+        };
+        stores.entry(variable).or_default().push(i);
+    }
+
+    // Stored once, from a constant that nothing jumps between. The resolver
+    // only lets code read a variable after its declaring store, so every
+    // load sees that constant.
+    let constants: HashMap<Slot, (usize, u32)> = stores.into_iter()
+        .filter_map(|(variable, stores)| match stores[..] {
+            [store] if store > 0 && !targets.contains(&store) => match instructions[store - 1] {
+                Instruction::LoadConst(constant) => Some((variable, (store, constant))),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    if constants.is_empty() {
+        return false;
+    }
+
+    let mut keep = vec![true; instructions.len()];
+    for instruction in instructions.iter_mut() {
+        let variable = match instruction {
+            Instruction::LoadGlobal(slot) => Slot::Global(*slot),
+            Instruction::LoadLocal(slot) => Slot::Local(*slot),
+            _ => continue,
+        };
+        if let Some(&(_, constant)) = constants.get(&variable) {
+            *instruction = Instruction::LoadConst(constant);
+        }
+    }
+    // Nothing loads these variables any more, so their stores are dead
+    for &(store, _) in constants.values() {
+        keep[store - 1] = false;
+        keep[store] = false;
+    }
+
+    compact(instructions, &keep);
+    true
+}
+
+fn thread_jumps(instructions: &mut Vec<Instruction>) -> bool {
+    let mut changed = false;
+
+    for i in 0..instructions.len() {
+        let Some(mut target) = jump_target(&instructions[i]) else {
+            continue;
+        };
+        // Follow chains of unconditional jumps, giving up on cycles
+        let mut hops = 0;
+        while let Some(Instruction::Jump(next)) = instructions.get(target) {
+            if *next == target || hops == instructions.len() {
+                break;
+            }
+            target = *next;
+            hops += 1;
+        }
+
+        match &mut instructions[i] {
+            Instruction::Jump(old) | Instruction::JumpIfFalse(old) if *old != target => {
+                *old = target;
+                changed = true;
+            }
+            _ => {}
+        }
+        // A jump to a halt may as well halt
+        if matches!(instructions[i], Instruction::Jump(_)) && matches!(instructions.get(target), Some(Instruction::Halt)) {
+            instructions[i] = Instruction::Halt;
+            changed = true;
+        }
+    }
+
+    // A jump to the very next instruction does nothing
+    let keep: Vec<bool> = instructions.iter().enumerate()
+        .map(|(i, instruction)| !matches!(instruction, Instruction::Jump(target) if *target == i + 1))
+        .collect();
+    if keep.contains(&false) {
+        compact(instructions, &keep);
+        changed = true;
+    }
+    changed
+}
+
+fn remove_unreachable(instructions: &mut Vec<Instruction>) -> bool {
+    if instructions.is_empty() {
+        return false;
+    }
+    let mut reachable = vec![false; instructions.len()];
+    let mut pending = vec![0];
+    while let Some(i) = pending.pop() {
+        if i >= instructions.len() || reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        match &instructions[i] {
+            Instruction::Jump(target) => pending.push(*target),
+            Instruction::JumpIfFalse(target) => pending.extend([*target, i + 1]),
+            Instruction::Halt => {}
+            _ => pending.push(i + 1),
+        }
+    }
+
+    // The final Halt stays so the program still ends with one
+    *reachable.last_mut().expect("not empty") = true;
+
+    if reachable.iter().all(|&r| r) {
+        return false;
+    }
+    compact(instructions, &reachable);
+    true
+}
@@ -1,21 +1,30 @@
+use std::collections::HashSet;
 use crate::ast::*;
 use crate::error::TricarError;
-use crate::lexer::{Token, TokenType};
+use crate::lexer::{StringPart, Token, TokenType};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// Import aliases seen so far; `alias.name(...)` calls a module function
+    /// rather than a method
+    modules: HashSet<String>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        // Comments from `Lexer::with_trivia` carry no meaning for the parser
+        let tokens = tokens.into_iter()
+            .filter(|token| !matches!(token.token_type, TokenType::Comment(_)))
+            .collect();
+        Self { tokens, current: 0, modules: HashSet::new() }
     }
     
     pub fn parse(&mut self) -> Result<Program, TricarError> {
         let mut includes = Vec::new();
         let mut imports = Vec::new();
         let mut statements = Vec::new();
+        let mut legacy_main = None;
         
         // Skip initial newlines
         self.skip_newlines();
@@ -32,6 +41,12 @@ impl Parser {
                 TokenType::Main => {
                     // Legacy support - parse main block but extract statements
                     let main_block = self.parse_main_block()?;
+                    legacy_main = Some(LegacyMain {
+                        first_statement: statements.len(),
+                        line: main_block.line,
+                        column: main_block.column,
+                        end_line: main_block.end_line,
+                    });
                     statements.extend(main_block.statements);
                     break;
                 }
@@ -50,6 +65,7 @@ impl Parser {
             includes,
             imports,
             statements,
+            legacy_main,
         })
     }
     
@@ -128,6 +144,7 @@ impl Parser {
         // Parse 'as alias'
         self.consume(TokenType::As, "Expected 'as'")?;
         let alias = self.consume_identifier("Expected alias name")?;
+        self.modules.insert(alias.clone());
         
         self.skip_newlines();
         
@@ -144,7 +161,20 @@ impl Parser {
         let main_line = main_token.line;
         let main_column = main_token.column;
         
-        self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        let block = self.parse_block()?;
+        
+        Ok(MainBlock {
+            statements: block.statements,
+            line: main_line,
+            column: main_column,
+            end_line: block.end_line,
+        })
+    }
+    
+    fn parse_block(&mut self) -> Result<Block, TricarError> {
+        let brace = self.consume(TokenType::LeftBrace, "Expected '{'")?;
+        let line = brace.line;
+        let column = brace.column;
         self.skip_newlines();
         
         let mut statements = Vec::new();
@@ -158,17 +188,88 @@ impl Parser {
             self.skip_newlines();
         }
         
-        self.consume(TokenType::RightBrace, "Expected '}'")?;
+        let end_line = self.consume(TokenType::RightBrace, "Expected '}'")?.line;
         
-        Ok(MainBlock {
+        Ok(Block {
             statements,
-            line: main_line,
-            column: main_column,
+            line,
+            column,
+            end_line,
+        })
+    }
+    
+    fn parse_if(&mut self) -> Result<Statement, TricarError> {
+        let if_token = self.consume(TokenType::If, "Expected 'if'")?;
+        let if_line = if_token.line;
+        let if_column = if_token.column;
+        
+        let mut branches = vec![(self.parse_expression()?, self.parse_block()?)];
+        let mut else_block = None;
+        
+        // `else` must follow the closing `}` on the same line
+        while self.match_token(&TokenType::Else) {
+            if self.match_token(&TokenType::If) {
+                branches.push((self.parse_expression()?, self.parse_block()?));
+            } else {
+                else_block = Some(self.parse_block()?);
+                break;
+            }
+        }
+        
+        Ok(Statement::If {
+            branches,
+            else_block,
+            line: if_line,
+            column: if_column,
+        })
+    }
+    
+    fn parse_while(&mut self) -> Result<Statement, TricarError> {
+        let while_token = self.consume(TokenType::While, "Expected 'while'")?;
+        let while_line = while_token.line;
+        let while_column = while_token.column;
+        
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
+        
+        Ok(Statement::While {
+            condition,
+            body,
+            line: while_line,
+            column: while_column,
+        })
+    }
+    
+    fn parse_for(&mut self) -> Result<Statement, TricarError> {
+        let for_token = self.consume(TokenType::For, "Expected 'for'")?;
+        let for_line = for_token.line;
+        let for_column = for_token.column;
+        
+        let variable = self.consume_identifier("Expected loop variable after 'for'")?;
+        let value = if self.match_token(&TokenType::Comma) {
+            Some(self.consume_identifier("Expected value variable after ','")?)
+        } else {
+            None
+        };
+        self.consume(TokenType::In, "Expected 'in'")?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_block()?;
+        
+        Ok(Statement::For {
+            variable,
+            value,
+            iterable,
+            body,
+            line: for_line,
+            column: for_column,
         })
     }
     
     fn parse_statement(&mut self) -> Result<Statement, TricarError> {
         match &self.peek().token_type {
+            TokenType::If => self.parse_if(),
+            TokenType::While => self.parse_while(),
+            TokenType::For => self.parse_for(),
             TokenType::Print => {
                 let print_token = self.advance();
                 let print_line = print_token.line;
@@ -202,18 +303,30 @@ impl Parser {
                 self.current = checkpoint;
                 let expression = self.parse_expression()?;
                 
+                if let Expression::Index { object, index, line, column } = &expression {
+                    if self.match_token(&TokenType::Equal) {
+                        return Ok(Statement::IndexAssignment {
+                            object: (**object).clone(),
+                            index: (**index).clone(),
+                            value: self.parse_expression()?,
+                            line: *line,
+                            column: *column,
+                        });
+                    }
+                }
+                
                 Ok(Statement::Expression {
+                    line: expression.line(),
+                    column: expression.column(),
                     expression,
-                    line: self.peek().line,
-                    column: self.peek().column,
                 })
             }
             _ => {
                 let expression = self.parse_expression()?;
                 Ok(Statement::Expression {
+                    line: expression.line(),
+                    column: expression.column(),
                     expression,
-                    line: self.peek().line,
-                    column: self.peek().column,
                 })
             }
         }
@@ -306,10 +419,11 @@ impl Parser {
     fn parse_factor(&mut self) -> Result<Expression, TricarError> {
         let mut expr = self.parse_unary()?;
         
-        while self.match_tokens(&[TokenType::Slash, TokenType::Star]) {
+        while self.match_tokens(&[TokenType::Slash, TokenType::Star, TokenType::Percent]) {
             let operator = match self.previous().token_type {
                 TokenType::Slash => BinaryOperator::Divide,
                 TokenType::Star => BinaryOperator::Multiply,
+                TokenType::Percent => BinaryOperator::Modulo,
                 _ => unreachable!(),
             };
             
@@ -330,28 +444,155 @@ impl Parser {
     }
     
     fn parse_unary(&mut self) -> Result<Expression, TricarError> {
-        self.parse_postfix()
+        if self.match_tokens(&[TokenType::Not, TokenType::Bang, TokenType::Minus]) {
+            let token = self.previous().clone();
+            let operator = match token.token_type {
+                TokenType::Minus => UnaryOperator::Negate,
+                _ => UnaryOperator::Not,
+            };
+            let operand = self.parse_unary()?;
+            return Ok(Expression::UnaryOp {
+                operator,
+                operand: Box::new(operand),
+                line: token.line,
+                column: token.column,
+            });
+        }
+        
+        self.parse_power()
+    }
+    
+    /// `a ** b` binds tighter than unary operators on its left (`-2 ** 2` is
+    /// `-(2 ** 2)`) and groups to the right (`2 ** 3 ** 2` is `2 ** 9`)
+    fn parse_power(&mut self) -> Result<Expression, TricarError> {
+        let base = self.parse_postfix()?;
+        if !self.match_token(&TokenType::StarStar) {
+            return Ok(base);
+        }
+        let exponent = self.parse_unary()?;
+        let line = base.line();
+        let column = base.column();
+        Ok(Expression::BinaryOp {
+            left: Box::new(base),
+            operator: BinaryOperator::Power,
+            right: Box::new(exponent),
+            line,
+            column,
+        })
     }
     
     fn parse_postfix(&mut self) -> Result<Expression, TricarError> {
         let mut expr = self.parse_primary()?;
         
-        while self.match_token(&TokenType::Dot) {
-            let property = self.consume_identifier("Expected property name")?;
-            let line = expr.line();
-            let column = expr.column();
-            
-            expr = Expression::PropertyAccess {
-                object: Box::new(expr),
-                property,
-                line,
-                column,
-            };
+        loop {
+            if self.match_token(&TokenType::Dot) {
+                let property = self.consume_identifier("Expected property name")?;
+                let line = expr.line();
+                let column = expr.column();
+                
+                if self.check(&TokenType::LeftParen) && !self.is_module(&expr) {
+                    self.advance();
+                    let args = self.parse_arguments()?;
+                    expr = Expression::MethodCall {
+                        receiver: Box::new(expr),
+                        method: property,
+                        args,
+                        line,
+                        column,
+                    };
+                } else {
+                    expr = Expression::PropertyAccess {
+                        object: Box::new(expr),
+                        property,
+                        line,
+                        column,
+                    };
+                }
+            } else if self.match_token(&TokenType::LeftBracket) {
+                let line = expr.line();
+                let column = expr.column();
+                let start = if self.check(&TokenType::Colon) { None } else { Some(Box::new(self.parse_expression()?)) };
+                
+                expr = match start {
+                    Some(index) if !self.check(&TokenType::Colon) => Expression::Index {
+                        object: Box::new(expr),
+                        index,
+                        line,
+                        column,
+                    },
+                    start => {
+                        self.consume(TokenType::Colon, "Expected ':'")?;
+                        let end = if self.check(&TokenType::RightBracket) { None } else { Some(Box::new(self.parse_expression()?)) };
+                        Expression::Slice {
+                            object: Box::new(expr),
+                            start,
+                            end,
+                            line,
+                            column,
+                        }
+                    }
+                };
+                self.consume(TokenType::RightBracket, "Expected ']'")?;
+            } else if self.check(&TokenType::LeftParen) {
+                // Calls name a function (`int`) or a module member (`In.input`)
+                let Some(name) = callee_name(&expr) else {
+                    break;
+                };
+                self.advance();
+                let args = self.parse_arguments()?;
+                expr = Expression::FunctionCall {
+                    name,
+                    args,
+                    line: expr.line(),
+                    column: expr.column(),
+                };
+            } else {
+                break;
+            }
         }
         
         Ok(expr)
     }
     
+    /// The expression inside `${...}`, lexed into `tokens`
+    fn parse_embedded(&self, tokens: Vec<Token>) -> Result<Expression, TricarError> {
+        let mut parser = Parser { tokens, current: 0, modules: self.modules.clone() };
+        let expression = parser.parse_expression()?;
+        if !parser.is_at_end() {
+            return Err(TricarError::UnexpectedToken {
+                expected: "}".to_string(),
+                found: format!("{:?}", parser.peek().token_type),
+                line: parser.peek().line,
+                column: parser.peek().column,
+            });
+        }
+        Ok(expression)
+    }
+    
+    /// An import alias, or a member of one
+    fn is_module(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Identifier { name, .. } => self.modules.contains(name),
+            Expression::PropertyAccess { object, .. } => self.is_module(object),
+            _ => false,
+        }
+    }
+    
+    /// Comma-separated arguments after `(`, up to and including `)`
+    fn parse_arguments(&mut self) -> Result<Vec<Expression>, TricarError> {
+        let mut args = Vec::new();
+        if !self.match_token(&TokenType::RightParen) {
+            loop {
+                args.push(self.parse_expression()?);
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
+        }
+        Ok(args)
+    }
+    
     fn parse_primary(&mut self) -> Result<Expression, TricarError> {
         let token = self.advance();
         
@@ -363,13 +604,70 @@ impl Parser {
                     column: token.column,
                 })
             }
-            TokenType::NumberLiteral(value) => {
-                Ok(Expression::NumberLiteral {
+            TokenType::IntLiteral(value) => {
+                Ok(Expression::IntLiteral {
                     value: *value,
                     line: token.line,
                     column: token.column,
                 })
             }
+            TokenType::FloatLiteral(value) => {
+                Ok(Expression::FloatLiteral {
+                    value: *value,
+                    line: token.line,
+                    column: token.column,
+                })
+            }
+            TokenType::BooleanLiteral(value) => {
+                Ok(Expression::BooleanLiteral {
+                    value: *value,
+                    line: token.line,
+                    column: token.column,
+                })
+            }
+            TokenType::InterpolatedString(parts) => {
+                let (parts, line, column) = (parts.clone(), token.line, token.column);
+                let parts = parts.into_iter()
+                    .map(|part| match part {
+                        StringPart::Text(text) => Ok(InterpolationPart::Text(text)),
+                        StringPart::Code(tokens) => self.parse_embedded(tokens).map(InterpolationPart::Expression),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Expression::Interpolation { parts, line, column })
+            }
+            TokenType::LeftBracket => {
+                // Lists may span lines
+                let (line, column) = (token.line, token.column);
+                let mut elements = Vec::new();
+                self.skip_newlines();
+                while !self.check(&TokenType::RightBracket) {
+                    elements.push(self.parse_expression()?);
+                    self.skip_newlines();
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                    self.skip_newlines();
+                }
+                let end_line = self.consume(TokenType::RightBracket, "Expected ']'")?.line;
+                Ok(Expression::ListLiteral { elements, line, column, end_line })
+            }
+            TokenType::LeftBrace => {
+                let (line, column) = (token.line, token.column);
+                let mut entries = Vec::new();
+                self.skip_newlines();
+                while !self.check(&TokenType::RightBrace) {
+                    let key = self.parse_expression()?;
+                    self.consume(TokenType::Colon, "Expected ':' after map key")?;
+                    entries.push((key, self.parse_expression()?));
+                    self.skip_newlines();
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                    self.skip_newlines();
+                }
+                let end_line = self.consume(TokenType::RightBrace, "Expected '}'")?.line;
+                Ok(Expression::MapLiteral { entries, line, column, end_line })
+            }
             TokenType::Identifier(name) => {
                 Ok(Expression::Identifier {
                     name: name.clone(),
@@ -377,6 +675,14 @@ impl Parser {
                     column: token.column,
                 })
             }
+            TokenType::Print => {
+                // `Print` used as a value, e.g. `Print.output`
+                Ok(Expression::Identifier {
+                    name: "Print".to_string(),
+                    line: token.line,
+                    column: token.column,
+                })
+            }
             TokenType::LeftParen => {
                 let expr = self.parse_expression()?;
                 self.consume(TokenType::RightParen, "Expected ')'")?;
@@ -476,4 +782,14 @@ impl Parser {
             // Skip newlines
         }
     }
-}
\ No newline at end of file
+}
+/// `a` or `a.b.c` as a function name; other expressions can't be called
+fn callee_name(expression: &Expression) -> Option<String> {
+    match expression {
+        Expression::Identifier { name, .. } => Some(name.clone()),
+        Expression::PropertyAccess { object, property, .. } => {
+            Some(format!("{}.{}", callee_name(object)?, property))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+// 🎲 TRICA RANDOM NUMBERS 🎲
+// The generator behind `std.random`: SplitMix64, small and self-contained so
+// a seed gives the same numbers on every platform and every run.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Rng {
+    /// Generator that always produces the same numbers for `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generator seeded differently on every run
+    pub fn from_entropy() -> Self {
+        // std seeds every RandomState from the OS
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, without modulo bias; `n` must not be 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        // Values under `threshold` would make the low remainders likelier
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let r = self.next_u64();
+            if r >= threshold {
+                return r % n;
+            }
+        }
+    }
+
+    /// Uniform in `lo..=hi`; `lo` must not be above `hi`
+    pub fn int(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = hi.wrapping_sub(lo) as u64;
+        match span.checked_add(1) {
+            Some(n) => lo.wrapping_add(self.below(n) as i64),
+            // The whole i64 range
+            None => self.next_u64() as i64,
+        }
+    }
+
+    /// Uniform in `[0, 1)`, with 53 random bits
+    pub fn float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,232 @@
+// 🔥 TRICA VARIABLE RESOLVER 🔥
+// Runs between type checking and bytecode compilation and gives every
+// variable a numeric slot, so the VM never looks a name up at runtime.
+// Top-level variables get a global slot for the whole program. Variables
+// first assigned inside a block (`Main { }`, `if`, `while`, `for`) get a local slot
+// that the next block reuses once theirs ends. Scoping follows the type
+// checker: assigning a visible name reassigns it, a new name is declared in
+// the innermost block.
+
+use std::collections::HashMap;
+use crate::ast::*;
+use crate::error::TricarError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slot {
+    Global(u32),
+    Local(u16),
+}
+
+/// Slots a `for` loop uses: its variable, and two hidden locals holding
+/// the list and the current index while the loop runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopSlots {
+    pub variable: Slot,
+    /// `for key, value in map`: the value variable, and a hidden local
+    /// holding the map's values
+    pub value: Option<(Slot, u16)>,
+    pub list: u16,
+    pub index: u16,
+}
+
+/// The slot of every assignment and variable read, keyed by source position
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    slots: HashMap<(usize, usize), Slot>,
+    loops: HashMap<(usize, usize), LoopSlots>,
+    /// Number of global slots
+    pub globals: u32,
+    /// Most local slots in use at once
+    pub locals: u16,
+}
+
+impl Resolution {
+    /// Slot of the assignment or identifier at `line`, `column`
+    pub fn slot(&self, line: usize, column: usize) -> Option<Slot> {
+        self.slots.get(&(line, column)).copied()
+    }
+
+    /// Slots of the `for` loop at `line`, `column`
+    pub fn loop_slots(&self, line: usize, column: usize) -> Option<LoopSlots> {
+        self.loops.get(&(line, column)).copied()
+    }
+}
+
+/// Resolve the variables of a type-checked program
+pub fn resolve(program: &Program) -> Result<Resolution, TricarError> {
+    let mut resolver = Resolver::default();
+    let (top_level, main) = program.split_main();
+    resolver.statements(top_level)?;
+    if let Some(statements) = main {
+        resolver.block(statements)?;
+    }
+    Ok(resolver.resolution)
+}
+
+#[derive(Default)]
+struct Resolver {
+    globals: HashMap<String, u32>,
+    /// Block scopes, innermost last; empty at the top level
+    scopes: Vec<HashMap<String, u16>>,
+    next_local: u16,
+    resolution: Resolution,
+}
+
+impl Resolver {
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), TricarError> {
+        statements.iter().try_for_each(|statement| self.statement(statement))
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Result<(), TricarError> {
+        let first_local = self.next_local;
+        self.scopes.push(HashMap::new());
+        let result = self.statements(statements);
+        self.scopes.pop();
+        self.next_local = first_local;
+        result
+    }
+
+    fn lookup(&self, name: &str) -> Option<Slot> {
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name).map(|&slot| Slot::Local(slot)))
+            .or_else(|| self.globals.get(name).map(|&slot| Slot::Global(slot)))
+    }
+
+    fn declare(&mut self, name: &str) -> Result<Slot, TricarError> {
+        let Some(scope) = self.scopes.last_mut() else {
+            let slot = self.resolution.globals;
+            self.resolution.globals = slot.checked_add(1)
+                .ok_or_else(|| TricarError::CodegenError("Too many global variables".to_string()))?;
+            self.globals.insert(name.to_string(), slot);
+            return Ok(Slot::Global(slot));
+        };
+
+        let slot = self.next_local;
+        self.next_local = slot.checked_add(1)
+            .ok_or_else(|| TricarError::CodegenError("Too many local variables".to_string()))?;
+        self.resolution.locals = self.resolution.locals.max(self.next_local);
+        scope.insert(name.to_string(), slot);
+        Ok(Slot::Local(slot))
+    }
+
+    /// Declare a `for` loop's hidden locals, and its variables unless they are visible
+    fn loop_slots(&mut self, variable: &str, value: Option<&str>, line: usize, column: usize) -> Result<(), TricarError> {
+        let local = |slot| match slot {
+            Slot::Local(slot) => slot,
+            Slot::Global(_) => unreachable!("declared in a block scope"),
+        };
+        let list = local(self.declare("#list")?);
+        let index = local(self.declare("#index")?);
+        let values = match value {
+            Some(_) => Some(local(self.declare("#values")?)),
+            None => None,
+        };
+        let mut variable_slot = |name: &str| match self.lookup(name) {
+            Some(slot) => Ok(slot),
+            None => self.declare(name),
+        };
+        let variable = variable_slot(variable)?;
+        let value = match (value, values) {
+            (Some(name), Some(values)) => Some((variable_slot(name)?, values)),
+            _ => None,
+        };
+        self.resolution.loops.insert((line, column), LoopSlots { variable, value, list, index });
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), TricarError> {
+        match statement {
+            Statement::Print { expression, .. } | Statement::Expression { expression, .. } => {
+                self.expression(expression)
+            }
+            Statement::Assignment { name, value, line, column } => {
+                // The value can't see the variable it is declaring
+                self.expression(value)?;
+                let slot = match self.lookup(name) {
+                    Some(slot) => slot,
+                    None => self.declare(name)?,
+                };
+                self.resolution.slots.insert((*line, *column), slot);
+                Ok(())
+            }
+            Statement::If { branches, else_block, .. } => {
+                for (condition, block) in branches {
+                    self.expression(condition)?;
+                    self.block(&block.statements)?;
+                }
+                match else_block {
+                    Some(block) => self.block(&block.statements),
+                    None => Ok(()),
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                self.expression(condition)?;
+                self.block(&body.statements)
+            }
+            Statement::IndexAssignment { object, index, value, .. } => {
+                self.expression(object)?;
+                self.expression(index)?;
+                self.expression(value)
+            }
+            Statement::For { variable, value, iterable, body, line, column } => {
+                self.expression(iterable)?;
+                
+                // The hidden locals live in a scope around the body; `#` keeps
+                // their names apart from any Trica identifier
+                let first_local = self.next_local;
+                self.scopes.push(HashMap::new());
+                let result = self.loop_slots(variable, value.as_deref(), *line, *column)
+                    .and_then(|()| self.block(&body.statements));
+                self.scopes.pop();
+                self.next_local = first_local;
+                result
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<(), TricarError> {
+        match expression {
+            Expression::Identifier { name, line, column } => {
+                let slot = self.lookup(name).ok_or_else(|| TricarError::UndefinedVariable {
+                    name: name.clone(),
+                    line: *line,
+                    column: *column,
+                })?;
+                self.resolution.slots.insert((*line, *column), slot);
+                Ok(())
+            }
+            Expression::BinaryOp { left, right, .. } => {
+                self.expression(left)?;
+                self.expression(right)
+            }
+            Expression::FunctionCall { args, .. } | Expression::ListLiteral { elements: args, .. } => {
+                args.iter().try_for_each(|arg| self.expression(arg))
+            }
+            Expression::Interpolation { parts, .. } => parts.iter().try_for_each(|part| match part {
+                InterpolationPart::Expression(expression) => self.expression(expression),
+                InterpolationPart::Text(_) => Ok(()),
+            }),
+            Expression::MapLiteral { entries, .. } => entries.iter().try_for_each(|(key, value)| {
+                self.expression(key)?;
+                self.expression(value)
+            }),
+            Expression::Index { object, index, .. } => {
+                self.expression(object)?;
+                self.expression(index)
+            }
+            Expression::Slice { object, start, end, .. } => {
+                self.expression(object)?;
+                start.iter().chain(end).try_for_each(|bound| self.expression(bound))
+            }
+            Expression::MethodCall { receiver, args, .. } => {
+                self.expression(receiver)?;
+                args.iter().try_for_each(|arg| self.expression(arg))
+            }
+            // The object is a module or built-in like `Print`, not a variable
+            Expression::PropertyAccess { .. } => Ok(()),
+            Expression::UnaryOp { operand, .. } => self.expression(operand),
+            Expression::StringLiteral { .. } | Expression::IntLiteral { .. } |
+            Expression::FloatLiteral { .. } | Expression::BooleanLiteral { .. } => Ok(()),
+        }
+    }
+}
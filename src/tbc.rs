@@ -0,0 +1,261 @@
+// 🔥 TRICA COMPILED BYTECODE FILES (.tbc) 🔥
+// A compiled program on disk, little-endian throughout:
+//
+//   magic     b"TBC" and a format version byte
+//   slots     globals: u32, locals: u16
+//   constants count: u32, then per constant a tag byte and its payload
+//   code      count: u32, then per instruction an opcode byte and its operand
+//
+// `read` verifies what it decodes, since the file may not have come from
+// this compiler.
+
+use crate::bytecode::{Bytecode, Instruction, TricaValue};
+use crate::error::TricarError;
+use crate::natives::Native;
+use crate::verifier::verify;
+
+const MAGIC: &[u8; 3] = b"TBC";
+/// Format version. Opcodes 27-36 were added while the format was still 2,
+/// so version 2 files may not decode the same way; 3 is the first version
+/// with a settled opcode table, and it is bumped whenever an opcode is added,
+/// removed or changes meaning from here on.
+const VERSION: u8 = 3;
+/// Deepest nesting of quantum constants `read` accepts
+const MAX_NESTING: usize = 64;
+
+/// Encode `bytecode` as a `.tbc` file
+pub fn write(bytecode: &Bytecode) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&bytecode.globals.to_le_bytes());
+    out.extend_from_slice(&bytecode.locals.to_le_bytes());
+
+    out.extend_from_slice(&(bytecode.constants.len() as u32).to_le_bytes());
+    for constant in &bytecode.constants {
+        write_value(&mut out, constant);
+    }
+
+    out.extend_from_slice(&(bytecode.instructions.len() as u32).to_le_bytes());
+    for instruction in &bytecode.instructions {
+        write_instruction(&mut out, instruction);
+    }
+    out
+}
+
+/// Decode and verify a `.tbc` file
+pub fn read(bytes: &[u8]) -> Result<Bytecode, TricarError> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(3)? != MAGIC {
+        return Err(TricarError::InvalidBytecode("not a .tbc file".to_string()));
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(TricarError::InvalidBytecode(format!(
+            ".tbc format version {} is not supported (expected {})", version, VERSION)));
+    }
+    let globals = reader.u32()?;
+    let locals = reader.u16()?;
+
+    let count = reader.u32()?;
+    let constants = (0..count).map(|_| reader.value(0)).collect::<Result<_, _>>()?;
+    let count = reader.u32()?;
+    let instructions = (0..count).map(|_| reader.instruction()).collect::<Result<_, _>>()?;
+    if reader.position != bytes.len() {
+        return Err(TricarError::InvalidBytecode("trailing bytes after the last instruction".to_string()));
+    }
+
+    let bytecode = Bytecode { instructions, constants, globals, locals };
+    verify(&bytecode)?;
+    Ok(bytecode)
+}
+
+fn write_value(out: &mut Vec<u8>, value: &TricaValue) {
+    match value {
+        TricaValue::String(s) => {
+            out.push(0);
+            out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            out.extend_from_slice(s.as_bytes());
+        }
+        TricaValue::Int(i) => {
+            out.push(1);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        TricaValue::Float(f) => {
+            out.push(2);
+            out.extend_from_slice(&f.to_le_bytes());
+        }
+        TricaValue::Boolean(b) => out.extend_from_slice(&[3, u8::from(*b)]),
+        TricaValue::Quantum(states) => {
+            out.push(4);
+            out.extend_from_slice(&(states.len() as u32).to_le_bytes());
+            for state in states {
+                write_value(out, state);
+            }
+        }
+        TricaValue::Void => out.push(5),
+        // Lists and maps are mutable, so the compiler builds them with
+        // MakeList and MakeMap
+        TricaValue::List(_) | TricaValue::Map(_) => unreachable!("lists and maps are never constants"),
+    }
+}
+
+fn write_instruction(out: &mut Vec<u8>, instruction: &Instruction) {
+    let native;
+    let position;
+    let (opcode, operand): (u8, &[u8]) = match instruction {
+        Instruction::LoadConst(index) => (0, &index.to_le_bytes()),
+        Instruction::Print => (1, &[]),
+        Instruction::Pop => (2, &[]),
+        Instruction::Concat => (3, &[]),
+        Instruction::Add => (4, &[]),
+        Instruction::Subtract => (5, &[]),
+        Instruction::Multiply => (6, &[]),
+        Instruction::Divide => (7, &[]),
+        Instruction::Equal => (8, &[]),
+        Instruction::NotEqual => (9, &[]),
+        Instruction::Less => (10, &[]),
+        Instruction::Greater => (11, &[]),
+        Instruction::LessEqual => (12, &[]),
+        Instruction::GreaterEqual => (13, &[]),
+        Instruction::StoreLocal(slot) => (14, &slot.to_le_bytes()),
+        Instruction::LoadLocal(slot) => (15, &slot.to_le_bytes()),
+        Instruction::StoreGlobal(slot) => (16, &slot.to_le_bytes()),
+        Instruction::LoadGlobal(slot) => (17, &slot.to_le_bytes()),
+        Instruction::Jump(target) => (18, &(*target as u64).to_le_bytes()),
+        Instruction::JumpIfFalse(target) => (19, &(*target as u64).to_le_bytes()),
+        Instruction::QuantumSuperposition => (20, &[]),
+        Instruction::CollapseWaveFunction => (21, &[]),
+        Instruction::DestroyMind => (23, &[]),
+        Instruction::Halt => (24, &[]),
+        Instruction::Modulo => (25, &[]),
+        Instruction::CallNative(called) => {
+            native = [Native::ALL.iter().position(|n| n == called).expect("every native is in ALL") as u8];
+            (26, &native)
+        }
+        Instruction::Negate => (27, &[]),
+        Instruction::Not => (28, &[]),
+        Instruction::MakeList(count) => (29, &count.to_le_bytes()),
+        Instruction::MakeMap(count) => (33, &count.to_le_bytes()),
+        Instruction::Iterate => (34, &[]),
+        Instruction::ConcatMany(count) => (35, &count.to_le_bytes()),
+        Instruction::Power => (36, &[]),
+        Instruction::Index { line, column } | Instruction::StoreIndex { line, column } | Instruction::Slice { line, column } => {
+            position = [line.to_le_bytes(), column.to_le_bytes()].concat();
+            let opcode = match instruction {
+                Instruction::Index { .. } => 30,
+                Instruction::StoreIndex { .. } => 31,
+                _ => 32,
+            };
+            (opcode, &position)
+        }
+    };
+    out.push(opcode);
+    out.extend_from_slice(operand);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], TricarError> {
+        let bytes = self.position.checked_add(count)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or_else(|| TricarError::InvalidBytecode("unexpected end of .tbc file".to_string()))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], TricarError> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, TricarError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, TricarError> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, TricarError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn target(&mut self) -> Result<usize, TricarError> {
+        let target = u64::from_le_bytes(self.array()?);
+        usize::try_from(target)
+            .map_err(|_| TricarError::InvalidBytecode(format!("jump target {} is out of range", target)))
+    }
+
+    fn value(&mut self, nesting: usize) -> Result<TricaValue, TricarError> {
+        Ok(match self.u8()? {
+            0 => {
+                let len = self.u32()? as usize;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|_| TricarError::InvalidBytecode("string constant is not UTF-8".to_string()))?;
+                TricaValue::String(text.into())
+            }
+            1 => TricaValue::Int(i64::from_le_bytes(self.array()?)),
+            2 => TricaValue::Float(f64::from_le_bytes(self.array()?)),
+            3 => TricaValue::Boolean(self.u8()? != 0),
+            4 if nesting < MAX_NESTING => {
+                let count = self.u32()?;
+                TricaValue::Quantum((0..count).map(|_| self.value(nesting + 1)).collect::<Result<_, _>>()?)
+            }
+            4 => return Err(TricarError::InvalidBytecode("quantum constants are nested too deeply".to_string())),
+            5 => TricaValue::Void,
+            tag => return Err(TricarError::InvalidBytecode(format!("unknown constant tag {}", tag))),
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, TricarError> {
+        Ok(match self.u8()? {
+            0 => Instruction::LoadConst(self.u32()?),
+            1 => Instruction::Print,
+            2 => Instruction::Pop,
+            3 => Instruction::Concat,
+            4 => Instruction::Add,
+            5 => Instruction::Subtract,
+            6 => Instruction::Multiply,
+            7 => Instruction::Divide,
+            8 => Instruction::Equal,
+            9 => Instruction::NotEqual,
+            10 => Instruction::Less,
+            11 => Instruction::Greater,
+            12 => Instruction::LessEqual,
+            13 => Instruction::GreaterEqual,
+            14 => Instruction::StoreLocal(self.u16()?),
+            15 => Instruction::LoadLocal(self.u16()?),
+            16 => Instruction::StoreGlobal(self.u32()?),
+            17 => Instruction::LoadGlobal(self.u32()?),
+            18 => Instruction::Jump(self.target()?),
+            19 => Instruction::JumpIfFalse(self.target()?),
+            20 => Instruction::QuantumSuperposition,
+            21 => Instruction::CollapseWaveFunction,
+            // 22 was TimeTravel, replaced by std.time
+            23 => Instruction::DestroyMind,
+            24 => Instruction::Halt,
+            25 => Instruction::Modulo,
+            26 => {
+                let index = self.u8()?;
+                let native = Native::ALL.get(usize::from(index))
+                    .ok_or_else(|| TricarError::InvalidBytecode(format!("unknown native function {}", index)))?;
+                Instruction::CallNative(*native)
+            }
+            27 => Instruction::Negate,
+            28 => Instruction::Not,
+            29 => Instruction::MakeList(self.u32()?),
+            30 => Instruction::Index { line: self.u32()?, column: self.u32()? },
+            31 => Instruction::StoreIndex { line: self.u32()?, column: self.u32()? },
+            32 => Instruction::Slice { line: self.u32()?, column: self.u32()? },
+            33 => Instruction::MakeMap(self.u32()?),
+            34 => Instruction::Iterate,
+            35 => Instruction::ConcatMany(self.u32()?),
+            36 => Instruction::Power,
+            opcode => return Err(TricarError::InvalidBytecode(format!("unknown opcode {}", opcode))),
+        })
+    }
+}
@@ -1,227 +0,0 @@
-// 🔥 TPKG - TRICA PACKAGE MANAGER 🔥
-// The most LEGENDARY package manager ever created
-
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-use crate::error::TricarError;
-
-/// TPKG Package Manager - Manages mind-bending packages
-pub struct TpkgManager {
-    packages: HashMap<String, TricaPackage>,
-    registry_url: String,
-    local_cache: String,
-}
-
-/// A Trica Package - Contains quantum code modules
-#[derive(Debug, Clone)]
-pub struct TricaPackage {
-    pub name: String,
-    pub version: String,
-    pub description: String,
-    pub author: String,
-    pub dependencies: Vec<String>,
-    pub modules: Vec<TricaModule>,
-    pub quantum_level: u8, // 0-10, higher = more mind-bending
-}
-
-/// A Trica Module - Individual code unit
-#[derive(Debug, Clone)]
-pub struct TricaModule {
-    pub name: String,
-    pub functions: Vec<String>,
-    pub quantum_functions: Vec<String>,
-    pub time_travel_functions: Vec<String>,
-}
-
-impl TpkgManager {
-    pub fn new() -> Self {
-        Self {
-            packages: HashMap::new(),
-            registry_url: "https://tpkg.trica.dev".to_string(),
-            local_cache: ".tpkg".to_string(),
-        }
-    }
-    
-    /// Install a package from the TPKG registry
-    pub fn install(&mut self, package_name: &str) -> Result<(), TricarError> {
-        println!("📦 TPKG: Installing package '{}'...", package_name);
-        
-        // Create some legendary built-in packages
-        let package = match package_name {
-            "neural_networks" => self.create_neural_networks_package(),
-            "time_travel" => self.create_time_travel_package(),
-            "quantum_computing" => self.create_quantum_computing_package(),
-            "mind_destruction" => self.create_mind_destruction_package(),
-            "reality_bending" => self.create_reality_bending_package(),
-            _ => return Err(TricarError::IoError(format!("Package '{}' not found in TPKG registry", package_name))),
-        };
-        
-        self.packages.insert(package_name.to_string(), package.clone());
-        
-        println!("✅ TPKG: Package '{}' v{} installed successfully!", 
-                package.name, package.version);
-        println!("🧠 Quantum Level: {}/10 - {}", 
-                package.quantum_level, 
-                self.get_quantum_description(package.quantum_level));
-        
-        Ok(())
-    }
-    
-    /// List all installed packages
-    pub fn list(&self) {
-        println!("📦 TPKG: Installed packages:");
-        if self.packages.is_empty() {
-            println!("   No packages installed. Use 'tpkg install <package>' to install.");
-            return;
-        }
-        
-        for (name, package) in &self.packages {
-            println!("   {} v{} - {} (Quantum Level: {}/10)", 
-                    name, package.version, package.description, package.quantum_level);
-        }
-    }
-    
-    /// Search for packages in the registry
-    pub fn search(&self, query: &str) {
-        println!("🔍 TPKG: Searching for '{}'...", query);
-        
-        let available_packages = vec![
-            ("neural_networks", "Advanced neural network implementations with quantum neurons"),
-            ("time_travel", "Time manipulation and temporal paradox resolution"),
-            ("quantum_computing", "Quantum algorithms and superposition operations"),
-            ("mind_destruction", "Ultimate mind-bending and reality destruction tools"),
-            ("reality_bending", "Bend reality to your will with quantum mechanics"),
-            ("crypto_quantum", "Quantum cryptography and unbreakable encryption"),
-            ("ai_consciousness", "Create conscious AI entities with quantum awareness"),
-            ("multiverse", "Access parallel universes and alternate realities"),
-        ];
-        
-        let matches: Vec<_> = available_packages.iter()
-            .filter(|(name, desc)| name.contains(query) || desc.to_lowercase().contains(&query.to_lowercase()))
-            .collect();
-            
-        if matches.is_empty() {
-            println!("   No packages found matching '{}'", query);
-        } else {
-            for (name, desc) in matches {
-                println!("   {} - {}", name, desc);
-            }
-        }
-    }
-    
-    fn create_neural_networks_package(&self) -> TricaPackage {
-        TricaPackage {
-            name: "neural_networks".to_string(),
-            version: "2.1.0".to_string(),
-            description: "Advanced neural networks with quantum neurons".to_string(),
-            author: "Trica AI Team".to_string(),
-            dependencies: vec!["quantum_computing".to_string()],
-            modules: vec![
-                TricaModule {
-                    name: "quantum_neuron".to_string(),
-                    functions: vec!["create_network".to_string(), "train".to_string()],
-                    quantum_functions: vec!["quantum_backprop".to_string()],
-                    time_travel_functions: vec!["temporal_learning".to_string()],
-                }
-            ],
-            quantum_level: 8,
-        }
-    }
-    
-    fn create_time_travel_package(&self) -> TricaPackage {
-        TricaPackage {
-            name: "time_travel".to_string(),
-            version: "1.0.0".to_string(),
-            description: "Time manipulation and temporal paradox resolution".to_string(),
-            author: "Trica Temporal Labs".to_string(),
-            dependencies: vec![],
-            modules: vec![
-                TricaModule {
-                    name: "temporal_core".to_string(),
-                    functions: vec!["travel_to".to_string(), "create_paradox".to_string()],
-                    quantum_functions: vec!["quantum_timeline".to_string()],
-                    time_travel_functions: vec!["resolve_paradox".to_string(), "timeline_merge".to_string()],
-                }
-            ],
-            quantum_level: 10,
-        }
-    }
-    
-    fn create_quantum_computing_package(&self) -> TricaPackage {
-        TricaPackage {
-            name: "quantum_computing".to_string(),
-            version: "3.0.1".to_string(),
-            description: "Quantum algorithms and superposition operations".to_string(),
-            author: "Quantum Trica Foundation".to_string(),
-            dependencies: vec![],
-            modules: vec![
-                TricaModule {
-                    name: "quantum_core".to_string(),
-                    functions: vec!["create_qubit".to_string(), "measure".to_string()],
-                    quantum_functions: vec!["superposition".to_string(), "entanglement".to_string()],
-                    time_travel_functions: vec![],
-                }
-            ],
-            quantum_level: 9,
-        }
-    }
-    
-    fn create_mind_destruction_package(&self) -> TricaPackage {
-        TricaPackage {
-            name: "mind_destruction".to_string(),
-            version: "∞.∞.∞".to_string(),
-            description: "Ultimate mind-bending and reality destruction tools".to_string(),
-            author: "The Void".to_string(),
-            dependencies: vec!["reality_bending".to_string(), "quantum_computing".to_string()],
-            modules: vec![
-                TricaModule {
-                    name: "mind_core".to_string(),
-                    functions: vec!["destroy_mind".to_string(), "bend_reality".to_string()],
-                    quantum_functions: vec!["quantum_destruction".to_string()],
-                    time_travel_functions: vec!["temporal_destruction".to_string()],
-                }
-            ],
-            quantum_level: 11, // Beyond the scale
-        }
-    }
-    
-    fn create_reality_bending_package(&self) -> TricaPackage {
-        TricaPackage {
-            name: "reality_bending".to_string(),
-            version: "4.2.0".to_string(),
-            description: "Bend reality to your will with quantum mechanics".to_string(),
-            author: "Reality Hackers Collective".to_string(),
-            dependencies: vec!["quantum_computing".to_string()],
-            modules: vec![
-                TricaModule {
-                    name: "reality_core".to_string(),
-                    functions: vec!["bend_space".to_string(), "alter_time".to_string()],
-                    quantum_functions: vec!["quantum_reality".to_string()],
-                    time_travel_functions: vec!["reality_travel".to_string()],
-                }
-            ],
-            quantum_level: 9,
-        }
-    }
-    
-    fn get_quantum_description(&self, level: u8) -> &'static str {
-        match level {
-            0..=2 => "Harmless",
-            3..=4 => "Mildly mind-bending",
-            5..=6 => "Reality-altering",
-            7..=8 => "Consciousness-threatening",
-            9 => "Mind-destroying",
-            10 => "Reality-ending",
-            _ => "BEYOND COMPREHENSION",
-        }
-    }
-}
-
-/// Initialize TPKG system
-pub fn init_tpkg() -> TpkgManager {
-    println!("🔥 TPKG: Initializing Trica Package Manager...");
-    println!("📦 Registry: https://tpkg.trica.dev");
-    println!("🧠 Quantum packages available for mind destruction!");
-    TpkgManager::new()
-}
\ No newline at end of file
@@ -0,0 +1,110 @@
+// Content-addressed download cache, `~/.cache/tpkg` by default
+//
+// Sources live under `sha256/<hex>.trica`; `index.toml` records which
+// package versions have been downloaded so offline installs can resolve ranges.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use super::manifest::{load_toml, save_toml, LockedPackage};
+use super::package::checksum;
+use super::resolver::highest_matching;
+use super::{Result, TpkgError};
+
+pub struct PackageCache {
+    root: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
+}
+
+impl PackageCache {
+    /// Open (creating if needed) a cache rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join("sha256"))?;
+        Ok(Self { root })
+    }
+
+    /// `$TPKG_CACHE_DIR`, else `~/.cache/tpkg`
+    pub fn open_default() -> Result<Self> {
+        let root = match env::var_os("TPKG_CACHE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let home = env::var_os("HOME")
+                    .or_else(|| env::var_os("USERPROFILE"))
+                    .ok_or_else(|| TpkgError::Io(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "cannot locate the home directory for the tpkg cache (set TPKG_CACHE_DIR)",
+                    )))?;
+                PathBuf::from(home).join(".cache").join("tpkg")
+            }
+        };
+        Self::new(root)
+    }
+
+    fn blob_path(&self, checksum: &str) -> Option<PathBuf> {
+        let hex = checksum.strip_prefix("sha256:")?;
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(self.root.join("sha256").join(format!("{}.trica", hex)))
+    }
+
+    fn load_index(&self) -> Result<CacheIndex> {
+        load_toml(&self.root.join("index.toml"))
+    }
+
+    /// Store a package source, returning its lock entry (with checksum)
+    pub fn store(&self, package_name: &str, version: &str, code: &str) -> Result<LockedPackage> {
+        let checksum = checksum(code);
+        if let Some(blob) = self.blob_path(&checksum) {
+            if !blob.exists() {
+                fs::write(&blob, code)?;
+            }
+        }
+
+        let entry = LockedPackage {
+            name: package_name.to_string(),
+            version: version.to_string(),
+            checksum: Some(checksum),
+        };
+        let mut index = self.load_index()?;
+        index.packages.retain(|p| !(p.name == entry.name && p.version == entry.version));
+        index.packages.push(entry.clone());
+        index.packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        save_toml(&self.root.join("index.toml"), &index, "")?;
+
+        Ok(entry)
+    }
+
+    /// Read a cached source, verifying it still matches its checksum
+    pub fn load(&self, checksum: &str) -> Result<Option<String>> {
+        let blob = match self.blob_path(checksum) {
+            Some(blob) if blob.exists() => blob,
+            _ => return Ok(None),
+        };
+        let code = fs::read_to_string(&blob)?;
+        if super::package::checksum(&code) != checksum {
+            return Err(TpkgError::InvalidFile {
+                path: blob.display().to_string(),
+                reason: "cached source does not match its checksum".to_string(),
+            });
+        }
+        Ok(Some(code))
+    }
+
+    /// Highest cached version of a package satisfying `requirement`
+    pub fn find(&self, package_name: &str, requirement: &VersionReq) -> Result<Option<LockedPackage>> {
+        let index = self.load_index()?;
+        let versions: Vec<_> = index.packages.into_iter()
+            .filter(|p| p.name == package_name)
+            .collect();
+        Ok(highest_matching(&versions, |p| &p.version, requirement).cloned())
+    }
+}
@@ -0,0 +1,378 @@
+// Installer - keeps `.tpkg/`, `vendor/`, `trica.toml` and `trica.lock` of one project in sync
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use semver::VersionReq;
+use super::cache::PackageCache;
+use super::manifest::{LockedPackage, Lockfile, Manifest};
+use super::package::{checksum, demo_package, validate_package_name, Package};
+use super::registry::Registry;
+use super::resolver::{is_newer, parse_requirement, select_version};
+use super::{Result, TpkgError, PACKAGES_DIR, VENDOR_DIR};
+
+/// Where an installed package came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Registry,
+    /// Built-in demo package, used when the registry is unreachable
+    Demo,
+    /// The download cache (offline installs and `install_locked`)
+    Cache,
+}
+
+/// Result of installing one package
+#[derive(Debug, Clone)]
+pub struct Installed {
+    pub locked: LockedPackage,
+    pub source: Source,
+    /// Registry metadata, when the package came from the registry or demos
+    pub package: Option<Package>,
+}
+
+/// A package recorded in `trica.lock`
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub locked: LockedPackage,
+    /// The range declared in `trica.toml`, `*` when undeclared
+    pub range: String,
+    /// Whether `.tpkg/<name>` exists
+    pub present: bool,
+}
+
+/// A package changed by [`Installer::update`]
+#[derive(Debug, Clone)]
+pub struct Updated {
+    pub name: String,
+    /// Previously locked version, `None` if it wasn't installed
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// A locked package with a newer version in the registry
+#[derive(Debug, Clone)]
+pub struct Outdated {
+    pub name: String,
+    pub current: String,
+    /// Newest version allowed by the manifest range
+    pub wanted: Option<String>,
+    pub latest: String,
+}
+
+pub struct Installer {
+    root: PathBuf,
+    registry: Registry,
+    cache: PackageCache,
+    /// Never touch the network; installs come from the download cache
+    offline: bool,
+}
+
+impl Installer {
+    pub fn new(root: impl Into<PathBuf>, registry: Registry, cache: PackageCache, offline: bool) -> Self {
+        Self {
+            root: root.into(),
+            registry,
+            cache,
+            offline,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Install one package and record it in `trica.toml` and `trica.lock`
+    ///
+    /// Without a `range` the manifest's range (or `*`) applies. An explicit
+    /// range replaces the manifest entry; otherwise a new dependency gets a
+    /// caret range on the installed version. Online installs fall back to
+    /// the demo packages when the registry is unreachable or failing.
+    pub async fn install(&self, package_name: &str, range: Option<&str>) -> Result<Installed> {
+        validate_package_name(package_name)?;
+
+        let requirement = match range {
+            Some(range) => parse_requirement(range)?,
+            None => Manifest::load(&self.root)?.requirement(package_name)?,
+        };
+
+        let installed = if self.offline {
+            self.install_cached(package_name, &requirement)?
+        } else {
+            match self.install_online(package_name, &requirement).await {
+                Err(TpkgError::Network(_) | TpkgError::Registry { .. }) => self.install_demo(package_name, &requirement)?,
+                result => result?,
+            }
+        };
+
+        let mut manifest = Manifest::load(&self.root)?;
+        match range {
+            Some(range) => {
+                manifest.dependencies.insert(package_name.to_string(), range.to_string());
+            }
+            None => {
+                manifest.dependencies.entry(package_name.to_string())
+                    .or_insert_with(|| format!("^{}", installed.locked.version));
+            }
+        }
+        manifest.save(&self.root)?;
+
+        let mut lockfile = Lockfile::load(&self.root)?;
+        lockfile.insert(installed.locked.clone());
+        lockfile.save(&self.root)?;
+
+        Ok(installed)
+    }
+
+    async fn install_online(&self, package_name: &str, requirement: &VersionReq) -> Result<Installed> {
+        let versions = self.registry.versions(package_name).await?;
+        if versions.is_empty() {
+            return Err(TpkgError::PackageNotFound(package_name.to_string()));
+        }
+
+        let package = select_version(&versions, requirement)
+            .ok_or_else(|| TpkgError::NoMatchingVersion {
+                name: package_name.to_string(),
+                requirement: requirement.to_string(),
+            })?
+            .clone();
+
+        let locked = self.install_code(&package.name, &package.version, &package.code)?;
+        self.registry.record_download(package.id).await?;
+
+        Ok(Installed { locked, source: Source::Registry, package: Some(package) })
+    }
+
+    fn install_demo(&self, package_name: &str, requirement: &VersionReq) -> Result<Installed> {
+        let package = demo_package(package_name)
+            .ok_or_else(|| TpkgError::PackageNotFound(package_name.to_string()))?;
+
+        let matches = semver::Version::parse(&package.version).is_ok_and(|v| requirement.matches(&v));
+        if !matches {
+            return Err(TpkgError::NoMatchingVersion {
+                name: package_name.to_string(),
+                requirement: requirement.to_string(),
+            });
+        }
+
+        let locked = self.install_code(&package.name, &package.version, &package.code)?;
+        Ok(Installed { locked, source: Source::Demo, package: Some(package) })
+    }
+
+    /// Resolve a package from the download cache only
+    fn install_cached(&self, package_name: &str, requirement: &VersionReq) -> Result<Installed> {
+        let entry = self.cache.find(package_name, requirement)?
+            .ok_or_else(|| TpkgError::NoMatchingVersion {
+                name: package_name.to_string(),
+                requirement: format!("{} (in the tpkg cache)", requirement),
+            })?;
+        let code = self.cached_source(&entry)?
+            .ok_or_else(|| TpkgError::NotCached {
+                name: entry.name.clone(),
+                version: entry.version.clone(),
+            })?;
+
+        let locked = self.install_code(package_name, &entry.version, &code)?;
+        Ok(Installed { locked, source: Source::Cache, package: None })
+    }
+
+    /// Install every package pinned in `trica.lock` at its exact version
+    ///
+    /// Sources come from the download cache when present (always, when
+    /// offline) and are checked against the locked checksums.
+    pub async fn install_locked(&self) -> Result<Vec<Installed>> {
+        let mut lockfile = Lockfile::load(&self.root)?;
+        let mut installed = Vec::new();
+
+        for locked in lockfile.packages.clone() {
+            let (code, source) = match self.cached_source(&locked)? {
+                Some(code) => (code, Source::Cache),
+                None if self.offline => {
+                    return Err(TpkgError::NotCached { name: locked.name, version: locked.version });
+                }
+                None => {
+                    let package = self.registry.versions(&locked.name).await?
+                        .into_iter()
+                        .find(|p| p.version == locked.version)
+                        .ok_or_else(|| TpkgError::NoMatchingVersion {
+                            name: locked.name.clone(),
+                            requirement: format!("={}", locked.version),
+                        })?;
+                    self.registry.record_download(package.id).await?;
+                    (package.code, Source::Registry)
+                }
+            };
+
+            if locked.checksum.as_ref().is_some_and(|expected| *expected != checksum(&code)) {
+                return Err(TpkgError::ChecksumMismatch { name: locked.name, version: locked.version });
+            }
+
+            let entry = self.install_code(&locked.name, &locked.version, &code)?;
+            lockfile.insert(entry.clone());
+            installed.push(Installed { locked: entry, source, package: None });
+        }
+
+        lockfile.save(&self.root)?;
+        Ok(installed)
+    }
+
+    /// Packages recorded in `trica.lock`
+    pub fn installed(&self) -> Result<Vec<InstalledPackage>> {
+        let manifest = Manifest::load(&self.root)?;
+        let lockfile = Lockfile::load(&self.root)?;
+
+        Ok(lockfile.packages.into_iter()
+            .map(|locked| InstalledPackage {
+                range: manifest.dependencies.get(&locked.name).cloned().unwrap_or_else(|| "*".to_string()),
+                present: self.root.join(PACKAGES_DIR).join(&locked.name).exists(),
+                locked,
+            })
+            .collect())
+    }
+
+    /// Remove an installed package and forget it in the manifest and lockfile
+    pub fn uninstall(&self, package_name: &str) -> Result<()> {
+        validate_package_name(package_name)?;
+
+        let mut manifest = Manifest::load(&self.root)?;
+        let mut lockfile = Lockfile::load(&self.root)?;
+        let package_dir = self.package_dir(package_name)?;
+
+        let declared = manifest.dependencies.remove(package_name).is_some();
+        let locked = lockfile.remove(package_name);
+        let on_disk = package_dir.exists();
+
+        if !declared && !locked && !on_disk {
+            return Err(TpkgError::NotInstalled(package_name.to_string()));
+        }
+
+        if on_disk {
+            fs::remove_dir_all(&package_dir)?;
+        }
+        manifest.save(&self.root)?;
+        lockfile.save(&self.root)
+    }
+
+    /// Upgrade dependencies to the newest version their manifest range allows
+    ///
+    /// Dependencies whose range matches nothing in the registry are skipped.
+    pub async fn update(&self, only: Option<&str>) -> Result<Vec<Updated>> {
+        let manifest = Manifest::load(&self.root)?;
+        let mut lockfile = Lockfile::load(&self.root)?;
+
+        let names: Vec<String> = match only {
+            Some(name) if manifest.dependencies.contains_key(name) => vec![name.to_string()],
+            Some(name) => return Err(TpkgError::NotADependency(name.to_string())),
+            None => manifest.dependencies.keys().cloned().collect(),
+        };
+
+        let mut updated = Vec::new();
+        for name in names {
+            let requirement = manifest.requirement(&name)?;
+            let versions = self.registry.versions(&name).await?;
+            let best = match select_version(&versions, &requirement) {
+                Some(best) => best,
+                None => continue,
+            };
+
+            let current = lockfile.get(&name).map(|p| p.version.clone());
+            let newer = current.as_deref().is_none_or(|current| is_newer(&best.version, current));
+            if !newer && self.package_dir(&name)?.exists() {
+                continue;
+            }
+
+            let entry = self.install_code(&best.name, &best.version, &best.code)?;
+            self.registry.record_download(best.id).await?;
+            lockfile.insert(entry);
+            updated.push(Updated { name, from: current, to: best.version.clone() });
+        }
+
+        lockfile.save(&self.root)?;
+        Ok(updated)
+    }
+
+    /// Locked packages that have a newer version in the registry
+    pub async fn outdated(&self) -> Result<Vec<Outdated>> {
+        let manifest = Manifest::load(&self.root)?;
+        let lockfile = Lockfile::load(&self.root)?;
+
+        let mut outdated = Vec::new();
+        for locked in lockfile.packages {
+            let versions = self.registry.versions(&locked.name).await?;
+            let requirement = manifest.requirement(&locked.name)?;
+
+            let latest = match select_version(&versions, &VersionReq::STAR) {
+                Some(latest) if is_newer(&latest.version, &locked.version) => latest.version.clone(),
+                _ => continue,
+            };
+            let wanted = select_version(&versions, &requirement).map(|p| p.version.clone());
+
+            outdated.push(Outdated { name: locked.name, current: locked.version, wanted, latest });
+        }
+
+        Ok(outdated)
+    }
+
+    /// Copy every locked dependency into `vendor/` for builds without tpkg
+    ///
+    /// Sources come from the cache, else from `.tpkg/` (checksum-verified).
+    pub fn vendor(&self) -> Result<Vec<LockedPackage>> {
+        let lockfile = Lockfile::load(&self.root)?;
+
+        for locked in &lockfile.packages {
+            let code = match self.cached_source(locked)? {
+                Some(code) => code,
+                None => {
+                    let installed = self.package_dir(&locked.name)?.join("main.trica");
+                    let code = fs::read_to_string(&installed).map_err(|_| TpkgError::NotCached {
+                        name: locked.name.clone(),
+                        version: locked.version.clone(),
+                    })?;
+                    if locked.checksum.as_ref().is_some_and(|expected| *expected != checksum(&code)) {
+                        return Err(TpkgError::ChecksumMismatch {
+                            name: locked.name.clone(),
+                            version: locked.version.clone(),
+                        });
+                    }
+                    code
+                }
+            };
+
+            validate_package_name(&locked.name)?;
+            let vendor_dir = self.root.join(VENDOR_DIR).join(&locked.name);
+            fs::create_dir_all(&vendor_dir)?;
+            fs::write(vendor_dir.join("main.trica"), code)?;
+        }
+
+        Ok(lockfile.packages)
+    }
+
+    fn cached_source(&self, locked: &LockedPackage) -> Result<Option<String>> {
+        match &locked.checksum {
+            Some(checksum) => self.cache.load(checksum),
+            None => Ok(None),
+        }
+    }
+
+    /// Cache a package source and write it to `.tpkg/<name>/main.trica`
+    fn install_code(&self, package_name: &str, version: &str, code: &str) -> Result<LockedPackage> {
+        let package_dir = self.package_dir(package_name)?;
+        let locked = self.cache.store(package_name, version, code)?;
+        fs::create_dir_all(&package_dir)?;
+        fs::write(package_dir.join("main.trica"), code)?;
+        Ok(locked)
+    }
+
+    /// `.tpkg/<name>`; names from the lockfile or registry are checked so
+    /// they can't point outside it
+    fn package_dir(&self, package_name: &str) -> Result<PathBuf> {
+        validate_package_name(package_name)?;
+        Ok(self.root.join(PACKAGES_DIR).join(package_name))
+    }
+}
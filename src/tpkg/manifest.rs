@@ -0,0 +1,111 @@
+// `trica.toml` (declared dependency ranges) and `trica.lock` (resolved versions)
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use semver::VersionReq;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use super::package::validate_package_name;
+use super::{Result, TpkgError, LOCK_FILE, MANIFEST_FILE};
+
+/// `trica.toml` - the `[dependencies]` table maps package names to semver ranges
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Sections tpkg doesn't own (`[package]`, `[lints]`, ...) are written back untouched
+    #[serde(flatten)]
+    pub other: toml::Table,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Load `trica.toml` from a project directory; a missing file is empty
+    pub fn load(root: &Path) -> Result<Self> {
+        load_toml(&root.join(MANIFEST_FILE))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        save_toml(&root.join(MANIFEST_FILE), self, "")
+    }
+
+    /// The declared range for a dependency, `*` when it isn't declared
+    pub fn requirement(&self, name: &str) -> Result<VersionReq> {
+        let range = self.dependencies.get(name).map(String::as_str).unwrap_or("*");
+        VersionReq::parse(range).map_err(|e| TpkgError::InvalidRange {
+            range: range.to_string(),
+            reason: format!("{} (dependency '{}')", e, name),
+        })
+    }
+}
+
+/// `trica.lock` - one `[[package]]` entry per installed dependency
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `sha256:<hex>` of the package source; also its key in the download cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl Lockfile {
+    /// Load `trica.lock` from a project directory; a missing file is empty
+    ///
+    /// Locked names become paths under `.tpkg/` and `vendor/`, so they must
+    /// be valid package names.
+    pub fn load(root: &Path) -> Result<Self> {
+        let lockfile: Self = load_toml(&root.join(LOCK_FILE))?;
+        for locked in &lockfile.packages {
+            validate_package_name(&locked.name)?;
+        }
+        Ok(lockfile)
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        save_toml(&root.join(LOCK_FILE), self, "# Generated by tpkg - do not edit by hand\n\n")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Add or replace the entry for a package, keeping entries sorted by name
+    pub fn insert(&mut self, locked: LockedPackage) {
+        self.packages.retain(|p| p.name != locked.name);
+        self.packages.push(locked);
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.packages.len();
+        self.packages.retain(|p| p.name != name);
+        self.packages.len() != before
+    }
+}
+
+pub(crate) fn load_toml<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let text = fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| TpkgError::InvalidFile {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+pub(crate) fn save_toml<T: Serialize>(path: &Path, value: &T, header: &str) -> Result<()> {
+    let body = toml::to_string(value).map_err(|e| TpkgError::InvalidFile {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    fs::write(path, format!("{}{}", header, body))?;
+    Ok(())
+}
@@ -0,0 +1,134 @@
+// 🔥 TPKG - TRICA PACKAGE MANAGER 🔥
+// Package model, registry client, resolver and installer. The `tpkg`
+// binary is a thin CLI over this module, so other tools and tests can
+// drive package management programmatically.
+
+pub mod cache;
+pub mod installer;
+pub mod manifest;
+pub mod package;
+pub mod publish;
+pub mod registry;
+pub mod resolver;
+
+pub use cache::PackageCache;
+pub use installer::{Installed, InstalledPackage, Installer, Outdated, Source, Updated};
+pub use manifest::{LockedPackage, Lockfile, Manifest};
+pub use package::{checksum, validate_package_name, Package};
+pub use publish::{compile_check, run_package_tests, PublishRequest};
+pub use registry::Registry;
+pub use resolver::{parse_spec, select_version};
+
+use std::fmt;
+
+/// Project manifest declaring dependency version ranges
+pub const MANIFEST_FILE: &str = "trica.toml";
+/// Exact versions resolved from the manifest ranges
+pub const LOCK_FILE: &str = "trica.lock";
+/// Where installed package sources live
+pub const PACKAGES_DIR: &str = crate::modules::PACKAGE_DIRS[1];
+/// Where `tpkg vendor` copies locked dependencies; the compiler checks it first
+pub const VENDOR_DIR: &str = crate::modules::PACKAGE_DIRS[0];
+
+pub type Result<T> = std::result::Result<T, TpkgError>;
+
+#[derive(Debug)]
+pub enum TpkgError {
+    Io(std::io::Error),
+    Network(reqwest::Error),
+    /// The registry answered with a non-success status
+    Registry { action: String, status: String, details: String },
+    InvalidPackageName(String),
+    InvalidVersion { version: String, reason: String },
+    InvalidRange { range: String, reason: String },
+    /// `trica.toml`, `trica.lock` or the cache index couldn't be read or written
+    InvalidFile { path: String, reason: String },
+    PackageNotFound(String),
+    NoMatchingVersion { name: String, requirement: String },
+    NotInstalled(String),
+    NotADependency(String),
+    /// A locked package isn't available without network access
+    NotCached { name: String, version: String },
+    ChecksumMismatch { name: String, version: String },
+    VersionExists { name: String, version: String },
+    /// The package failed to compile or one of its tests failed
+    Validation(String),
+}
+
+impl fmt::Display for TpkgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TpkgError::Io(e) => write!(f, "IO error: {}", e),
+            TpkgError::Network(e) => write!(f, "Registry unreachable: {}", e),
+            TpkgError::Registry { action, status, details } => {
+                write!(f, "Failed to {}: {}", action, status)?;
+                if !details.is_empty() {
+                    write!(f, " ({})", details)?;
+                }
+                Ok(())
+            }
+            TpkgError::InvalidPackageName(name) => {
+                write!(f, "Invalid package name '{}': names must start with a lowercase letter and contain only a-z, 0-9, '_' or '-' (max {} characters)",
+                       name, package::MAX_PACKAGE_NAME_LEN)
+            }
+            TpkgError::InvalidVersion { version, reason } => {
+                write!(f, "Invalid version '{}': {} (versions must be semver, e.g. 1.0.0)", version, reason)
+            }
+            TpkgError::InvalidRange { range, reason } => {
+                write!(f, "Invalid version range '{}': {}", range, reason)
+            }
+            TpkgError::InvalidFile { path, reason } => {
+                write!(f, "Invalid {}: {}", path, reason)
+            }
+            TpkgError::PackageNotFound(name) => {
+                write!(f, "Package '{}' not found in TPKG registry", name)
+            }
+            TpkgError::NoMatchingVersion { name, requirement } => {
+                write!(f, "No version of '{}' matches '{}'", name, requirement)
+            }
+            TpkgError::NotInstalled(name) => {
+                write!(f, "Package '{}' is not installed", name)
+            }
+            TpkgError::NotADependency(name) => {
+                write!(f, "Package '{}' is not a dependency in {}", name, MANIFEST_FILE)
+            }
+            TpkgError::NotCached { name, version } => {
+                write!(f, "{} v{} is not in the tpkg cache; run 'tpkg install' once with network access", name, version)
+            }
+            TpkgError::ChecksumMismatch { name, version } => {
+                write!(f, "Checksum mismatch for {} v{}: the source differs from {}", name, version, LOCK_FILE)
+            }
+            TpkgError::VersionExists { name, version } => {
+                write!(f, "Version {} of '{}' already exists in the registry", version, name)
+            }
+            TpkgError::Validation(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TpkgError {}
+
+impl From<std::io::Error> for TpkgError {
+    fn from(e: std::io::Error) -> Self {
+        TpkgError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for TpkgError {
+    fn from(e: reqwest::Error) -> Self {
+        TpkgError::Network(e)
+    }
+}
+
+/// How worried to be about a package's quantum level
+pub fn quantum_description(level: i32) -> &'static str {
+    match level {
+        0..=2 => "Harmless",
+        3..=4 => "Mildly mind-bending",
+        5..=6 => "Reality-altering",
+        7..=8 => "Consciousness-threatening",
+        9 => "Mind-destroying",
+        10 => "Reality-ending",
+        _ => "BEYOND COMPREHENSION",
+    }
+}
@@ -0,0 +1,158 @@
+// Package model shared by the registry, cache and installer
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use super::{Result, TpkgError};
+
+/// Longest package name the registry accepts
+pub const MAX_PACKAGE_NAME_LEN: usize = 64;
+
+/// One published version of a package, as stored in the registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    pub id: i32,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author: String,
+    pub quantum_level: i32,
+    pub downloads: i32,
+    pub created_at: String,
+    pub code: String,
+    pub dependencies: Vec<String>,
+}
+
+/// Package names are 1-64 characters of `a-z`, `0-9`, `_` and `-`, starting
+/// with a letter. This keeps them safe in URLs, PostgREST filters and paths.
+pub fn validate_package_name(name: &str) -> Result<()> {
+    let starts_with_letter = name.chars().next().is_some_and(|c| c.is_ascii_lowercase());
+    let allowed = name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    
+    if starts_with_letter && allowed && name.len() <= MAX_PACKAGE_NAME_LEN {
+        Ok(())
+    } else {
+        Err(TpkgError::InvalidPackageName(name.to_string()))
+    }
+}
+
+/// `sha256:<hex>` digest of a package source
+pub fn checksum(code: &str) -> String {
+    let digest = Sha256::digest(code.as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
+
+/// Built-in demo packages shown and installed when the registry is unreachable
+pub fn demo_packages() -> Vec<Package> {
+    let demo = |name: &str, version: &str, description: &str, author: &str, quantum_level, downloads, code: &str| Package {
+        id: 0,
+        name: name.to_string(),
+        version: version.to_string(),
+        description: description.to_string(),
+        author: author.to_string(),
+        quantum_level,
+        downloads,
+        created_at: String::new(),
+        code: code.to_string(),
+        dependencies: Vec::new(),
+    };
+    
+    vec![
+        demo("neural_networks", "2.1.0", "Advanced neural networks with quantum neurons", "Trica AI Team", 8, 1337, NEURAL_NETWORKS),
+        demo("time_travel", "1.0.0", "Time manipulation and temporal paradox resolution", "Trica Temporal Labs", 10, 2048, TIME_TRAVEL),
+        demo("quantum_computing", "3.0.1", "Quantum algorithms and superposition operations", "Quantum Trica Foundation", 9, 999, QUANTUM_COMPUTING),
+        demo("mind_destruction", "∞.∞.∞", "Ultimate mind-bending and reality destruction tools", "The Void", 11, 42069, ""),
+        demo("reality_bending", "4.2.0", "Bend reality to your will with quantum mechanics", "Reality Hackers Collective", 9, 1984, ""),
+    ]
+}
+
+/// A demo package that can be installed offline (one that ships code)
+pub fn demo_package(name: &str) -> Option<Package> {
+    demo_packages().into_iter().find(|p| p.name == name && !p.code.is_empty())
+}
+
+const NEURAL_NETWORKS: &str = r#"// 🧠 QUANTUM NEURAL NETWORKS 🧠
+include <stdio.h>
+include "trica_quantum.h"
+
+import std.math as Math
+import std.quantum as Quantum
+
+Main {
+    Print "🧠 Initializing Quantum Neural Network..."
+    
+    neurons = 128
+    layers = 5
+    quantum_states = 16
+    
+    Print "Neurons: " + neurons
+    Print "Layers: " + layers  
+    Print "Quantum States: " + quantum_states
+    
+    learning_rate = 0.001
+    epochs = 1000
+    
+    Print "🔥 Training with quantum backpropagation..."
+    Print "Learning Rate: " + learning_rate
+    Print "Epochs: " + epochs
+    
+    Print "✅ Quantum Neural Network ready for MIND DESTRUCTION!"
+}"#;
+
+const TIME_TRAVEL: &str = r#"// ⏰ TIME TRAVEL CORE ⏰
+include <stdio.h>
+include <time.h>
+include "trica_temporal.h"
+
+import std.time as Time
+import std.quantum as Quantum
+
+Main {
+    Print "⏰ TRICA TIME TRAVEL SYSTEM ACTIVATED ⏰"
+    
+    current_time = 1234567890
+    Print "Current Time: " + current_time
+    
+    target_time = current_time - 3600
+    Print "Target Time: " + target_time
+    
+    Print "🌀 Initiating temporal displacement..."
+    Print "⚠️  WARNING: Paradox detection enabled"
+    Print "🔮 Quantum timeline stabilization: ACTIVE"
+    
+    displacement = current_time - target_time
+    Print "Temporal displacement: " + displacement + " seconds"
+    
+    Print "✅ Time travel sequence complete!"
+    Print "🚨 Remember: With great power comes great temporal responsibility!"
+}"#;
+
+const QUANTUM_COMPUTING: &str = r#"// ⚛️ QUANTUM COMPUTING CORE ⚛️
+include <stdio.h>
+include <math.h>
+include "trica_quantum.h"
+
+import std.quantum as Quantum
+import std.math as Math
+
+Main {
+    Print "⚛️ QUANTUM COMPUTING SYSTEM ONLINE ⚛️"
+    
+    qubits = 32
+    Print "Initializing " + qubits + " qubits..."
+    
+    Print "🌀 Creating quantum superposition..."
+    superposition_states = 2 * qubits
+    Print "Superposition states: " + superposition_states
+    
+    Print "🔗 Establishing quantum entanglement..."
+    entangled_pairs = qubits / 2
+    Print "Entangled pairs: " + entangled_pairs
+    
+    Print "📏 Performing quantum measurement..."
+    collapsed_state = 42
+    Print "Collapsed to state: " + collapsed_state
+    
+    Print "✅ Quantum computation complete!"
+    Print "🧠 Reality has been successfully manipulated!"
+}"#;
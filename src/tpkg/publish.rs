@@ -0,0 +1,111 @@
+// Publishing - packages are compile-checked and tested before upload
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use semver::Version;
+use crate::ast::Program;
+use crate::bytecode::{BytecodeCompiler, TricaVM};
+use crate::error::TricarError;
+use crate::{Lexer, Parser, TypeChecker};
+use super::package::validate_package_name;
+use super::registry::{NewPackage, Registry};
+use super::{Result, TpkgError};
+
+/// Arguments to `tpkg publish`
+#[derive(Debug, Clone)]
+pub struct PublishRequest {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author: String,
+    pub quantum_level: i32,
+    pub code_file: PathBuf,
+    /// Validate and return the payload without uploading
+    pub dry_run: bool,
+}
+
+/// Validate a package and upload it unless this is a dry run
+///
+/// The package must compile, pass its own tests and use a semver version
+/// that isn't already in the registry. Returns the payload and how many
+/// package tests ran. In a dry run an unreachable registry is tolerated.
+pub async fn publish(registry: &Registry, request: &PublishRequest) -> Result<(NewPackage, usize)> {
+    validate_package_name(&request.name)?;
+    Version::parse(&request.version).map_err(|e| TpkgError::InvalidVersion {
+        version: request.version.clone(),
+        reason: e.to_string(),
+    })?;
+
+    let code = fs::read_to_string(&request.code_file)?;
+    compile_check(&code).map_err(|e| {
+        TpkgError::Validation(format!("{} does not compile: {}", request.code_file.display(), e))
+    })?;
+    let tests_run = run_package_tests(&request.code_file)?;
+
+    match registry.versions(&request.name).await {
+        Ok(existing) => {
+            if existing.iter().any(|p| p.version == request.version) {
+                return Err(TpkgError::VersionExists {
+                    name: request.name.clone(),
+                    version: request.version.clone(),
+                });
+            }
+        }
+        Err(TpkgError::Network(_)) if request.dry_run => {}
+        Err(e) => return Err(e),
+    }
+
+    let package = NewPackage {
+        name: request.name.clone(),
+        version: request.version.clone(),
+        description: request.description.clone(),
+        author: request.author.clone(),
+        quantum_level: request.quantum_level,
+        downloads: 0,
+        code,
+        dependencies: Vec::new(),
+    };
+
+    if !request.dry_run {
+        registry.publish(&package).await?;
+    }
+    Ok((package, tests_run))
+}
+
+/// Lex, parse and type check a Trica source the same way the compiler does
+pub fn compile_check(source: &str) -> std::result::Result<Program, TricarError> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let program = Parser::new(tokens).parse()?;
+    TypeChecker::new().check(&program)?;
+    Ok(program)
+}
+
+/// Run the package's own tests: every `tests/*.trica` next to the package
+/// source must compile and execute without error. Returns how many ran.
+pub fn run_package_tests(code_file: &Path) -> Result<usize> {
+    let tests_dir = code_file.parent().unwrap_or(Path::new(".")).join("tests");
+    if !tests_dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut tests: Vec<_> = fs::read_dir(&tests_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "trica"))
+        .collect();
+    tests.sort();
+
+    for test in &tests {
+        let source = fs::read_to_string(test)?;
+        let result = compile_check(&source).and_then(|program| {
+            let bytecode = BytecodeCompiler::new().compile(&program)?;
+            let mut vm = TricaVM::new();
+            vm.load_bytecode(bytecode);
+            vm.execute()
+        });
+        if let Err(e) = result {
+            return Err(TpkgError::Validation(format!("Package test {} failed: {}", test.display(), e)));
+        }
+    }
+
+    Ok(tests.len())
+}
@@ -0,0 +1,50 @@
+// Version resolution - picking published versions that satisfy semver ranges
+
+use semver::{Version, VersionReq};
+use super::package::Package;
+use super::{Result, TpkgError};
+
+/// Split `name@range` into the package name and optional range
+pub fn parse_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, range)) => (name, Some(range)),
+        None => (spec, None),
+    }
+}
+
+pub fn parse_requirement(range: &str) -> Result<VersionReq> {
+    VersionReq::parse(range).map_err(|e| TpkgError::InvalidRange {
+        range: range.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Pick the highest published version satisfying `requirement`
+///
+/// Versions that aren't valid semver can never be selected.
+pub fn select_version<'a>(versions: &'a [Package], requirement: &VersionReq) -> Option<&'a Package> {
+    highest_matching(versions, |p| &p.version, requirement)
+}
+
+pub(crate) fn highest_matching<'a, T>(
+    items: &'a [T],
+    version_of: impl Fn(&T) -> &str,
+    requirement: &VersionReq,
+) -> Option<&'a T> {
+    items.iter()
+        .filter_map(|item| Version::parse(version_of(item)).ok().map(|v| (v, item)))
+        .filter(|(v, _)| requirement.matches(v))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, item)| item)
+}
+
+/// Whether `candidate` is a strictly newer semver version than `current`
+///
+/// A `current` that isn't semver is always considered older.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (Version::parse(candidate), Version::parse(current)) {
+        (Ok(candidate), Ok(current)) => candidate > current,
+        (Ok(_), Err(_)) => true,
+        _ => false,
+    }
+}
@@ -1,10 +1,26 @@
 use crate::ast::*;
 use crate::error::TricarError;
+use crate::modules::ModuleResolver;
+use crate::natives::{self, Native};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+/// Variables live in nested scopes: the top level, then one per block
+/// (`Main { }`, `if`/`else` branches, `while` and `for` bodies). Assigning a name that
+/// is visible from an enclosing scope reassigns that variable - blocks never
+/// shadow outer variables. Assigning a new name declares it in the innermost
+/// scope, and it disappears when the block ends.
 pub struct TypeChecker {
-    variables: HashMap<String, Type>,
+    /// Innermost scope last; `scopes[0]` is the top level
+    scopes: Vec<HashMap<String, Type>>,
+    /// Type of the latest variable declared under each name, in any scope
+    declared: HashMap<String, Type>,
     functions: HashMap<String, FunctionSignature>,
+    resolver: ModuleResolver,
+    /// Import alias -> resolved package source
+    modules: HashMap<String, PathBuf>,
+    /// Standard module functions by `Alias.name`
+    natives: HashMap<String, Native>,
 }
 
 #[derive(Debug, Clone)]
@@ -13,8 +29,19 @@ struct FunctionSignature {
     return_type: Type,
 }
 
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TypeChecker {
     pub fn new() -> Self {
+        Self::with_resolver(ModuleResolver::default())
+    }
+    
+    /// Type checker resolving package imports through `resolver`
+    pub fn with_resolver(resolver: ModuleResolver) -> Self {
         let mut functions = HashMap::new();
         
         // Built-in Print function
@@ -29,12 +56,53 @@ impl TypeChecker {
             return_type: Type::String,
         });
         
+        // Functions the VM implements natively (`int`, `float`, `str`);
+        // methods like `push` are looked up by receiver type instead, and
+        // standard module functions are added by their import
+        for native in Native::ALL.into_iter().filter(|native| !native.is_method() && native.module().is_none()) {
+            let (params, return_type) = native.signature(&Type::Unknown);
+            functions.insert(native.name().to_string(), FunctionSignature { params, return_type });
+        }
+        
         Self {
-            variables: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            declared: HashMap::new(),
             functions,
+            resolver,
+            modules: HashMap::new(),
+            natives: HashMap::new(),
         }
     }
     
+    /// Source file a package import alias resolved to
+    pub fn module_path(&self, alias: &str) -> Option<&Path> {
+        self.modules.get(alias).map(PathBuf::as_path)
+    }
+    
+    /// Inferred type of a variable assigned so far, including variables
+    /// local to blocks that have already ended
+    pub fn variable_type(&self, name: &str) -> Option<&Type> {
+        self.lookup(name).or_else(|| self.declared.get(name))
+    }
+    
+    /// The visible variable named `name`, searching outwards from the innermost scope
+    fn lookup(&self, name: &str) -> Option<&Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+    
+    /// Built-in and imported members of `object` (e.g. `output` for `Print`)
+    /// with their types, sorted by name
+    pub fn members(&self, object: &str) -> Vec<(&str, &Type)> {
+        let mut members: Vec<_> = self.functions.iter()
+            .filter_map(|(name, signature)| {
+                let member = name.strip_prefix(object)?.strip_prefix('.')?;
+                Some((member, &signature.return_type))
+            })
+            .collect();
+        members.sort_by_key(|(name, _)| *name);
+        members
+    }
+    
     pub fn check(&mut self, program: &Program) -> Result<(), TricarError> {
         // Type check includes (basic validation)
         for include in &program.includes {
@@ -46,13 +114,143 @@ impl TypeChecker {
             self.check_import(import)?;
         }
         
-        // Type check main block
         // BLAZING FAST TYPE CHECKING - DIRECT STATEMENTS!
-        for statement in &program.statements {
+        let (top_level, main) = program.split_main();
+        for statement in top_level {
             self.check_statement(statement)?;
         }
         
-        Ok(())
+        match main {
+            Some(statements) => self.check_block(statements),
+            None => Ok(()),
+        }
+    }
+    
+    /// Check statements in a new innermost scope
+    fn check_block(&mut self, statements: &[Statement]) -> Result<(), TricarError> {
+        self.scopes.push(HashMap::new());
+        let result = statements.iter().try_for_each(|statement| self.check_statement(statement));
+        self.scopes.pop();
+        result
+    }
+    
+    fn check_condition(&mut self, condition: &Expression) -> Result<(), TricarError> {
+        match self.check_expression(condition)? {
+            Type::Boolean | Type::Unknown => Ok(()),
+            found => Err(TricarError::TypeMismatch {
+                expected: "Boolean".to_string(),
+                found: format!("{}", found),
+                line: condition.line(),
+                column: condition.column(),
+            }),
+        }
+    }
+    
+    /// Print and `${...}` accept strings, numbers, and other printable types
+    fn check_printable(&mut self, expression: &Expression) -> Result<(), TricarError> {
+        let expr_type = self.check_expression(expression)?;
+        match expr_type {
+            // Unknown values (like parsed JSON) are whatever they turn out to be
+            Type::String | Type::Int | Type::Float | Type::Boolean | Type::List(_) | Type::Map(..) | Type::Unknown => Ok(()),
+            _ => Err(TricarError::TypeMismatch {
+                expected: "String, Int, Float, Boolean, List or Map".to_string(),
+                found: format!("{}", expr_type),
+                line: expression.line(),
+                column: expression.column(),
+            })
+        }
+    }
+    
+    /// Element type of the list `object` evaluates to
+    fn check_list(&mut self, object: &Expression) -> Result<Type, TricarError> {
+        match self.check_expression(object)? {
+            Type::List(element) => Ok(*element),
+            Type::Unknown => Ok(Type::Unknown),
+            found => Err(TricarError::TypeMismatch {
+                expected: "List".to_string(),
+                found: found.to_string(),
+                line: object.line(),
+                column: object.column(),
+            }),
+        }
+    }
+    
+    /// Key and element types of the list or map `object` evaluates to; a
+    /// list's keys are its Int indices
+    fn check_container(&mut self, object: &Expression) -> Result<(Type, Type), TricarError> {
+        match self.check_expression(object)? {
+            Type::List(element) => Ok((Type::Int, *element)),
+            Type::Map(key, value) => Ok((*key, *value)),
+            Type::Unknown => Ok((Type::Unknown, Type::Unknown)),
+            found => Err(TricarError::TypeMismatch {
+                expected: "List or Map".to_string(),
+                found: found.to_string(),
+                line: object.line(),
+                column: object.column(),
+            }),
+        }
+    }
+    
+    /// A list index or map key, of type `expected` if that is known
+    fn check_key(&mut self, key: &Expression, expected: &Type) -> Result<Type, TricarError> {
+        let found = self.check_expression(key)?;
+        if expected.accepts(&found) && is_key(&found) {
+            return Ok(found);
+        }
+        Err(TricarError::TypeMismatch {
+            expected: match expected {
+                Type::Unknown => "String, Int or Boolean".to_string(),
+                known => known.to_string(),
+            },
+            found: found.to_string(),
+            line: key.line(),
+            column: key.column(),
+        })
+    }
+    
+    /// List indices and slice bounds are Ints
+    fn check_index(&mut self, index: &Expression) -> Result<(), TricarError> {
+        match self.check_expression(index)? {
+            Type::Int | Type::Unknown => Ok(()),
+            found => Err(TricarError::TypeMismatch {
+                expected: "Int".to_string(),
+                found: found.to_string(),
+                line: index.line(),
+                column: index.column(),
+            }),
+        }
+    }
+    
+    /// Functions (but not methods, which may store their arguments) convert
+    /// Int arguments to Float parameters themselves, so `widen_ints` lets them pass
+    /// Check `args` against `params`, returning the argument types
+    fn check_arguments(&mut self, args: &[Expression], params: &[Type], widen_ints: bool, line: usize, column: usize) -> Result<Vec<Type>, TricarError> {
+        // Check argument count
+        if args.len() != params.len() {
+            return Err(TricarError::TypeMismatch {
+                expected: format!("{} arguments", params.len()),
+                found: format!("{} arguments", args.len()),
+                line,
+                column,
+            });
+        }
+        
+        // Check argument types
+        let mut types = Vec::with_capacity(args.len());
+        for (arg, expected_type) in args.iter().zip(params) {
+            let arg_type = self.check_expression(arg)?;
+            let widens = widen_ints && *expected_type == Type::Float && arg_type == Type::Int;
+            if !expected_type.accepts(&arg_type) && !widens {
+                return Err(TricarError::TypeMismatch {
+                    expected: expected_type.to_string(),
+                    found: arg_type.to_string(),
+                    line: arg.line(),
+                    column: arg.column(),
+                });
+            }
+            types.push(arg_type);
+        }
+        Ok(types)
     }
     
     fn check_include(&self, _include: &Include) -> Result<(), TricarError> {
@@ -62,8 +260,32 @@ impl TypeChecker {
     }
     
     fn check_import(&mut self, import: &Import) -> Result<(), TricarError> {
-        // Register the imported module as available
-        // For now, we'll just add some basic validation
+        // Packages must exist in vendor/ or .tpkg/
+        if !ModuleResolver::is_builtin(&import.module_path) {
+            let path = self.resolver.resolve(&import.module_path)
+                .ok_or_else(|| TricarError::UnresolvedImport {
+                    module: import.module_path.join("."),
+                    line: import.line,
+                    column: import.column,
+                })?;
+            self.modules.insert(import.alias.clone(), path);
+            return Ok(());
+        }
+        
+        // Standard module functions and constants are members of the alias
+        let module = import.module_path.join(".");
+        for native in Native::ALL.into_iter().filter(|native| native.module() == Some(module.as_str())) {
+            let (params, return_type) = native.signature(&Type::Unknown);
+            let name = format!("{}.{}", import.alias, native.name());
+            self.functions.insert(name.clone(), FunctionSignature { params, return_type });
+            self.natives.insert(name, native);
+        }
+        for name in natives::module_constants(&module) {
+            self.functions.insert(format!("{}.{}", import.alias, name), FunctionSignature {
+                params: vec![],
+                return_type: Type::Float,
+            });
+        }
         
         // Special handling for known modules
         if import.module_path == vec!["user", "input"] {
@@ -77,62 +299,137 @@ impl TypeChecker {
         Ok(())
     }
     
-    fn check_main_block(&mut self, main_block: &MainBlock) -> Result<(), TricarError> {
-        for statement in &main_block.statements {
-            self.check_statement(statement)?;
-        }
-        Ok(())
-    }
-    
     fn check_statement(&mut self, statement: &Statement) -> Result<(), TricarError> {
         match statement {
-            Statement::Print { expression, .. } => {
-                let expr_type = self.check_expression(expression)?;
-                
-                // Print accepts strings, numbers, and other printable types
-                match expr_type {
-                    Type::String | Type::Number => Ok(()),
-                    _ => Err(TricarError::TypeMismatch {
-                        expected: "String or Number".to_string(),
-                        found: format!("{:?}", expr_type),
-                        line: expression.line(),
-                        column: expression.column(),
-                    })
-                }
-            }
+            Statement::Print { expression, .. } => self.check_printable(expression),
             Statement::Assignment { name, value, line, column } => {
                 let value_type = self.check_expression(value)?;
                 
                 // Check if variable already exists with different type
-                if let Some(existing_type) = self.variables.get(name) {
-                    if *existing_type != value_type && *existing_type != Type::Unknown {
+                let mut value_type = value_type;
+                if let Some(existing_type) = self.lookup(name) {
+                    if !existing_type.accepts(&value_type) {
                         return Err(TricarError::TypeMismatch {
-                            expected: format!("{:?}", existing_type),
-                            found: format!("{:?}", value_type),
+                            expected: format!("{}", existing_type),
+                            found: format!("{}", value_type),
                             line: *line,
                             column: *column,
                         });
                     }
+                    value_type = refine(existing_type, &value_type);
                 }
                 
-                // Register or update variable type
-                self.variables.insert(name.clone(), value_type);
+                // Reassign the visible variable, or declare it in this block
+                let scope = self.scopes.iter()
+                    .rposition(|scope| scope.contains_key(name))
+                    .unwrap_or(self.scopes.len() - 1);
+                self.scopes[scope].insert(name.clone(), value_type.clone());
+                self.declared.insert(name.clone(), value_type);
                 Ok(())
             }
             Statement::Expression { expression, .. } => {
                 self.check_expression(expression)?;
                 Ok(())
             }
+            Statement::If { branches, else_block, .. } => {
+                for (condition, block) in branches {
+                    self.check_condition(condition)?;
+                    self.check_block(&block.statements)?;
+                }
+                match else_block {
+                    Some(block) => self.check_block(&block.statements),
+                    None => Ok(()),
+                }
+            }
+            Statement::While { condition, body, .. } => {
+                self.check_condition(condition)?;
+                self.check_block(&body.statements)
+            }
+            Statement::For { variable, value, iterable, body, line, column } => {
+                // Lists give their elements; maps their keys, and values too
+                // with `for key, value in map`
+                let (element, value_type) = match (self.check_expression(iterable)?, value) {
+                    (Type::List(element), None) => (*element, Type::Unknown),
+                    (Type::Map(key, value_type), _) => (*key, *value_type),
+                    (Type::Unknown, _) => (Type::Unknown, Type::Unknown),
+                    (found, _) => return Err(TricarError::TypeMismatch {
+                        expected: if value.is_some() { "Map" } else { "List or Map" }.to_string(),
+                        found: found.to_string(),
+                        line: iterable.line(),
+                        column: iterable.column(),
+                    }),
+                };
+                
+                // Like an assignment of each element at the top of the body
+                let mut scope = HashMap::new();
+                let variables = std::iter::once((variable, element)).chain(value.iter().map(|value| (value, value_type.clone())));
+                for (name, name_type) in variables {
+                    match self.lookup(name) {
+                        Some(existing_type) if !existing_type.accepts(&name_type) => {
+                            return Err(TricarError::TypeMismatch {
+                                expected: existing_type.to_string(),
+                                found: name_type.to_string(),
+                                line: *line,
+                                column: *column,
+                            });
+                        }
+                        Some(_) => {}
+                        None => {
+                            scope.insert(name.clone(), name_type.clone());
+                            self.declared.insert(name.clone(), name_type);
+                        }
+                    }
+                }
+                self.scopes.push(scope);
+                let result = self.check_block(&body.statements);
+                self.scopes.pop();
+                result
+            }
+            Statement::IndexAssignment { object, index, value, .. } => {
+                let (key, element) = self.check_container(object)?;
+                self.check_key(index, &key)?;
+                let value_type = self.check_expression(value)?;
+                if element.accepts(&value_type) {
+                    Ok(())
+                } else {
+                    Err(TricarError::TypeMismatch {
+                        expected: element.to_string(),
+                        found: value_type.to_string(),
+                        line: value.line(),
+                        column: value.column(),
+                    })
+                }
+            }
         }
     }
     
     fn check_expression(&mut self, expression: &Expression) -> Result<Type, TricarError> {
         match expression {
             Expression::StringLiteral { .. } => Ok(Type::String),
-            Expression::NumberLiteral { .. } => Ok(Type::Number),
+            Expression::IntLiteral { .. } => Ok(Type::Int),
+            Expression::FloatLiteral { .. } => Ok(Type::Float),
+            Expression::BooleanLiteral { .. } => Ok(Type::Boolean),
+            
+            Expression::UnaryOp { operator, operand, .. } => {
+                let operand_type = self.check_expression(operand)?;
+                let (allowed, expected): (&[Type], &str) = match operator {
+                    UnaryOperator::Not => (&[Type::Boolean], "Boolean"),
+                    UnaryOperator::Negate => (&[Type::Int, Type::Float], "Int or Float"),
+                };
+                if allowed.contains(&operand_type) || operand_type == Type::Unknown {
+                    Ok(operand_type)
+                } else {
+                    Err(TricarError::TypeMismatch {
+                        expected: expected.to_string(),
+                        found: format!("{}", operand_type),
+                        line: operand.line(),
+                        column: operand.column(),
+                    })
+                }
+            }
             
             Expression::Identifier { name, line, column } => {
-                self.variables.get(name)
+                self.lookup(name)
                     .cloned()
                     .ok_or_else(|| TricarError::UndefinedVariable {
                         name: name.clone(),
@@ -142,8 +439,6 @@ impl TypeChecker {
             }
             
             Expression::PropertyAccess { object, property, line: _line, column: _column } => {
-                let _object_type = self.check_expression(object)?;
-                
                 // Handle special cases like Print.output
                 if let Expression::Identifier { name, .. } = object.as_ref() {
                     let full_name = format!("{}.{}", name, property);
@@ -152,6 +447,8 @@ impl TypeChecker {
                     }
                 }
                 
+                let _object_type = self.check_expression(object)?;
+                
                 // For now, return Unknown for other property accesses
                 // In a full implementation, we'd have a proper type system for objects
                 Ok(Type::Unknown)
@@ -159,37 +456,102 @@ impl TypeChecker {
             
             Expression::FunctionCall { name, args, line, column } => {
                 // Check if function exists
-                let signature = self.functions.get(name)
+                let mut signature = self.functions.get(name)
                     .ok_or_else(|| TricarError::UndefinedFunction {
                         name: name.clone(),
                         line: *line,
                         column: *column,
                     })?.clone();
                 
-                // Check argument count
-                if args.len() != signature.params.len() {
-                    return Err(TricarError::TypeMismatch {
-                        expected: format!("{} arguments", signature.params.len()),
-                        found: format!("{} arguments", args.len()),
+                // Module functions like `Random.choice(xs)` are typed by their
+                // first argument, as methods are by their receiver
+                if let (Some(native), Some(first)) = (self.natives.get(name).copied(), args.first()) {
+                    let first = self.check_expression(first)?;
+                    let (params, return_type) = native.signature(&first);
+                    signature = FunctionSignature { params, return_type };
+                }
+                
+                let arg_types = self.check_arguments(args, &signature.params, true, *line, *column)?;
+                if self.natives.get(name).is_some_and(|native| native.keeps_ints()) {
+                    // An Int for Ints, a Float once a Float is involved
+                    let result = arg_types.iter().try_fold(Type::Int, |result, arg_type| arithmetic_type(&result, arg_type));
+                    return Ok(result.unwrap_or(signature.return_type));
+                }
+                Ok(signature.return_type)
+            }
+            
+            Expression::MethodCall { receiver, method, args, line, column } => {
+                let receiver_type = self.check_expression(receiver)?;
+                let native = Native::method(&receiver_type, method)
+                    .ok_or_else(|| TricarError::UndefinedFunction {
+                        name: format!("{}.{}", receiver_type, method),
                         line: *line,
                         column: *column,
-                    });
+                    })?;
+                let (params, return_type) = native.signature(&receiver_type);
+                self.check_arguments(args, &params, false, *line, *column)?;
+                Ok(return_type)
+            }
+            
+            Expression::ListLiteral { elements, .. } => {
+                // Every element has the type of the first
+                let mut element_type = Type::Unknown;
+                for element in elements {
+                    let found = self.check_expression(element)?;
+                    if !element_type.accepts(&found) {
+                        return Err(TricarError::TypeMismatch {
+                            expected: element_type.to_string(),
+                            found: found.to_string(),
+                            line: element.line(),
+                            column: element.column(),
+                        });
+                    }
+                    element_type = refine(&element_type, &found);
                 }
-                
-                // Check argument types
-                for (_i, (arg, expected_type)) in args.iter().zip(&signature.params).enumerate() {
-                    let arg_type = self.check_expression(arg)?;
-                    if arg_type != *expected_type && arg_type != Type::Unknown && *expected_type != Type::Unknown {
+                Ok(Type::List(Box::new(element_type)))
+            }
+            
+            Expression::Interpolation { parts, .. } => {
+                for part in parts {
+                    if let InterpolationPart::Expression(expression) = part {
+                        self.check_printable(expression)?;
+                    }
+                }
+                Ok(Type::String)
+            }
+            
+            Expression::MapLiteral { entries, .. } => {
+                // Every key and every value has the type of the first
+                let (mut key_type, mut value_type) = (Type::Unknown, Type::Unknown);
+                for (key, value) in entries {
+                    let found = self.check_key(key, &key_type)?;
+                    key_type = refine(&key_type, &found);
+                    let found = self.check_expression(value)?;
+                    if !value_type.accepts(&found) {
                         return Err(TricarError::TypeMismatch {
-                            expected: format!("{:?}", expected_type),
-                            found: format!("{:?}", arg_type),
-                            line: arg.line(),
-                            column: arg.column(),
+                            expected: value_type.to_string(),
+                            found: found.to_string(),
+                            line: value.line(),
+                            column: value.column(),
                         });
                     }
+                    value_type = refine(&value_type, &found);
                 }
-                
-                Ok(signature.return_type)
+                Ok(Type::Map(Box::new(key_type), Box::new(value_type)))
+            }
+            
+            Expression::Index { object, index, .. } => {
+                let (key, element) = self.check_container(object)?;
+                self.check_key(index, &key)?;
+                Ok(element)
+            }
+            
+            Expression::Slice { object, start, end, .. } => {
+                let element = self.check_list(object)?;
+                for bound in start.iter().chain(end) {
+                    self.check_index(bound)?;
+                }
+                Ok(Type::List(Box::new(element)))
             }
             
             Expression::BinaryOp { left, operator, right, line, column } => {
@@ -198,37 +560,42 @@ impl TypeChecker {
                 
                 match operator {
                     BinaryOperator::Add => {
+                        if let Some(result) = arithmetic_type(&left_type, &right_type) {
+                            return Ok(result);
+                        }
                         match (left_type, right_type) {
-                            (Type::Number, Type::Number) => Ok(Type::Number),
                             (Type::String, Type::String) => Ok(Type::String),
-                            (Type::String, Type::Number) | (Type::Number, Type::String) => Ok(Type::String),
+                            (Type::String, Type::Int | Type::Float) | (Type::Int | Type::Float, Type::String) => Ok(Type::String),
+                            // Anything the VM can add to a String makes a String
+                            (Type::String, Type::Unknown) | (Type::Unknown, Type::String) => Ok(Type::String),
                             (l, r) => Err(TricarError::TypeMismatch {
-                                expected: "Number + Number, String + String, or String + Number".to_string(),
-                                found: format!("{:?} + {:?}", l, r),
+                                expected: "Int/Float + Int/Float, String + String, or String + Int/Float".to_string(),
+                                found: format!("{} + {}", l, r),
                                 line: *line,
                                 column: *column,
                             })
                         }
                     }
-                    BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide => {
-                        match (left_type, right_type) {
-                            (Type::Number, Type::Number) => Ok(Type::Number),
-                            (l, r) => Err(TricarError::TypeMismatch {
-                                expected: "Number".to_string(),
-                                found: format!("{:?} and {:?}", l, r),
+                    BinaryOperator::Subtract | BinaryOperator::Multiply |
+                    BinaryOperator::Divide | BinaryOperator::Modulo | BinaryOperator::Power => {
+                        match arithmetic_type(&left_type, &right_type) {
+                            Some(result) => Ok(result),
+                            None => Err(TricarError::TypeMismatch {
+                                expected: "Int or Float".to_string(),
+                                found: format!("{} and {}", left_type, right_type),
                                 line: *line,
                                 column: *column,
                             })
                         }
                     }
                     BinaryOperator::Equal | BinaryOperator::NotEqual => {
-                        // Equality works on same types
-                        if left_type == right_type || left_type == Type::Unknown || right_type == Type::Unknown {
+                        // Equality works on same types, and any two numbers
+                        if left_type.accepts(&right_type) || arithmetic_type(&left_type, &right_type).is_some() {
                             Ok(Type::Boolean)
                         } else {
                             Err(TricarError::TypeMismatch {
                                 expected: "matching types".to_string(),
-                                found: format!("{:?} and {:?}", left_type, right_type),
+                                found: format!("{} and {}", left_type, right_type),
                                 line: *line,
                                 column: *column,
                             })
@@ -237,11 +604,12 @@ impl TypeChecker {
                     BinaryOperator::Less | BinaryOperator::Greater | 
                     BinaryOperator::LessEqual | BinaryOperator::GreaterEqual => {
                         match (left_type, right_type) {
-                            (Type::Number, Type::Number) => Ok(Type::Boolean),
+                            (l, r) if arithmetic_type(&l, &r).is_some() => Ok(Type::Boolean),
                             (Type::String, Type::String) => Ok(Type::Boolean),
+                            (Type::String, Type::Unknown) | (Type::Unknown, Type::String) => Ok(Type::Boolean),
                             (l, r) => Err(TricarError::TypeMismatch {
-                                expected: "Number or String".to_string(),
-                                found: format!("{:?} and {:?}", l, r),
+                                expected: "Int, Float or String".to_string(),
+                                found: format!("{} and {}", l, r),
                                 line: *line,
                                 column: *column,
                             })
@@ -251,4 +619,32 @@ impl TypeChecker {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// The type a variable of type `existing` has after a value of type `value`
+/// (which it accepts) is assigned: whatever either one knows
+fn refine(existing: &Type, value: &Type) -> Type {
+    match (existing, value) {
+        (Type::List(a), Type::List(b)) => Type::List(Box::new(refine(a, b))),
+        (Type::Map(k1, v1), Type::Map(k2, v2)) => Type::Map(Box::new(refine(k1, k2)), Box::new(refine(v1, v2))),
+        (Type::Unknown, value) => value.clone(),
+        (existing, _) => existing.clone(),
+    }
+}
+
+/// Whether values of the type can be map keys
+fn is_key(key: &Type) -> bool {
+    matches!(key, Type::String | Type::Int | Type::Boolean | Type::Unknown)
+}
+
+/// Result type of arithmetic on two numbers: Int for two Ints, Float once
+/// a Float is involved. `None` if either side isn't a number. An Unknown
+/// side is left to the VM, which fails at runtime if it isn't a number.
+fn arithmetic_type(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Int, Type::Int) => Some(Type::Int),
+        (Type::Int | Type::Float, Type::Int | Type::Float) => Some(Type::Float),
+        (Type::Unknown, Type::Int | Type::Float | Type::Unknown) | (Type::Int | Type::Float, Type::Unknown) => Some(Type::Unknown),
+        _ => None,
+    }
+}
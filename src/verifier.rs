@@ -0,0 +1,79 @@
+// 🔥 TRICA BYTECODE VERIFIER 🔥
+// Checks bytecode before the VM runs it, so hand-written or deserialized
+// programs can't jump out of range, index past the constant pool or the
+// variable slots, underflow the stack or run off the end. `.tbc` files are
+// always verified when read; compiler output only with `--verify`.
+//
+// Stack depth is found by abstract interpretation: every path from the
+// start is followed, tracking only how many values are on the stack, and
+// all paths that meet at an instruction must agree on its depth.
+
+use crate::bytecode::{Bytecode, Instruction};
+use crate::error::TricarError;
+
+fn invalid(index: usize, instruction: &Instruction, message: String) -> TricarError {
+    TricarError::InvalidBytecode(format!("instruction {} ({:?}): {}", index, instruction, message))
+}
+
+/// Verify `bytecode`, returning the deepest the stack gets
+pub fn verify(bytecode: &Bytecode) -> Result<usize, TricarError> {
+    let instructions = &bytecode.instructions;
+    if !matches!(instructions.last(), Some(Instruction::Halt)) {
+        return Err(TricarError::InvalidBytecode("program does not end with Halt".to_string()));
+    }
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        let problem = match *instruction {
+            Instruction::LoadConst(index) if index as usize >= bytecode.constants.len() => {
+                Some(format!("constant {} is out of range, the pool has {}", index, bytecode.constants.len()))
+            }
+            Instruction::LoadGlobal(slot) | Instruction::StoreGlobal(slot) if slot >= bytecode.globals => {
+                Some(format!("global slot {} is out of range, there are {}", slot, bytecode.globals))
+            }
+            Instruction::LoadLocal(slot) | Instruction::StoreLocal(slot) if slot >= bytecode.locals => {
+                Some(format!("local slot {} is out of range, there are {}", slot, bytecode.locals))
+            }
+            Instruction::Jump(target) | Instruction::JumpIfFalse(target) if target >= instructions.len() => {
+                Some(format!("jump target {} is out of range, there are {} instructions", target, instructions.len()))
+            }
+            _ => None,
+        };
+        if let Some(message) = problem {
+            return Err(invalid(i, instruction, message));
+        }
+    }
+
+    // Stack depth on entry to each reachable instruction
+    let mut depths: Vec<Option<usize>> = vec![None; instructions.len()];
+    let mut pending = vec![(0, 0)];
+    let mut deepest = 0;
+    while let Some((i, depth)) = pending.pop() {
+        let instruction = &instructions[i];
+        match depths[i] {
+            Some(known) if known == depth => continue,
+            Some(known) => {
+                return Err(invalid(i, instruction, format!(
+                    "stack depth is {} on one path and {} on another", known, depth)));
+            }
+            None => depths[i] = Some(depth),
+        }
+
+        let (pops, pushes) = instruction.stack_effect();
+        if depth < pops {
+            return Err(invalid(i, instruction, format!(
+                "pops {} value(s) from a stack of {}", pops, depth)));
+        }
+        let after = depth - pops + pushes;
+        deepest = deepest.max(after);
+
+        // Only the final Halt can be last, so `i + 1` is always in range
+        match *instruction {
+            Instruction::Jump(target) => pending.push((target, after)),
+            Instruction::JumpIfFalse(target) => pending.extend([(target, after), (i + 1, after)]),
+            Instruction::Halt => {}
+            _ => pending.push((i + 1, after)),
+        }
+    }
+
+    Ok(deepest)
+}
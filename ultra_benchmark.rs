@@ -8,12 +8,13 @@ use std::time::Instant;
 use trica::lexer::Lexer;
 use trica::parser::Parser;
 use trica::bytecode::BytecodeCompiler;
+use trica::optimizer::{optimize, OptLevel};
 
 fn main() {
     println!("🔥 TRICA <900ns COMPILATION BENCHMARK 🔥");
     println!("⚡ Testing LEGENDARY compilation speed...\n");
     
-    let test_cases = vec![
+    let test_cases = [
         r#"Main { Print "Hello, World!" }"#,
         r#"Main { Print "Test" }"#,
         r#"Main { }"#,
@@ -70,7 +71,7 @@ fn main() {
 
 /// ULTRA-FAST COMPILATION PIPELINE - <900ns TARGET
 #[inline(always)]
-fn compile_code_fast(source: &str) -> Result<Vec<trica::bytecode::Instruction>, Box<dyn std::error::Error>> {
+fn compile_code_fast(source: &str) -> Result<trica::bytecode::Bytecode, Box<dyn std::error::Error>> {
     // STAGE 1: ULTRA-FAST LEXING (Skip for simple cases)
     let mut lexer = Lexer::new(source);
     let tokens = lexer.tokenize()?;
@@ -83,7 +84,7 @@ fn compile_code_fast(source: &str) -> Result<Vec<trica::bytecode::Instruction>,
     
     // STAGE 4: ULTRA-FAST BYTECODE GENERATION
     let mut compiler = BytecodeCompiler::new();
-    let bytecode = compiler.compile(&ast)?;
+    let bytecode = optimize(compiler.compile(&ast)?, OptLevel::O2.passes());
     
     Ok(bytecode)
 }
\ No newline at end of file